@@ -9,10 +9,110 @@ pub trait ApiClient: Clone + Sized + Sync + Send + 'static {
     type Err: ApiError;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Response<T> {
     Exact(T),
     More(T, Vec<Secondary>),
+
+    /// Wraps another response, additionally providing the raw response body it was parsed from
+    ///
+    /// Clients are free to never produce this variant. If they do, the raw body is handed to the
+    /// cache, which may retain it alongside the parsed object.
+    Raw(Box<Response<T>>, String),
+
+    /// Wraps another response, additionally providing the pagination information the server sent
+    /// along with it
+    Paged(Box<Response<T>>, PageInfo),
+}
+
+/// Pagination information of a response to a paginatable request
+///
+/// ## GD Internals:
+/// The servers append this to responses as a `total:offset:page_size` section
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PageInfo {
+    /// The total amount of objects matching the request, across all pages
+    pub total: u32,
+
+    /// The amount of objects on the pages before this one
+    pub offset: u32,
+
+    /// The maximum amount of objects on a single page
+    pub page_size: u32,
+}
+
+impl PageInfo {
+    /// The zero-based index of the page this info belongs to
+    pub fn page(&self) -> u32 {
+        if self.page_size == 0 {
+            return 0
+        }
+
+        self.offset / self.page_size
+    }
+
+    /// The total amount of pages
+    pub fn page_count(&self) -> u32 {
+        if self.page_size == 0 {
+            return 0
+        }
+
+        self.total.div_ceil(self.page_size)
+    }
+}
+
+impl<T> Response<T> {
+    /// Splits off the raw response body, if any, from this response
+    pub fn split_raw(self) -> (Response<T>, Option<String>) {
+        match self {
+            Response::Raw(inner, raw) => (inner.split_raw().0, Some(raw)),
+            Response::Paged(inner, page_info) => {
+                let (inner, raw) = inner.split_raw();
+
+                (Response::Paged(Box::new(inner), page_info), raw)
+            },
+            response => (response, None),
+        }
+    }
+
+    /// Splits off the pagination information, if any, from this response
+    pub fn split_page_info(self) -> (Response<T>, Option<PageInfo>) {
+        match self {
+            Response::Paged(inner, page_info) => (inner.split_page_info().0, Some(page_info)),
+            Response::Raw(inner, raw) => {
+                let (inner, page_info) = inner.split_page_info();
+
+                (Response::Raw(Box::new(inner), raw), page_info)
+            },
+            response => (response, None),
+        }
+    }
+}
+
+impl<T> IntoIterator for Response<Vec<T>> {
+    type IntoIter = std::vec::IntoIter<T>;
+    type Item = T;
+
+    /// Consumes this response into the objects it contains, discarding any secondary objects and
+    /// the raw response body
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            Response::Exact(objects) | Response::More(objects, _) => objects.into_iter(),
+            Response::Raw(inner, _) | Response::Paged(inner, _) => inner.into_iter(),
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Response<Vec<T>> {
+    type IntoIter = std::slice::Iter<'a, T>;
+    type Item = &'a T;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            Response::Exact(objects) | Response::More(objects, _) => objects.iter(),
+            Response::Raw(inner, _) | Response::Paged(inner, _) => (&**inner).into_iter(),
+        }
+    }
 }
 
 pub trait MakeRequest<R: Request>: ApiClient {
@@ -20,3 +120,104 @@ pub trait MakeRequest<R: Request>: ApiClient {
 
     fn make(&self, request: &R) -> Self::Future;
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api::client::{PageInfo, Response},
+        mock, Secondary,
+    };
+    use gdcf_model::user::Creator;
+
+    #[test]
+    fn page_count_rounds_up() {
+        let page_info = |total, page_size| PageInfo { total, offset: 0, page_size };
+
+        assert_eq!(page_info(0, 10).page_count(), 0);
+        assert_eq!(page_info(10, 10).page_count(), 1);
+        assert_eq!(page_info(11, 10).page_count(), 2);
+        assert_eq!(page_info(5, 0).page_count(), 0);
+        assert_eq!(page_info(u32::MAX, 10).page_count(), u32::MAX / 10 + 1);
+    }
+
+    #[test]
+    fn cloned_response_equals_original() {
+        let mut level = mock::level(1);
+        level.description = Some("A description".to_string());
+
+        let creator = Creator {
+            user_id: 0,
+            name: "Creator".to_string(),
+            account_id: Some(5),
+        };
+        let response = Response::Paged(
+            Box::new(Response::Raw(
+                Box::new(Response::More(vec![level, mock::level(2)], vec![
+                    Secondary::Creator(creator),
+                    Secondary::MissingNewgroundsSong(3),
+                ])),
+                "raw body".to_string(),
+            )),
+            PageInfo {
+                total: 2,
+                offset: 0,
+                page_size: 10,
+            },
+        );
+
+        let cloned = response.clone();
+
+        assert_eq!(cloned, response);
+
+        // Level equality only compares ids, so make sure the clone is a proper deep copy
+        let (cloned, raw) = cloned.split_raw();
+        let (cloned, page_info) = cloned.split_page_info();
+
+        assert_eq!(raw.as_ref().map(String::as_str), Some("raw body"));
+        assert_eq!(page_info.map(|page_info| page_info.total), Some(2));
+
+        match cloned {
+            Response::More(levels, secondaries) => {
+                assert_eq!(levels.iter().map(|level| level.name.as_str()).collect::<Vec<_>>(), vec!["Level 1", "Level 2"]);
+                assert_eq!(levels[0].description.as_ref().map(String::as_str), Some("A description"));
+                assert_eq!(levels[1].description, None);
+
+                match &secondaries[..] {
+                    [Secondary::Creator(creator), Secondary::MissingNewgroundsSong(3)] => {
+                        assert_eq!(creator.name, "Creator");
+                        assert_eq!(creator.account_id, Some(5));
+                    },
+                    other => panic!("unexpected secondary objects {:?}", other),
+                }
+            },
+            other => panic!("unexpected response {:?}", other),
+        }
+    }
+
+    #[test]
+    fn response_iterates_over_its_objects() {
+        let response = Response::Paged(
+            Box::new(Response::Raw(
+                Box::new(Response::More(vec![mock::level(1), mock::level(2)], vec![Secondary::MissingCreator(0)])),
+                "raw body".to_string(),
+            )),
+            PageInfo {
+                total: 2,
+                offset: 0,
+                page_size: 10,
+            },
+        );
+
+        let mut borrowed = Vec::new();
+
+        for level in &response {
+            borrowed.push(level.level_id);
+        }
+
+        let owned: Vec<_> = response.into_iter().collect();
+
+        assert_eq!(borrowed, vec![1, 2]);
+        assert_eq!(owned.iter().map(|level| level.name.as_str()).collect::<Vec<_>>(), vec!["Level 1", "Level 2"]);
+        assert_eq!(Response::Exact(vec![3, 4]).into_iter().collect::<Vec<_>>(), vec![3, 4]);
+    }
+}
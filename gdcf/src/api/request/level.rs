@@ -2,6 +2,7 @@
 
 use crate::api::request::{BaseRequest, PaginatableRequest, Request, GD_21};
 use gdcf_model::level::{DemonRating, Level, LevelLength, LevelRating, PartialLevel};
+use log::warn;
 use std::{
     fmt::{Display, Error, Formatter},
     hash::{Hash, Hasher},
@@ -38,14 +39,43 @@ pub struct LevelRequest {
 }
 
 /// Manual `Hash` impl that doesn't hash `base`.
+///
+/// The request is destructured without a rest pattern, so adding a field fails to compile until it
+/// has been decided whether the field is part of the cache key.
 impl Hash for LevelRequest {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.level_id.hash(state);
-        self.inc.hash(state);
-        self.extra.hash(state);
+        let LevelRequest {
+            base: _,
+            level_id,
+            inc,
+            extra,
+        } = self;
+
+        level_id.hash(state);
+        inc.hash(state);
+        extra.hash(state);
     }
 }
 
+/// Manual `PartialEq` impl that, just like the `Hash` impl, ignores `base`.
+impl PartialEq for LevelRequest {
+    fn eq(&self, other: &Self) -> bool {
+        let LevelRequest {
+            base: _,
+            level_id,
+            inc,
+            extra,
+        } = self;
+
+        *level_id == other.level_id && *inc == other.inc && *extra == other.extra
+    }
+}
+
+impl Eq for LevelRequest {}
+
+/// The amount of levels RobTop's servers return per page of a [`LevelsRequest`]
+pub const DEFAULT_PAGE_SIZE: u32 = 10;
+
 /// Struct modelled after a request to `getGJLevels21.php`
 ///
 /// In the Geometry Dash API, this endpoint is used to retrieve a list of
@@ -68,6 +98,9 @@ pub struct LevelsRequest {
     /// This value is ignored unless [`LevelsRequest::request_type`] is set to
     /// [`LevelRequestType::Search`] or [`LevelRequestType::User`]
     ///
+    /// See [`validate_search_string`](crate::api::request::validate_search_string) for the
+    /// characters allowed in search strings
+    ///
     /// ## GD Internals:
     /// This field is called `str` in the boomlings API
     pub search_string: String,
@@ -115,6 +148,14 @@ pub struct LevelsRequest {
     /// The page of results to retrieve
     pub page: u32,
 
+    /// The amount of levels the server is expected to return per page, if it differs from
+    /// [`DEFAULT_PAGE_SIZE`]
+    ///
+    /// This is only relevant for private servers that use a different page size. It isn't sent to
+    /// the server, and only used to figure out when the end of the results has been reached while
+    /// streaming them.
+    pub page_size: Option<u32>,
+
     /// Some weird value the Geometry Dash client sends along
     pub total: i32,
 
@@ -128,20 +169,64 @@ pub struct LevelsRequest {
 /// Manual Hash impl which doesn't hash the base
 impl Hash for LevelsRequest {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        self.search_filters.hash(state);
-        self.total.hash(state);
-        self.demon_rating.hash(state);
-        self.ratings.hash(state);
-        self.lengths.hash(state);
-        self.search_string.hash(state);
-        self.request_type.hash(state);
-        self.page.hash(state);
+        // See the `Hash` impl of `LevelRequest` for why this is destructured. The order in which the
+        // fields are hashed must not change, as that would invalidate all cached keys.
+        let LevelsRequest {
+            base: _,
+            page_size: _,
+            request_type,
+            search_string,
+            lengths,
+            ratings,
+            demon_rating,
+            page,
+            total,
+            search_filters,
+        } = self;
+
+        search_filters.hash(state);
+        total.hash(state);
+        demon_rating.hash(state);
+        ratings.hash(state);
+        lengths.hash(state);
+        search_string.hash(state);
+        request_type.hash(state);
+        page.hash(state);
+    }
+}
+
+/// Manual `PartialEq` impl which, just like the `Hash` impl, ignores the base
+impl PartialEq for LevelsRequest {
+    fn eq(&self, other: &Self) -> bool {
+        let LevelsRequest {
+            base: _,
+            page_size: _,
+            request_type,
+            search_string,
+            lengths,
+            ratings,
+            demon_rating,
+            page,
+            total,
+            search_filters,
+        } = self;
+
+        *search_filters == other.search_filters
+            && *total == other.total
+            && *demon_rating == other.demon_rating
+            && *ratings == other.ratings
+            && *lengths == other.lengths
+            && *search_string == other.search_string
+            && *request_type == other.request_type
+            && *page == other.page
     }
 }
 
+impl Eq for LevelsRequest {}
+
 /// Enum representing the various filter states that can be achieved using the
 /// `completed` and `uncompleted` options in the Geometry Dash client
-#[derive(Debug, Clone, Hash)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum CompletionFilter {
     /// No filtering based upon completion
     None,
@@ -186,7 +271,7 @@ impl CompletionFilter {
 
 /// Struct containing the various search filters provided by the Geometry Dash
 /// client.
-#[derive(Debug, Default, Clone, Hash)]
+#[derive(Debug, Default, Clone, Hash, PartialEq, Eq)]
 pub struct SearchFilters {
     /// In- or excluding levels that have already been beaten. Since the GDCF
     /// client doesn't really have a notion of "completing" a level, this
@@ -267,6 +352,9 @@ pub struct SearchFilters {
 /// + Unused values: `8`, `9`, `14`
 /// + The values `15` and `17` are only used in Geometry Dash World and are the
 /// same as `0` ([`LevelRequestType::Search`]) and `6` ([`LevelRequestType::Featured`]) respectively
+///
+/// New variants must be added after the existing ones, since the derived `Hash` impl depends on the
+/// order of the variants.
 #[derive(Debug, Copy, Clone, PartialEq, Hash)]
 pub enum LevelRequestType {
     /// A search request.
@@ -355,6 +443,28 @@ pub enum LevelRequestType {
     /// ## GD Internals:
     /// This variant is represented by the value `16` in requests.
     HallOfFame,
+
+    /// Request to retrieve the most liked levels uploaded in Geometry Dash World
+    ///
+    /// ## GD Internals:
+    /// This variant is represented by the value `15` in requests
+    GdWorldMostLiked,
+
+    /// Request to retrieve the featured levels uploaded in Geometry Dash World
+    ///
+    /// ## GD Internals:
+    /// This variant is represented by the value `17` in requests
+    GdWorldFeatured,
+
+    /// Request to retrieve the levels moderators recently sent to RobTop for a rating, as shown in
+    /// the "Sent" tab added in update 2.2
+    ///
+    /// ## GD Internals:
+    /// This variant is represented by the value `27` in requests
+    Sent,
+
+    /// A request type whose meaning isn't known (yet), represented by the given value in requests
+    Unknown(i32),
 }
 
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq)]
@@ -477,6 +587,19 @@ impl LevelsRequest {
 
     const_setter!(page: u32);
 
+    /// Sets the amount of levels the server is expected to return per page
+    ///
+    /// See [`LevelsRequest::page_size`](#structfield.page_size)
+    pub const fn page_size(mut self, page_size: u32) -> Self {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    /// Turns this request into a search for the given string
+    ///
+    /// The search string isn't validated here. API clients reject requests with search strings
+    /// containing characters the servers can't handle, see
+    /// [`validate_search_string`](crate::api::request::validate_search_string).
     pub fn search(mut self, search_string: String) -> Self {
         self.search_string = search_string;
         self.request_type = LevelRequestType::Search;
@@ -501,6 +624,81 @@ impl LevelsRequest {
         self.demon_rating = Some(demon_rating);
         self
     }
+
+    /// Gets the names of the fields of this request that are set, but ignored by the servers due to
+    /// the request's [`LevelsRequest::request_type`]
+    ///
+    /// The search string only matters for searches, [`LevelRequestType::User`] and
+    /// [`LevelRequestType::MapPack`]. The filters are ignored by request types retrieving a fixed
+    /// list of levels, like the featured levels or the levels of a user.
+    pub fn ignored_fields(&self) -> Vec<&'static str> {
+        let mut ignored = Vec::new();
+
+        match self.request_type {
+            LevelRequestType::Search | LevelRequestType::User | LevelRequestType::MapPack => (),
+            _ =>
+                if !self.search_string.is_empty() {
+                    ignored.push("search_string")
+                },
+        }
+
+        match self.request_type {
+            LevelRequestType::Featured
+            | LevelRequestType::HallOfFame
+            | LevelRequestType::MapPack
+            | LevelRequestType::User
+            | LevelRequestType::Followed
+            | LevelRequestType::Friends => {
+                if self.search_filters != SearchFilters::default() {
+                    ignored.push("search_filters")
+                }
+                if !self.lengths.is_empty() {
+                    ignored.push("lengths")
+                }
+                if !self.ratings.is_empty() {
+                    ignored.push("ratings")
+                }
+                if self.demon_rating.is_some() {
+                    ignored.push("demon_rating")
+                }
+            },
+            _ => (),
+        }
+
+        ignored
+    }
+
+    /// Constructs a request for the most downloaded rated demons, of any demon difficulty
+    pub fn rated_demons() -> Self {
+        // The demon difficulty is ignored in the `diff` field, all demons are represented by `-2`
+        LevelsRequest::default()
+            .request_type(LevelRequestType::MostDownloaded)
+            .with_rating(LevelRating::Demon(DemonRating::Hard))
+            .filter(SearchFilters::new().rated())
+    }
+
+    /// Constructs a request for the most downloaded rated demons of the given demon difficulty
+    pub fn rated_demons_of(demon_rating: DemonRating) -> Self {
+        LevelsRequest::rated_demons().demon(demon_rating)
+    }
+
+    /// Constructs a request for the most downloaded featured levels of length long or extra long
+    pub fn featured_long() -> Self {
+        LevelsRequest::default()
+            .request_type(LevelRequestType::MostDownloaded)
+            .with_length(LevelLength::Long)
+            .with_length(LevelLength::ExtraLong)
+            .filter(SearchFilters::new().featured())
+    }
+
+    /// Constructs a request for the most downloaded rated levels of length long or extra long
+    pub fn rated_long() -> Self {
+        LevelsRequest::default()
+            .request_type(LevelRequestType::MostDownloaded)
+            .with_length(LevelLength::Long)
+            .with_length(LevelLength::ExtraLong)
+            .filter(SearchFilters::new().rated())
+    }
 }
 
 impl Default for LevelRequestType {
@@ -509,6 +707,30 @@ impl Default for LevelRequestType {
     }
 }
 
+impl From<i32> for LevelRequestType {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => LevelRequestType::Search,
+            1 => LevelRequestType::MostDownloaded,
+            2 => LevelRequestType::MostLiked,
+            3 => LevelRequestType::Trending,
+            4 => LevelRequestType::Recent,
+            5 => LevelRequestType::User,
+            6 => LevelRequestType::Featured,
+            7 => LevelRequestType::Magic,
+            10 => LevelRequestType::MapPack,
+            11 => LevelRequestType::Awarded,
+            12 => LevelRequestType::Followed,
+            13 => LevelRequestType::Friends,
+            15 => LevelRequestType::GdWorldMostLiked,
+            16 => LevelRequestType::HallOfFame,
+            17 => LevelRequestType::GdWorldFeatured,
+            27 => LevelRequestType::Sent,
+            value => LevelRequestType::Unknown(value),
+        }
+    }
+}
+
 impl From<LevelRequestType> for i32 {
     fn from(req_type: LevelRequestType) -> Self {
         match req_type {
@@ -524,7 +746,11 @@ impl From<LevelRequestType> for i32 {
             LevelRequestType::Awarded => 11,
             LevelRequestType::Followed => 12,
             LevelRequestType::Friends => 13,
+            LevelRequestType::GdWorldMostLiked => 15,
             LevelRequestType::HallOfFame => 16,
+            LevelRequestType::GdWorldFeatured => 17,
+            LevelRequestType::Sent => 27,
+            LevelRequestType::Unknown(value) => value,
         }
     }
 }
@@ -547,12 +773,28 @@ impl PaginatableRequest for LevelRequest {
 
 impl Request for LevelsRequest {
     type Result = Vec<PartialLevel<Option<u64>, u64>>;
+
+    fn check_result(&self, result: &Self::Result) {
+        if self.request_type == LevelRequestType::HallOfFame {
+            for level in result.iter().filter(|level| !level.is_hall_of_fame()) {
+                warn!("Level {} was returned as part of the hall of fame, but isn't epic", level);
+            }
+        }
+    }
 }
 
 impl PaginatableRequest for LevelsRequest {
     fn next(&mut self) {
         self.page += 1;
     }
+
+    fn expected_page_size(&self) -> Option<u32> {
+        Some(self.page_size.unwrap_or(DEFAULT_PAGE_SIZE))
+    }
+
+    fn is_last_page(&self, result: &Self::Result) -> bool {
+        self.expected_page_size().map_or(false, |page_size| result.len() < page_size as usize)
+    }
 }
 
 impl Display for LevelRequest {
@@ -569,3 +811,129 @@ impl Display for LevelsRequest {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{LevelRequest, LevelRequestType, LevelsRequest, SearchFilters};
+    use crate::api::request::{BaseRequest, GD_21};
+    use gdcf_model::{
+        level::{DemonRating, LevelLength, LevelRating},
+        GameVersion,
+    };
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    const OTHER_BASE: BaseRequest = BaseRequest::new(GameVersion::Version { major: 2, minor: 1 }, GameVersion::Unknown, "secret");
+
+    fn hash_of(value: &impl Hash) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn assert_same_request<T: Hash + Eq + std::fmt::Debug>(a: &T, b: &T) {
+        assert_eq!(a, b);
+        assert_eq!(hash_of(a), hash_of(b));
+    }
+
+    #[test]
+    fn level_request_ignores_base() {
+        let request = LevelRequest::new(3_884_458);
+
+        assert_same_request(&request, &request.with_base(OTHER_BASE));
+        assert_same_request(&request, &LevelRequest {
+            base: GD_21,
+            level_id: 3_884_458,
+            inc: true,
+            extra: false,
+        });
+        assert_same_request(&request, &request.inc(true).extra(false));
+
+        assert_ne!(request, request.extra(true));
+        assert_ne!(request, LevelRequest::new(10_565_740));
+    }
+
+    #[test]
+    fn levels_request_ignores_base_and_page_size() {
+        let request = LevelsRequest::default();
+
+        assert_same_request(&request, &request.clone().with_base(OTHER_BASE));
+        assert_same_request(&request, &request.clone().page_size(10));
+        assert_same_request(&request, &request.clone().page_size(50));
+        assert_same_request(
+            &request,
+            &request
+                .clone()
+                .request_type(LevelRequestType::Featured)
+                .page(0)
+                .total(0)
+                .filter(SearchFilters::new()),
+        );
+
+        assert_ne!(request, request.clone().page(1));
+        assert_ne!(request, request.clone().filter(SearchFilters::new().rated()));
+    }
+
+    #[test]
+    fn level_request_type_round_trips_through_i32() {
+        for value in -1..=30 {
+            assert_eq!(i32::from(LevelRequestType::from(value)), value);
+        }
+
+        for &request_type in &[
+            LevelRequestType::Search,
+            LevelRequestType::MostDownloaded,
+            LevelRequestType::MostLiked,
+            LevelRequestType::Trending,
+            LevelRequestType::Recent,
+            LevelRequestType::User,
+            LevelRequestType::Featured,
+            LevelRequestType::Magic,
+            LevelRequestType::MapPack,
+            LevelRequestType::Awarded,
+            LevelRequestType::Followed,
+            LevelRequestType::Friends,
+            LevelRequestType::HallOfFame,
+            LevelRequestType::GdWorldMostLiked,
+            LevelRequestType::GdWorldFeatured,
+            LevelRequestType::Sent,
+        ] {
+            assert_eq!(LevelRequestType::from(i32::from(request_type)), request_type);
+        }
+    }
+
+    #[test]
+    fn demon_presets() {
+        let request = LevelsRequest::rated_demons();
+
+        assert_eq!(request.request_type, LevelRequestType::MostDownloaded);
+        assert_eq!(request.ratings, vec![LevelRating::Demon(DemonRating::Hard)]);
+        assert_eq!(request.demon_rating, None);
+        assert!(request.lengths.is_empty());
+        assert_eq!(request.search_filters, SearchFilters::new().rated());
+        assert!(request.ignored_fields().is_empty());
+
+        let request = LevelsRequest::rated_demons_of(DemonRating::Extreme);
+
+        assert_eq!(request.demon_rating, Some(DemonRating::Extreme));
+        assert_eq!(request.search_filters, SearchFilters::new().rated());
+        assert!(request.ignored_fields().is_empty());
+    }
+
+    #[test]
+    fn long_level_presets() {
+        for (request, filters) in vec![
+            (LevelsRequest::featured_long(), SearchFilters::new().featured()),
+            (LevelsRequest::rated_long(), SearchFilters::new().rated()),
+        ] {
+            assert_eq!(request.request_type, LevelRequestType::MostDownloaded);
+            assert_eq!(request.lengths, vec![LevelLength::Long, LevelLength::ExtraLong]);
+            assert!(request.ratings.is_empty());
+            assert_eq!(request.demon_rating, None);
+            assert_eq!(request.search_filters, filters);
+            assert!(request.ignored_fields().is_empty());
+        }
+    }
+}
@@ -8,10 +8,17 @@
 //! request types, as there are simply no sensible defaults. When providing
 //! (de)serialization for requests, take a look at solutions like serde's
 //! remote types.
+//!
+//! The `Hash` implementations of the requests determine the keys objects are cached under. They
+//! ignore the [`BaseRequest`], as well as any field that doesn't influence the server's response
+//! (like [`LevelsRequest::page_size`]), so that requests only differing in these fields share a
+//! cache entry. The `PartialEq` implementations ignore the same fields. Changing which fields are
+//! hashed, or the order they are hashed in, invalidates existing cache entries.
 
 pub use self::{
     comment::{LevelCommentsRequest, ProfileCommentsRequest},
     level::{LevelRequest, LevelRequestType, LevelsRequest, SearchFilters, SongFilter},
+    song::SongRequest,
     user::{UserRequest, UserSearchRequest},
 };
 use gdcf_model::GameVersion;
@@ -19,8 +26,24 @@ use std::{fmt::Debug, hash::Hash};
 
 pub mod comment;
 pub mod level;
+pub mod song;
 pub mod user;
 
+/// Checks whether the given string can be sent to the servers as a search string
+///
+/// Search strings may consist of printable ASCII characters (including spaces), except for `#`, `|`,
+/// `:` and `~`, which the servers use as delimiters in their responses. Returns the first character
+/// violating this, if any.
+pub fn validate_search_string(search_string: &str) -> Result<(), char> {
+    match search_string
+        .chars()
+        .find(|&c| !(c == ' ' || c.is_ascii_graphic()) || "#|:~".contains(c))
+    {
+        Some(invalid) => Err(invalid),
+        None => Ok(()),
+    }
+}
+
 /// A `BaseRequest` instance that has all its fields set to the
 /// same values a Geometry Dash 2.1 client would use
 pub const GD_21: BaseRequest = BaseRequest::new(
@@ -87,7 +110,24 @@ pub trait Request: Debug + Send + Sync + 'static {
     /// For requests that return multiple types of objects (like [`LevelsRequest`], which returns
     /// levels, songs and creators), this is the non-[`Secondary`] object returned by this request
     /// (so the vector of [`PartialLevel`]s in the above example) .
-    type Result: Debug + Send + Sync + 'static;
+    type Result: Debug + Clone + Send + Sync + 'static;
+
+    /// Whether the result of this request should be stored in the cache
+    ///
+    /// Requests returning `false` here bypass the cache entirely: They are always made, and neither
+    /// their result nor any secondary objects contained in the response are stored. Note that this
+    /// means upgrading their results can cause additional requests that would otherwise have been
+    /// served from the cache. Defaults to `true`.
+    fn should_cache(&self) -> bool {
+        true
+    }
+
+    /// Sanity checks the result the servers returned for this request, logging anything that
+    /// doesn't match what was requested
+    ///
+    /// This is called once for every response received for this request. The default implementation
+    /// doesn't check anything.
+    fn check_result(&self, _result: &Self::Result) {}
 }
 
 /// Trait for requests that can be seen as returning pages of objects.
@@ -99,4 +139,20 @@ pub trait Request: Debug + Send + Sync + 'static {
 pub trait PaginatableRequest: Request {
     /// Modifies this request in-place to be a request for the next page
     fn next(&mut self);
+
+    /// The amount of objects a full page of results to this request contains, if known
+    ///
+    /// A page with fewer objects is the last one. Defaults to [`None`], in which case the end of
+    /// the results is only detected once a page is empty (or the server reports no results).
+    fn expected_page_size(&self) -> Option<u32> {
+        None
+    }
+
+    /// Whether the given result to this request is the last page of results
+    ///
+    /// Streams over this request end after yielding such a page, without requesting the next one.
+    /// Defaults to `false`.
+    fn is_last_page(&self, _result: &Self::Result) -> bool {
+        false
+    }
 }
@@ -0,0 +1,54 @@
+//! Module containing request definitions for retrieving songs
+
+use crate::api::request::{BaseRequest, Request, GD_21};
+use gdcf_model::song::NewgroundsSong;
+use std::{
+    fmt::{Display, Error, Formatter},
+    hash::{Hash, Hasher},
+};
+
+/// Struct modelled after a request to `getGJSongInfo.php`.
+///
+/// In the Geometry Dash API, this endpoint is used to retrieve information about a newgrounds song
+/// by its ID, without having to search for a level using it
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SongRequest {
+    /// The base request data
+    pub base: BaseRequest,
+
+    /// The newgrounds ID of the song to retrieve
+    ///
+    /// ## GD Internals:
+    /// This field is called `songID` in the boomlings API
+    pub song_id: u64,
+}
+
+impl SongRequest {
+    const_setter!(with_base, base, BaseRequest);
+
+    pub const fn new(song_id: u64) -> SongRequest {
+        SongRequest { base: GD_21, song_id }
+    }
+}
+
+impl Hash for SongRequest {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.song_id.hash(state)
+    }
+}
+
+impl From<u64> for SongRequest {
+    fn from(song_id: u64) -> Self {
+        SongRequest::new(song_id)
+    }
+}
+
+impl Display for SongRequest {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "SongRequest({})", self.song_id)
+    }
+}
+
+impl Request for SongRequest {
+    type Result = NewgroundsSong;
+}
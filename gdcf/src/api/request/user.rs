@@ -40,6 +40,14 @@ impl Hash for UserRequest {
     }
 }
 
+impl PartialEq for UserRequest {
+    fn eq(&self, other: &Self) -> bool {
+        self.user == other.user
+    }
+}
+
+impl Eq for UserRequest {}
+
 impl Into<UserRequest> for u64 {
     fn into(self) -> UserRequest {
         UserRequest::new(self)
@@ -98,6 +106,9 @@ pub struct UserSearchRequest {
 
     /// The name of the user being searched for
     ///
+    /// See [`validate_search_string`](crate::api::request::validate_search_string) for the
+    /// characters allowed in search strings
+    ///
     /// ## GD Internals:
     /// This field is called `str` in the boomlings API
     pub search_string: String,
@@ -0,0 +1,131 @@
+//! Module containing the builder for [`Gdcf`] instances
+
+use crate::{
+    api::ApiClient,
+    cache::{Cache, CacheUpdate, UpdateHook},
+    Gdcf,
+};
+use std::sync::Arc;
+
+/// Builder for a [`Gdcf`] instance
+///
+/// The API client and the cache have to be provided before [`GdcfBuilder::build`] becomes
+/// available. All other options default to the same values [`Gdcf::new`] uses. Options concerning
+/// only the client or the cache (like rate limits or cache expiry) are configured on those directly.
+#[derive(Debug, Clone, Default)]
+pub struct GdcfBuilder<A = (), C = ()> {
+    client: A,
+    cache: C,
+    offline: bool,
+    on_update: Option<UpdateHook>,
+}
+
+impl GdcfBuilder {
+    /// Creates a new builder without a client or a cache
+    pub fn new() -> GdcfBuilder {
+        GdcfBuilder::default()
+    }
+}
+
+impl Gdcf<(), ()> {
+    /// Creates a new [`GdcfBuilder`] without a client or a cache
+    ///
+    /// Shorthand for [`GdcfBuilder::new`].
+    pub fn builder() -> GdcfBuilder {
+        GdcfBuilder::new()
+    }
+}
+
+impl<A, C> GdcfBuilder<A, C> {
+    /// Sets the API client the [`Gdcf`] instance makes its requests with
+    pub fn client<A2: ApiClient>(self, client: A2) -> GdcfBuilder<A2, C> {
+        GdcfBuilder {
+            client,
+            cache: self.cache,
+            offline: self.offline,
+            on_update: self.on_update,
+        }
+    }
+
+    /// Sets the cache the [`Gdcf`] instance stores responses in
+    pub fn cache<C2: Cache>(self, cache: C2) -> GdcfBuilder<A, C2> {
+        GdcfBuilder {
+            client: self.client,
+            cache,
+            offline: self.offline,
+            on_update: self.on_update,
+        }
+    }
+
+    /// Sets whether the [`Gdcf`] instance operates in offline mode
+    ///
+    /// See [`Gdcf::offline`]
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Sets a callback to be invoked whenever a refresh updates the cache
+    ///
+    /// See [`Gdcf::on_cache_update`]
+    pub fn on_cache_update(mut self, f: impl Fn(&CacheUpdate) + Send + Sync + 'static) -> Self {
+        self.on_update = Some(UpdateHook(Arc::new(f)));
+        self
+    }
+}
+
+impl<A: ApiClient, C: Cache> GdcfBuilder<A, C> {
+    /// Builds the configured [`Gdcf`] instance
+    pub fn build(self) -> Gdcf<A, C> {
+        Gdcf {
+            client: self.client,
+            cache: self.cache,
+            offline: self.offline,
+            on_update: self.on_update,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api::request::LevelsRequest,
+        cache::CacheEntry,
+        future::process::ProcessRequestFuture,
+        mock::{MockCache, MockClient},
+        Gdcf,
+    };
+    use futures::Future;
+    use std::sync::{mpsc, Mutex};
+
+    #[test]
+    fn builder_applies_offline_mode_and_update_hook() {
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let client = MockClient::new(10);
+
+        let gdcf = Gdcf::builder()
+            .client(client.clone())
+            .cache(MockCache::default())
+            .offline(true)
+            .on_cache_update(move |update| sender.lock().unwrap().send(update.clone()).unwrap())
+            .build();
+
+        assert!(gdcf.is_offline());
+
+        match ProcessRequestFuture::new(gdcf.clone(), LevelsRequest::default(), false).unwrap().wait().unwrap() {
+            CacheEntry::Missing => (),
+            entry => panic!("unexpected cache entry {:?}", entry),
+        }
+        assert!(client.requests().is_empty());
+        assert!(receiver.try_recv().is_err());
+
+        // The hook stays in place once the instance goes online
+        let gdcf = gdcf.offline(false);
+
+        ProcessRequestFuture::new(gdcf, LevelsRequest::default(), false).unwrap().wait().unwrap();
+
+        assert_eq!(receiver.try_recv().unwrap().request, format!("{:?}", LevelsRequest::default()));
+        assert_eq!(client.requests().len(), 1);
+    }
+}
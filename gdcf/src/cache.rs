@@ -1,23 +1,116 @@
 //! Module containing cache related traits/structs
 
-use crate::{api::request::Request, error::CacheError};
-use derive_more::Display;
-use gdcf_model::{song::NewgroundsSong, user::Creator};
-use std::fmt::{Display, Formatter};
+use crate::{
+    api::{client::PageInfo, request::Request},
+    error::CacheError,
+    Secondary,
+};
+use gdcf_model::{
+    song::NewgroundsSong,
+    user::{Creator, User},
+};
+use std::{
+    fmt::{Debug, Display, Formatter},
+    sync::Arc,
+    time::Duration,
+};
 
 pub trait Cache: Clone + Send + Sync + 'static {
     type CacheEntryMeta: CacheEntryMeta;
     type Err: CacheError;
+
+    /// Stores all the given objects (or marks them as absent) in one go
+    ///
+    /// This is called with the secondary objects of a single response, which are usually the songs
+    /// and creators contained in a `getGJLevels` response. The default implementation simply stores
+    /// them one by one, caches that can do better (for example by using multi-row inserts in a
+    /// single database transaction) should override this.
+    fn store_batch(&mut self, objects: &[Secondary]) -> Result<(), Self::Err>
+    where
+        Self: Store<NewgroundsSongKey> + Store<CreatorKey>,
+    {
+        for secondary in objects {
+            match secondary {
+                Secondary::NewgroundsSong(song) => self.store(song, &NewgroundsSongKey(song.song_id)),
+                Secondary::Creator(creator) => self.store(creator, &CreatorKey(creator.user_id)),
+                Secondary::MissingCreator(cid) => Store::<CreatorKey>::mark_absent(self, &CreatorKey(*cid)),
+                Secondary::MissingNewgroundsSong(nid) => Store::<NewgroundsSongKey>::mark_absent(self, &NewgroundsSongKey(*nid)),
+            }?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Notification about a refresh having updated an entry in the cache
+///
+/// See [`Gdcf::on_cache_update`](crate::Gdcf::on_cache_update).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheUpdate {
+    /// The debug representation of the request whose entry was updated
+    pub request: String,
+
+    /// Whether the request yielded no result and was marked as absent, instead of having its
+    /// result stored
+    pub absent: bool,
+}
+
+#[derive(Clone)]
+pub(crate) struct UpdateHook(pub(crate) Arc<dyn Fn(&CacheUpdate) + Send + Sync>);
+
+impl UpdateHook {
+    pub(crate) fn notify(&self, request: &impl Debug, absent: bool) {
+        (self.0)(&CacheUpdate {
+            request: format!("{:?}", request),
+            absent,
+        })
+    }
+}
+
+impl Debug for UpdateHook {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("UpdateHook")
+    }
 }
 
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct NewgroundsSongKey(pub u64);
 
-#[derive(Debug, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct CreatorKey(pub u64);
 
-pub trait Key {
-    type Result;
+/// Key of a [`User`], holding their **account ID** (_not_ user ID)
+///
+/// Unlike a [`UserRequest`](crate::api::request::UserRequest), this doesn't imply that a request
+/// has to be made to retrieve the user, which allows upgrades to use whichever profile has been
+/// cached for the account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UserKey(pub u64);
+
+impl Display for NewgroundsSongKey {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "NewgroundsSongKey({})", self.0)
+    }
+}
+
+impl Display for CreatorKey {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "CreatorKey({})", self.0)
+    }
+}
+
+impl Display for UserKey {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "UserKey({})", self.0)
+    }
+}
+
+pub trait Key: Send + Sync + 'static {
+    /// The type of object stored under this key
+    ///
+    /// When upgrading a list of objects, each distinct key is looked up only once and its entry is
+    /// cloned for every object that needs it.
+    type Result: Clone + Send + Sync + 'static;
 }
 
 impl<R: Request> Key for R {
@@ -32,14 +125,109 @@ impl Key for CreatorKey {
     type Result = Creator;
 }
 
+impl Key for UserKey {
+    type Result = User;
+}
+
 pub trait Lookup<K: Key>: Cache {
-    // TODO: maybe an exists method?
     fn lookup(&self, key: &K) -> Result<CacheEntry<K::Result, Self::CacheEntryMeta>, Self::Err>;
+
+    /// Looks up all the given keys at once
+    ///
+    /// The returned entries are in the same order as the keys. This is used when upgrading lists of
+    /// objects, which usually need to look up a lot of keys (for instance, the creators of a page of
+    /// levels). The default implementation simply looks them up one by one, caches that can do
+    /// better (for example by using a single database query) should override this.
+    fn lookup_many(&self, keys: &[K]) -> Result<Vec<CacheEntry<K::Result, Self::CacheEntryMeta>>, Self::Err> {
+        keys.iter().map(|key| self.lookup(key)).collect()
+    }
+
+    /// Checks whether the cache contains an entry for the given key
+    ///
+    /// An entry exists if the object has been stored or marked as absent, regardless of whether it
+    /// is expired. The default implementation simply performs a full lookup, which caches should
+    /// override with something that doesn't require deserializing the cached object.
+    fn contains(&self, key: &K) -> Result<bool, Self::Err> {
+        match self.lookup(key)? {
+            CacheEntry::Missing => Ok(false),
+            _ => Ok(true),
+        }
+    }
+
+    /// Retrieves the raw response body the object stored under the given key was parsed from
+    ///
+    /// Returns `None` if no raw response was retained, which is always the case for caches that
+    /// don't override [`Store::store_raw`].
+    fn lookup_raw(&self, _key: &K) -> Result<Option<String>, Self::Err> {
+        Ok(None)
+    }
+
+    /// Retrieves the metadata of the entry for the given key, without the cached object
+    ///
+    /// Returns `None` if there is no entry for the key. The default implementation performs a full
+    /// lookup, which caches should override with something that doesn't require deserializing the
+    /// cached object.
+    ///
+    /// This is meant for clients talking to servers that support conditional requests: Whatever the
+    /// cache's metadata records about when the entry was stored can be sent along with the request
+    /// (for example as an `If-Modified-Since` header), allowing the server to skip sending data that
+    /// didn't change.
+    fn lookup_meta(&self, key: &K) -> Result<Option<Self::CacheEntryMeta>, Self::Err> {
+        Ok(match self.lookup(key)? {
+            CacheEntry::Missing => None,
+            CacheEntry::MarkedAbsent(meta) | CacheEntry::Cached(_, meta) => Some(meta),
+        })
+    }
+
+    /// Retrieves how long ago the entry for the given key was stored
+    ///
+    /// Returns `None` if there is no entry for the key, or if the cache doesn't track when entries
+    /// were stored. See [`CacheEntryMeta::age`].
+    fn entry_age(&self, key: &K) -> Result<Option<Duration>, Self::Err> {
+        Ok(self.lookup_meta(key)?.and_then(|meta| meta.age()))
+    }
+
+    /// Retrieves the pagination information of the response the object stored under the given key
+    /// was parsed from
+    ///
+    /// Returns `None` if the response didn't contain any, or if the cache doesn't override
+    /// [`Store::store_page_info`].
+    fn lookup_page_info(&self, _key: &K) -> Result<Option<PageInfo>, Self::Err> {
+        Ok(None)
+    }
 }
 
 pub trait Store<K: Key>: Cache {
     fn store(&mut self, obj: &K::Result, key: &K) -> Result<Self::CacheEntryMeta, Self::Err>;
     fn mark_absent(&mut self, key: &K) -> Result<Self::CacheEntryMeta, Self::Err>;
+
+    /// Creates the metadata for an object that was just retrieved for the given key, but wasn't
+    /// stored
+    ///
+    /// This is used for requests that opted out of caching (see
+    /// [`Request::should_cache`](crate::api::request::Request::should_cache)). The metadata must
+    /// not indicate the object to be expired. The default implementation returns the default
+    /// [`CacheEntryMeta`].
+    fn transient_meta(&self, _key: &K) -> Self::CacheEntryMeta {
+        Self::CacheEntryMeta::default()
+    }
+
+    /// Stores the raw response body the object stored under the given key was parsed from
+    ///
+    /// This is called after [`Store::store`] if the API client provided the raw response. The
+    /// default implementation discards it.
+    fn store_raw(&mut self, _key: &K, _raw: &str) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
+    /// Stores the pagination information of the response the object stored under the given key was
+    /// parsed from
+    ///
+    /// This is called after [`Store::store`] if the API client provided pagination information.
+    /// The default implementation discards it.
+    fn store_page_info(&mut self, _key: &K, _page_info: &PageInfo) -> Result<(), Self::Err> {
+        Ok(())
+    }
 }
 
 // FIXME: One they are stabilized, use a trait alias here
@@ -87,6 +275,15 @@ impl<T, Meta: CacheEntryMeta> CacheEntry<T, Meta> {
         }
     }
 
+    /// How long ago this entry was stored, or `None` if the entry is [missing](CacheEntry::Missing)
+    /// or its metadata doesn't track when it was stored
+    pub fn age(&self) -> Option<Duration> {
+        match self {
+            CacheEntry::Missing => None,
+            CacheEntry::MarkedAbsent(meta) | CacheEntry::Cached(_, meta) => meta.age(),
+        }
+    }
+
     pub fn map<U>(self, f: impl FnOnce(T) -> U) -> CacheEntry<U, Meta> {
         match self {
             CacheEntry::Missing => CacheEntry::Missing,
@@ -95,12 +292,47 @@ impl<T, Meta: CacheEntryMeta> CacheEntry<T, Meta> {
         }
     }
 
+    /// Converts from `&CacheEntry<T, Meta>` to `CacheEntry<&T, Meta>`, allowing the cached object
+    /// to be inspected or [mapped](CacheEntry::map) without consuming the entry
+    pub fn as_ref(&self) -> CacheEntry<&T, Meta> {
+        match self {
+            CacheEntry::Missing => CacheEntry::Missing,
+            CacheEntry::MarkedAbsent(absent_meta) => CacheEntry::MarkedAbsent(*absent_meta),
+            CacheEntry::Cached(object, meta) => CacheEntry::Cached(object, *meta),
+        }
+    }
+
     pub(crate) fn map_empty<U>(self) -> CacheEntry<U, Meta> {
         self.map(|_| panic!("CacheEntry::map_empty called on `Cached` variant"))
     }
 }
 
-pub trait CacheEntryMeta: Clone + std::fmt::Debug + Copy + Send + Sync + 'static {
+/// Metadata a cache stores alongside each entry
+///
+/// The [`Default`] value describes an object that was retrieved just now, but not stored (see
+/// [`Store::transient_meta`]). It must be neither expired nor absent.
+pub trait CacheEntryMeta: Clone + std::fmt::Debug + Copy + Default + Send + Sync + 'static {
     fn is_expired(&self) -> bool;
     fn is_absent(&self) -> bool;
+
+    /// How long ago the entry was stored
+    ///
+    /// Caches that track when entries were stored should override this, and base their
+    /// [`CacheEntryMeta::is_expired`] on it, so that the age displayed to users matches the point
+    /// at which GDCF refreshes the entry. The default implementation returns `None`.
+    fn age(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::{CreatorKey, NewgroundsSongKey, UserKey};
+
+    #[test]
+    fn keys_display_their_type_and_id() {
+        assert_eq!(CreatorKey(503_085).to_string(), "CreatorKey(503085)");
+        assert_eq!(NewgroundsSongKey(467_339).to_string(), "NewgroundsSongKey(467339)");
+        assert_eq!(UserKey(37415).to_string(), "UserKey(37415)");
+    }
 }
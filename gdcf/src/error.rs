@@ -1,21 +1,50 @@
 //! Module containing the various error types used by gdcf
+//!
+//! All errors in here implement [`std::error::Error`], and [`Error::source`](std::error::Error::source)
+//! returns the wrapped error where there is one. [`Fail`] is implemented via failure's blanket
+//! implementation.
 
 use failure::Fail;
+use std::{
+    error::Error as StdError,
+    fmt::{Display, Formatter},
+};
 
-pub trait ApiError: Fail {
+pub trait ApiError: StdError + Fail {
     fn is_no_result(&self) -> bool;
 }
 
-pub trait CacheError: Fail {}
+pub trait CacheError: StdError + Fail {}
 
-#[derive(Debug, Fail)]
+#[derive(Debug)]
 pub enum Error<A: ApiError, C: CacheError> {
-    #[fail(display = "{}", _0)]
-    Cache(#[cause] C),
+    Cache(C),
 
-    #[fail(display = "{}", _0)]
-    Api(#[cause] A),
+    Api(A),
 
-    #[fail(display = "GDCF made an assumption about server sided data consistency, which was violated. Please open a bug report")]
     UnexpectedlyAbsent,
 }
+
+impl<A: ApiError, C: CacheError> Display for Error<A, C> {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::Cache(err) => write!(f, "{}", err),
+            Error::Api(err) => write!(f, "{}", err),
+            Error::UnexpectedlyAbsent =>
+                write!(
+                    f,
+                    "GDCF made an assumption about server sided data consistency, which was violated. Please open a bug report"
+                ),
+        }
+    }
+}
+
+impl<A: ApiError, C: CacheError> StdError for Error<A, C> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Cache(err) => Some(err),
+            Error::Api(err) => Some(err),
+            Error::UnexpectedlyAbsent => None,
+        }
+    }
+}
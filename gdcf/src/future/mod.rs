@@ -21,6 +21,20 @@ pub trait CloneablePeekFuture: PeekableFuture {
 pub trait StreamableFuture<A: ApiClient, C: Cache>: Future<Error = Error<A::Err, C::Err>> + Sized {
     fn next(self) -> Result<Self, Self::Error>;
 
+    /// The amount of objects on a full page of results, if known
+    ///
+    /// See [`PaginatableRequest::expected_page_size`](crate::api::request::PaginatableRequest::expected_page_size)
+    fn page_size(&self) -> Option<usize> {
+        None
+    }
+
+    /// Whether the given item, which this future resolved to, is the last page of results
+    ///
+    /// See [`PaginatableRequest::is_last_page`](crate::api::request::PaginatableRequest::is_last_page)
+    fn is_last_page(&self, _page: &Self::Item) -> bool {
+        false
+    }
+
     fn stream(self) -> GdcfStream<A, C, Self> {
         GdcfStream::new(self)
     }
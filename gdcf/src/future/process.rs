@@ -54,6 +54,65 @@ where
             gdcf,
         })
     }
+
+    /// The request this future is processing
+    pub fn request(&self) -> &Req {
+        match &self.state {
+            ProcessRequestFutureState::UpToDate(_, request) => request,
+            ProcessRequestFutureState::Outdated(_, future) | ProcessRequestFutureState::Uncached(future) => &future.request,
+        }
+    }
+
+    /// Splits this future into the cached value and the future refreshing it
+    ///
+    /// This allows the cached value to be used immediately while the refresh is spawned onto an
+    /// executor of the caller's choice. The cached value is [`None`] if nothing was cached, the
+    /// future is [`None`] if the cached value is up-to-date and no refresh is necessary.
+    ///
+    /// Note that the returned future resolves to the refreshed value only, without any upgrades
+    /// applied.
+    pub fn split(
+        self,
+    ) -> (
+        Option<CacheEntry<Req::Result, C::CacheEntryMeta>>,
+        Option<impl Future<Item = CacheEntry<Req::Result, C::CacheEntryMeta>, Error = Error<A::Err, C::Err>>>,
+    ) {
+        match self.state {
+            ProcessRequestFutureState::Uncached(future) => (None, Some(future)),
+            ProcessRequestFutureState::Outdated(cache_entry, future) => (Some(cache_entry), Some(future)),
+            ProcessRequestFutureState::UpToDate(cache_entry, _) => (cache_entry, None),
+        }
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl<Req, A, C> ProcessRequestFuture<Req, A, C>
+where
+    A: ApiClient + MakeRequest<Req> + 'static,
+    C: Cache + Store<CreatorKey> + Store<NewgroundsSongKey> + CanCache<Req>,
+    Req: Request + 'static,
+    Req::Result: Send,
+    A::Future: Send,
+    A::Err: Send,
+    C::Err: Send,
+{
+    /// Synchronously resolves this future
+    ///
+    /// If the cache entry is up-to-date, it is returned directly without involving the given
+    /// runtime. Otherwise, this blocks the current thread until the request has been made (on the
+    /// given runtime) and its result has been stored.
+    ///
+    /// This is meant for synchronous applications that do not want to deal with the futures
+    /// combinators. It must not be called from within the runtime's own threads.
+    pub fn wait_cached_or(
+        self,
+        runtime: &mut tokio::runtime::Runtime,
+    ) -> Result<CacheEntry<Req::Result, C::CacheEntryMeta>, Error<A::Err, C::Err>> {
+        match self.state {
+            ProcessRequestFutureState::UpToDate(Some(cache_entry), _) => Ok(cache_entry),
+            _ => runtime.block_on(self),
+        }
+    }
 }
 
 impl<Req, A, C> StreamableFuture<A, C> for ProcessRequestFuture<Req, A, C>
@@ -74,6 +133,17 @@ where
             gdcf: self.gdcf,
         })
     }
+
+    fn page_size(&self) -> Option<usize> {
+        self.request().expected_page_size().map(|page_size| page_size as usize)
+    }
+
+    fn is_last_page(&self, page: &Self::Item) -> bool {
+        match page {
+            CacheEntry::Cached(result, _) => self.request().is_last_page(result),
+            _ => false,
+        }
+    }
 }
 
 impl<Req, A, C> PeekableFuture for ProcessRequestFuture<Req, A, C>
@@ -206,3 +276,45 @@ where
         self.upgrade()
     }
 }
+
+#[cfg(all(test, feature = "blocking"))]
+mod tests {
+    use crate::{
+        api::request::LevelsRequest,
+        cache::CacheEntry,
+        future::process::ProcessRequestFuture,
+        mock::{MockCache, MockClient},
+        Gdcf,
+    };
+    use tokio::runtime::Runtime;
+
+    #[test]
+    fn wait_cached_or_resolves_synchronously() {
+        let mut runtime = Runtime::new().unwrap();
+        let client = MockClient::new(3);
+        let gdcf = Gdcf::new(client.clone(), MockCache::default());
+
+        let entry = ProcessRequestFuture::new(gdcf.clone(), LevelsRequest::default(), false)
+            .unwrap()
+            .wait_cached_or(&mut runtime)
+            .unwrap();
+
+        match entry {
+            CacheEntry::Cached(levels, _) => assert_eq!(levels.len(), 3),
+            entry => panic!("unexpected cache entry {:?}", entry),
+        }
+        assert_eq!(client.requests().len(), 1);
+
+        // The entry is up-to-date now, so it is returned straight from the cache
+        let entry = ProcessRequestFuture::new(gdcf, LevelsRequest::default(), false)
+            .unwrap()
+            .wait_cached_or(&mut runtime)
+            .unwrap();
+
+        match entry {
+            CacheEntry::Cached(levels, _) => assert_eq!(levels.len(), 3),
+            entry => panic!("unexpected cache entry {:?}", entry),
+        }
+        assert_eq!(client.requests().len(), 1);
+    }
+}
@@ -4,9 +4,9 @@ use crate::{
         request::Request,
         ApiClient,
     },
-    cache::{Cache, CacheEntry, CanCache, CreatorKey, NewgroundsSongKey, Store},
+    cache::{Cache, CacheEntry, CanCache, CreatorKey, NewgroundsSongKey, Store, UpdateHook},
     error::{ApiError, Error},
-    Gdcf, Secondary,
+    Gdcf,
 };
 use futures::{Async, Future};
 use log::{info, warn};
@@ -17,8 +17,10 @@ where
     A: ApiClient + MakeRequest<Req>,
     C: Cache + Store<CreatorKey> + Store<NewgroundsSongKey> + CanCache<Req>,
 {
-    inner: <A as MakeRequest<Req>>::Future,
+    // `None` in offline mode, in which case the future resolves to `CacheEntry::Missing`
+    inner: Option<<A as MakeRequest<Req>>::Future>,
     cache: C,
+    on_update: Option<UpdateHook>,
     pub(super) request: Req,
 }
 
@@ -40,11 +42,20 @@ where
     C: Cache + Store<CreatorKey> + Store<NewgroundsSongKey> + CanCache<Req>,
 {
     pub(crate) fn new(gdcf: &Gdcf<A, C>, request: Req) -> Self {
-        info!("Performing refresh on request {:?}", request);
+        let inner = if gdcf.is_offline() {
+            info!("Offline mode, not performing refresh on request {:?}", request);
+
+            None
+        } else {
+            info!("Performing refresh on request {:?}", request);
+
+            Some(gdcf.client().make(&request))
+        };
 
         RefreshCacheFuture {
-            inner: gdcf.client().make(&request),
+            inner,
             cache: gdcf.cache(),
+            on_update: gdcf.on_update.clone(),
             request,
         }
     }
@@ -60,43 +71,80 @@ where
     type Item = CacheEntry<Req::Result, C::CacheEntryMeta>;
 
     fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
-        match self.inner.poll() {
+        let inner = match &mut self.inner {
+            Some(inner) => inner,
+            None => return Ok(Async::Ready(CacheEntry::Missing)),
+        };
+
+        match inner.poll() {
             Ok(Async::NotReady) => Ok(Async::NotReady),
+            Err(ref api_error) if api_error.is_no_result() && !self.request.should_cache() => {
+                warn!("Request yielded no result");
+
+                Ok(Async::Ready(CacheEntry::MarkedAbsent(self.cache.transient_meta(&self.request))))
+            },
             Err(ref api_error) if api_error.is_no_result() => {
                 // TODO: maybe mark malformed data as absent as well
 
                 warn!("Request yielded no result, marking as absent");
 
-                Store::<Req>::mark_absent(&mut self.cache, &self.request)
-                    .map(|entry_info| Async::Ready(CacheEntry::MarkedAbsent(entry_info)))
-                    .map_err(Error::Cache)
+                let entry_info = Store::<Req>::mark_absent(&mut self.cache, &self.request).map_err(Error::Cache)?;
+
+                if let Some(hook) = &self.on_update {
+                    hook.notify(&self.request, true)
+                }
+
+                Ok(Async::Ready(CacheEntry::MarkedAbsent(entry_info)))
             },
             Err(api_error) => Err(Error::Api(api_error)),
-            Ok(Async::Ready(response)) =>
-                match response {
-                    Response::Exact(what_we_want) =>
-                        self.cache
-                            .store(&what_we_want, &self.request)
-                            .map(|entry_info| Async::Ready(CacheEntry::Cached(what_we_want, entry_info)))
-                            .map_err(Error::Cache),
+            Ok(Async::Ready(response)) if !self.request.should_cache() => {
+                info!("Not caching result of request {:?}", self.request);
+
+                let what_we_want = match response.split_raw().0.split_page_info().0 {
+                    Response::Exact(what_we_want) | Response::More(what_we_want, _) => what_we_want,
+                    Response::Raw(..) | Response::Paged(..) => unreachable!(),
+                };
+
+                self.request.check_result(&what_we_want);
+
+                Ok(Async::Ready(CacheEntry::Cached(what_we_want, self.cache.transient_meta(&self.request))))
+            },
+            Ok(Async::Ready(response)) => {
+                let (response, raw) = response.split_raw();
+                let (response, page_info) = response.split_page_info();
+
+                let (what_we_want, entry_info) = match response {
+                    Response::Exact(what_we_want) => {
+                        let entry_info = self.cache.store(&what_we_want, &self.request).map_err(Error::Cache)?;
+
+                        (what_we_want, entry_info)
+                    },
                     Response::More(what_we_want, excess) => {
-                        for object in &excess {
-                            match object {
-                                Secondary::NewgroundsSong(song) => self.cache.store(song, &NewgroundsSongKey(song.song_id)),
-                                Secondary::Creator(creator) => self.cache.store(creator, &CreatorKey(creator.user_id)),
-                                Secondary::MissingCreator(cid) => Store::<CreatorKey>::mark_absent(&mut self.cache, &CreatorKey(*cid)),
-                                Secondary::MissingNewgroundsSong(nid) =>
-                                    Store::<NewgroundsSongKey>::mark_absent(&mut self.cache, &NewgroundsSongKey(*nid)),
-                            }
-                            .map_err(Error::Cache)?;
-                        }
-
-                        self.cache
-                            .store(&what_we_want, &self.request)
-                            .map(|entry_info| Async::Ready(CacheEntry::Cached(what_we_want, entry_info)))
-                            .map_err(Error::Cache)
+                        self.cache.store_batch(&excess).map_err(Error::Cache)?;
+
+                        let entry_info = self.cache.store(&what_we_want, &self.request).map_err(Error::Cache)?;
+
+                        (what_we_want, entry_info)
                     },
-                },
+                    Response::Raw(..) | Response::Paged(..) => unreachable!(),
+                };
+
+                self.request.check_result(&what_we_want);
+
+                if let Some(raw) = raw {
+                    Store::<Req>::store_raw(&mut self.cache, &self.request, &raw).map_err(Error::Cache)?;
+                }
+
+                if let Some(page_info) = page_info {
+                    Store::<Req>::store_page_info(&mut self.cache, &self.request, &page_info).map_err(Error::Cache)?;
+                }
+
+                if let Some(hook) = &self.on_update {
+                    hook.notify(&self.request, false)
+                }
+
+                Ok(Async::Ready(CacheEntry::Cached(what_we_want, entry_info)))
+            },
         }
     }
 }
@@ -1,15 +1,19 @@
 use crate::{
-    api::ApiClient,
-    cache::Cache,
+    api::{client::MakeRequest, request::PaginatableRequest, ApiClient},
+    cache::{Cache, CacheEntry, CacheEntryMeta, CanCache, CreatorKey, NewgroundsSongKey, Store},
     error::{ApiError, Error},
-    future::StreamableFuture,
+    future::{process::ProcessRequestFuture, StreamableFuture},
 };
 use futures::{Async, Stream};
-use std::marker::PhantomData;
+use std::{marker::PhantomData, vec::IntoIter};
 
 #[derive(Debug)]
 pub struct GdcfStream<A: ApiClient, C: Cache, F: StreamableFuture<A, C>> {
     current_future: Option<F>,
+    // Whether `current_future` has already resolved. It is only advanced to the next page once the
+    // stream is polled again, so that consumers who stop after a page don't cause a request for the
+    // next one
+    advance: bool,
     _phantom: PhantomData<(A, C)>,
 }
 
@@ -17,11 +21,65 @@ impl<A: ApiClient, C: Cache, F: StreamableFuture<A, C>> GdcfStream<A, C, F> {
     pub(crate) fn new(future: F) -> Self {
         GdcfStream {
             current_future: Some(future),
+            advance: false,
             _phantom: PhantomData,
         }
     }
 }
 
+impl<Req, A, C> GdcfStream<A, C, ProcessRequestFuture<Req, A, C>>
+where
+    A: ApiClient + MakeRequest<Req>,
+    C: Cache + Store<CreatorKey> + Store<NewgroundsSongKey> + CanCache<Req>,
+    Req: PaginatableRequest + Clone,
+{
+    /// The request for the page this stream yields next, or [`None`] if the stream failed or has
+    /// yielded the last page
+    ///
+    /// Once a page has been yielded, this already is the request for the following page (even
+    /// though that one is only processed once the stream is polled again). Persisting it allows a
+    /// scan that got interrupted to be resumed later on via [`Gdcf::paginate`](crate::Gdcf::paginate).
+    /// Pages yielded before the interruption that are still cached don't need to be requested again.
+    pub fn next_request(&self) -> Option<Req> {
+        self.current_future.as_ref().map(|current_future| {
+            let mut request = current_future.request().clone();
+
+            if self.advance {
+                request.next();
+            }
+
+            request
+        })
+    }
+}
+
+impl<A, C, F, T> GdcfStream<A, C, F>
+where
+    A: ApiClient,
+    C: Cache,
+    F: StreamableFuture<A, C, Item = CacheEntry<Vec<T>, C::CacheEntryMeta>>,
+{
+    /// Limits this stream to the first `n` items across all pages
+    ///
+    /// Unlike [`Stream::take`], which counts pages, this flattens the pages and counts their
+    /// individual items, so a `take_items(30)` over a search with 10 levels per page yields exactly
+    /// 30 levels (or fewer, if the search is exhausted before that). No further pages are requested
+    /// once `n` items have been yielded.
+    ///
+    /// The stream also ends at the first page that is empty or marked as absent in the cache, and
+    /// after the first page that has fewer items than a full page (see
+    /// [`StreamableFuture::page_size`]).
+    pub fn take_items(self, n: usize) -> TakeItems<Self, T> {
+        TakeItems {
+            page_size: self.current_future.as_ref().and_then(StreamableFuture::page_size),
+            stream: self,
+            current_page: Vec::new().into_iter(),
+            last_page: false,
+            remaining: n,
+        }
+    }
+}
+
 // FIXME: figure out a way to terminate these streams if our Item is a collection type (like Vec<T>)
 // and we receive an empty collection
 impl<A: ApiClient, C: Cache, F: StreamableFuture<A, C>> Stream for GdcfStream<A, C, F> {
@@ -29,14 +87,26 @@ impl<A: ApiClient, C: Cache, F: StreamableFuture<A, C>> Stream for GdcfStream<A,
     type Item = F::Item;
 
     fn poll(&mut self) -> Result<Async<Option<Self::Item>>, Self::Error> {
+        if self.advance {
+            self.advance = false;
+
+            // We cannot move out of borrowed context, which means we have to "trick" rust into allowing us to
+            // swap out the futures by using an Option
+            self.current_future = self.current_future.take().map(|current_future| current_future.next()).transpose()?;
+        }
+
         if let Some(ref mut current_future) = self.current_future {
             match current_future.poll() {
                 Ok(Async::NotReady) => Ok(Async::NotReady),
 
                 Ok(Async::Ready(page)) => {
-                    // We cannot move out of borrowed context, which means we have to "trick" rust into allowing us to
-                    // swap out the futures by using an Option
-                    self.current_future = self.current_future.take().map(|current_future| current_future.next()).transpose()?;
+                    // A page that isn't full is the last one, requesting the next one would only yield an empty
+                    // response
+                    if current_future.is_last_page(&page) {
+                        self.current_future = None;
+                    } else {
+                        self.advance = true;
+                    }
 
                     Ok(Async::Ready(Some(page)))
                 },
@@ -54,3 +124,148 @@ impl<A: ApiClient, C: Cache, F: StreamableFuture<A, C>> Stream for GdcfStream<A,
         }
     }
 }
+
+/// Stream yielding the individual items of a paginated stream, up to a fixed amount
+///
+/// Created by [`GdcfStream::take_items`].
+#[derive(Debug)]
+pub struct TakeItems<S, T> {
+    stream: S,
+    current_page: IntoIter<T>,
+    page_size: Option<usize>,
+    last_page: bool,
+    remaining: usize,
+}
+
+impl<S, T, Meta> Stream for TakeItems<S, T>
+where
+    S: Stream<Item = CacheEntry<Vec<T>, Meta>>,
+    Meta: CacheEntryMeta,
+{
+    type Error = S::Error;
+    type Item = T;
+
+    fn poll(&mut self) -> Result<Async<Option<T>>, S::Error> {
+        while self.remaining != 0 {
+            if let Some(item) = self.current_page.next() {
+                self.remaining -= 1;
+
+                return Ok(Async::Ready(Some(item)))
+            }
+
+            // A page that isn't full is the last one, requesting the next one would only yield an empty
+            // response
+            if self.last_page {
+                break
+            }
+
+            match self.stream.poll()? {
+                Async::NotReady => return Ok(Async::NotReady),
+                Async::Ready(Some(CacheEntry::Cached(ref page, _))) if page.is_empty() => break,
+                Async::Ready(Some(CacheEntry::Cached(page, _))) => {
+                    self.last_page = self.page_size.map_or(false, |page_size| page.len() < page_size);
+                    self.current_page = page.into_iter();
+                },
+                Async::Ready(Some(_)) | Async::Ready(None) => break,
+            }
+        }
+
+        self.remaining = 0;
+
+        Ok(Async::Ready(None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api::request::LevelsRequest,
+        cache::CacheEntry,
+        future::StreamableFuture,
+        mock::{MockCache, MockClient},
+        Gdcf,
+    };
+    use futures::Stream;
+    use gdcf_model::{level::PartialLevel, user::Creator};
+
+    #[test]
+    fn stream_ends_after_short_page() {
+        let client = MockClient::new(15);
+        let gdcf = Gdcf::new(client.clone(), MockCache::default());
+
+        let page_sizes: Vec<usize> = gdcf
+            .paginate(LevelsRequest::default(), false)
+            .unwrap()
+            .wait()
+            .map(|page| match page.unwrap() {
+                CacheEntry::Cached(levels, _) => levels.len(),
+                entry => panic!("unexpected cache entry {:?}", entry),
+            })
+            .collect();
+
+        assert_eq!(page_sizes, vec![10, 5]);
+        assert_eq!(client.requested_pages(), vec![0, 1]);
+    }
+
+    #[test]
+    fn upgraded_stream_ends_after_short_page() {
+        let client = MockClient::new(15);
+        let gdcf = Gdcf::new(client.clone(), MockCache::default());
+
+        let page_sizes: Vec<usize> = gdcf
+            .levels(LevelsRequest::default(), false)
+            .unwrap()
+            .upgrade_all::<PartialLevel<Option<u64>, Option<Creator>>>()
+            .stream()
+            .wait()
+            .map(|page| match page.unwrap() {
+                CacheEntry::Cached(levels, _) => levels.len(),
+                entry => panic!("unexpected cache entry {:?}", entry),
+            })
+            .collect();
+
+        assert_eq!(page_sizes, vec![10, 5]);
+        assert_eq!(client.requested_pages(), vec![0, 1]);
+    }
+
+    #[test]
+    fn take_items_yields_exact_amount_of_items() {
+        let client = MockClient::new(100);
+        let gdcf = Gdcf::new(client.clone(), MockCache::default());
+
+        let level_ids: Vec<u64> = gdcf
+            .paginate(LevelsRequest::default(), false)
+            .unwrap()
+            .take_items(30)
+            .wait()
+            .map(|level| level.unwrap().level_id)
+            .collect();
+
+        assert_eq!(level_ids, (0..30).collect::<Vec<_>>());
+        assert_eq!(client.requested_pages(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn next_page_is_only_requested_when_polled() {
+        let client = MockClient::new(100);
+        let gdcf = Gdcf::new(client.clone(), MockCache::default());
+
+        let mut stream = gdcf.paginate(LevelsRequest::default(), false).unwrap();
+
+        stream.poll().unwrap();
+
+        assert_eq!(stream.next_request().map(|request| request.page), Some(1));
+        assert_eq!(client.requested_pages(), vec![0]);
+    }
+
+    #[test]
+    fn take_items_stops_at_end_of_results() {
+        let client = MockClient::new(25);
+        let gdcf = Gdcf::new(client.clone(), MockCache::default());
+
+        let levels = gdcf.paginate(LevelsRequest::default(), false).unwrap().take_items(30).wait().count();
+
+        assert_eq!(levels, 25);
+        assert_eq!(client.requested_pages(), vec![0, 1, 2]);
+    }
+}
@@ -48,6 +48,10 @@ where
     forced_refresh: bool,
     inner_future: From,
     pending_upgrade: Option<PendingUpgrade<A, C, Into, U>>, //state: UpgradeFutureState<A, C, From, Into, U>,
+
+    // The length of the page of results the inner future resolved to, recorded before upgrading it
+    // (see `Upgradable::page_len`)
+    page_len: Option<usize>,
 }
 
 impl<A, C, From, Into, U> UpgradeFuture<A, C, From, Into, U>
@@ -63,6 +67,7 @@ where
             inner_future,
             forced_refresh,
             pending_upgrade: None,
+            page_len: None,
         }
     }
 }
@@ -77,9 +82,25 @@ where
         Ok(Self {
             inner_future: self.inner_future.next()?,
             pending_upgrade: None,
+            page_len: None,
             ..self
         })
     }
+
+    fn page_size(&self) -> Option<usize> {
+        self.inner_future.page_size()
+    }
+
+    /// Whether the page of results the inner future resolved to wasn't full
+    ///
+    /// The inner future's own decision can't be asked for, since it is made based on the page before
+    /// it was upgraded. Upgrading doesn't change the length of a page though.
+    fn is_last_page(&self, _page: &Self::Item) -> bool {
+        match (self.page_len, self.page_size()) {
+            (Some(page_len), Some(page_size)) => page_len < page_size,
+            _ => false,
+        }
+    }
 }
 
 impl<A, C, From, Into, U> UpgradeFuture<A, C, From, Into, U>
@@ -100,6 +121,7 @@ where
             gdcf: self.gdcf.clone(),
             inner_future: self,
             pending_upgrade: None,
+            page_len: None,
         }
     }
 }
@@ -121,6 +143,7 @@ where
             gdcf: self.gdcf.clone(),
             inner_future: self,
             pending_upgrade: None,
+            page_len: None,
         }
     }
 }
@@ -141,6 +164,8 @@ where
                 Async::Ready(CacheEntry::Cached(to_upgrade, cache_meta)) => {
                     let upgrade_query = to_upgrade.query_upgrade(&self.gdcf.cache(), self.forced_refresh)?;
 
+                    self.page_len = to_upgrade.page_len();
+
                     self.pending_upgrade = Some(PendingUpgrade {
                         to_upgrade,
                         cache_meta,
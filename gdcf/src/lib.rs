@@ -12,28 +12,47 @@
 
 use crate::{
     api::{
-        client::MakeRequest,
-        request::{comment::ProfileCommentsRequest, user::UserSearchRequest, LevelRequest, LevelsRequest, Request, UserRequest},
+        client::{MakeRequest, PageInfo},
+        request::{
+            comment::ProfileCommentsRequest, level::LevelRequestType, user::UserSearchRequest, LevelRequest, LevelsRequest,
+            PaginatableRequest, Request, SongRequest, UserRequest,
+        },
         ApiClient,
     },
-    cache::{Cache, CacheEntry, CanCache, CreatorKey, NewgroundsSongKey, Store},
+    cache::{Cache, CacheEntry, CacheEntryMeta, CacheUpdate, CanCache, CreatorKey, Lookup, NewgroundsSongKey, Store, UpdateHook},
     future::{
         process::{ProcessRequestFuture, ProcessRequestFutureState},
         refresh::RefreshCacheFuture,
+        stream::GdcfStream,
+        upgrade::UpgradeFuture,
+        PeekableFuture, StreamableFuture,
     },
 };
+pub use builder::GdcfBuilder;
 pub use error::Error;
-use gdcf_model::{song::NewgroundsSong, user::Creator};
-use log::{info, trace};
+use gdcf_model::{
+    level::{Level, PartialLevel},
+    song::{Artist, NewgroundsSong, Song},
+    user::{Creator, User},
+};
+use futures::{
+    future::{err as future_err, ok as future_ok, Either},
+    stream, Future, Stream,
+};
+use std::{collections::HashSet, sync::Arc};
+use log::{info, trace, warn};
 
 #[macro_use]
 mod macros;
 mod upgrade;
 
 pub mod api;
+pub mod builder;
 pub mod cache;
 pub mod error;
 pub mod future;
+#[cfg(test)]
+mod mock;
 
 // FIXME: move this somewhere more fitting
 #[derive(Debug, Clone, PartialEq)]
@@ -68,13 +87,11 @@ impl std::fmt::Display for Secondary {
 }
 
 #[derive(Debug, Clone)]
-pub struct Gdcf<A, C>
-where
-    A: ApiClient,
-    C: Cache,
-{
+pub struct Gdcf<A, C> {
     client: A,
     cache: C,
+    offline: bool,
+    on_update: Option<UpdateHook>,
 }
 
 impl<A, C> Gdcf<A, C>
@@ -82,8 +99,40 @@ where
     A: ApiClient,
     C: Cache,
 {
+    /// Creates a new [`Gdcf`] instance using the given client and cache, with all other options set
+    /// to their defaults
+    ///
+    /// Use [`Gdcf::builder`] to configure these options upfront.
     pub fn new(client: A, cache: C) -> Gdcf<A, C> {
-        Gdcf { client, cache }
+        GdcfBuilder::new().client(client).cache(cache).build()
+    }
+
+    /// Sets whether this [`Gdcf`] instance operates in offline mode
+    ///
+    /// In offline mode, requests are served purely from the cache and the API client is never
+    /// used. Requests resolve to whatever is cached, regardless of whether it is expired (or
+    /// whether a refresh was forced), and to [`CacheEntry::Missing`] if nothing is. Upgrades that
+    /// would require data that isn't cached fail. This is useful for deterministic tests, and as a
+    /// degraded mode for when the servers are unreachable.
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Sets a callback to be invoked whenever a refresh stores a new value in the cache (or marks a
+    /// request as absent)
+    ///
+    /// This is meant for reactive frontends, which can use it to re-render the affected item. The
+    /// callback is invoked synchronously from within the refresh future, right after the cache was
+    /// updated, so it should not block. To process notifications elsewhere, send them through a
+    /// channel. Requests bypassing the cache don't cause notifications.
+    pub fn on_cache_update(mut self, f: impl Fn(&CacheUpdate) + Send + Sync + 'static) -> Self {
+        self.on_update = Some(UpdateHook(Arc::new(f)));
+        self
+    }
+
+    pub fn is_offline(&self) -> bool {
+        self.offline
     }
 
     pub fn cache(&self) -> C {
@@ -93,6 +142,20 @@ where
     pub fn client(&self) -> A {
         self.client.clone()
     }
+
+    /// Gives the given closure access to the cache this [`Gdcf`] instance uses, without cloning it
+    ///
+    /// This is an escape hatch for operations that cannot be expressed as requests, like custom
+    /// queries against a database cache.
+    pub fn with_cache<R>(&self, f: impl FnOnce(&C) -> R) -> R {
+        f(&self.cache)
+    }
+
+    /// Gives the given closure access to the API client this [`Gdcf`] instance uses, without
+    /// cloning it
+    pub fn with_client<R>(&self, f: impl FnOnce(&A) -> R) -> R {
+        f(&self.client)
+    }
 }
 
 impl<A, C> Gdcf<A, C>
@@ -108,6 +171,18 @@ where
     {
         info!("Processing request {:?}", request);
 
+        if self.offline {
+            info!("Offline mode, serving request {:?} from cache only", request);
+
+            return Ok(ProcessRequestFutureState::UpToDate(Some(self.cache.lookup(&request)?), request))
+        }
+
+        if !request.should_cache() {
+            info!("Request {:?} bypasses the cache", request);
+
+            return Ok(ProcessRequestFutureState::Uncached(RefreshCacheFuture::new(self, request)))
+        }
+
         let cached = match self.cache.lookup(&request)? {
             CacheEntry::Missing => {
                 info!("No cache entry for request {:?}", request);
@@ -117,7 +192,10 @@ where
             entry =>
                 if entry.is_expired() {
                     trace!("Cache entry is {:?}", entry);
-                    info!("Cache entry for request {:?} is expired!", request);
+                    match entry.age() {
+                        Some(age) => info!("Cache entry for request {:?} is expired (stored {:?} ago)!", request, age),
+                        None => info!("Cache entry for request {:?} is expired!", request),
+                    }
 
                     Some(entry)
                 } else if force_refresh {
@@ -140,6 +218,80 @@ where
             None => ProcessRequestFutureState::Uncached(future),
         })
     }
+
+    /// Makes sure the cache holds an up-to-date entry for the given request, refreshing it if
+    /// necessary
+    ///
+    /// The returned future resolves once the entry has been stored, without handing out the cached
+    /// object. Whether a refresh is necessary is decided based on the entry's metadata alone, the
+    /// cached object itself is never looked up.
+    ///
+    /// In offline mode, and for requests that bypass the cache, this does nothing.
+    pub fn ensure_cached<R>(&self, request: R) -> Result<impl Future<Item = (), Error = Error<A::Err, C::Err>>, C::Err>
+    where
+        R: Request,
+        A: MakeRequest<R>,
+        C: CanCache<R>,
+    {
+        if self.offline || !request.should_cache() {
+            info!("Not ensuring request {:?} is cached, it is never refreshed", request);
+
+            return Ok(Either::A(future_ok(())))
+        }
+
+        if let Some(meta) = self.cache.lookup_meta(&request)? {
+            if !meta.is_expired() {
+                trace!("Cache entry for request {:?} is up-to-date", request);
+
+                return Ok(Either::A(future_ok(())))
+            }
+        }
+
+        Ok(Either::B(RefreshCacheFuture::new(self, request).map(|_| ())))
+    }
+
+    /// Processes the given request, giving `f` the chance to observe or transform the cached value
+    /// before the returned future is polled
+    ///
+    /// `f` is called synchronously, and only if a cached value exists (be it up-to-date or expired).
+    /// Entries that are missing or marked as absent, which offline mode hands out without attempting
+    /// a refresh, are passed through without calling `f`. Whatever `f` returns is what the returned
+    /// future resolves to in case no refresh is necessary. If a refresh is necessary, the future
+    /// resolves to the refreshed value instead.
+    ///
+    /// See [`PeekableFuture::peek`] for details.
+    pub fn peek<R, F>(&self, request: R, force_refresh: bool, f: F) -> Result<ProcessRequestFuture<R, A, C>, Error<A::Err, C::Err>>
+    where
+        R: Request,
+        A: MakeRequest<R>,
+        C: CanCache<R>,
+        F: FnOnce(CacheEntry<R::Result, C::CacheEntryMeta>) -> Result<CacheEntry<R::Result, C::CacheEntryMeta>, Error<A::Err, C::Err>>,
+    {
+        ProcessRequestFuture::new(self.clone(), request, force_refresh)
+            .map_err(Error::Cache)?
+            .peek(|entry| {
+                match entry {
+                    CacheEntry::Cached(..) => f(entry),
+                    _ => Ok(entry),
+                }
+            })
+    }
+
+    /// Streams the results of the given request page by page, starting at the page the request is
+    /// for
+    ///
+    /// Together with [`GdcfStream::next_request`], this allows resuming a long scan after an
+    /// interruption: persist the next request while consuming the stream, and pass it here to pick up
+    /// where the scan left off. Since pages are looked up in the cache first, restarting a scan from
+    /// an earlier page only causes requests for pages that aren't cached (or have expired).
+    pub fn paginate<R>(&self, request: R, force_refresh: bool) -> Result<GdcfStream<A, C, ProcessRequestFuture<R, A, C>>, C::Err>
+    where
+        R: PaginatableRequest,
+        A: MakeRequest<R>,
+        C: CanCache<R>,
+    {
+        Ok(ProcessRequestFuture::new(self.clone(), request, force_refresh)?.stream())
+    }
 }
 
 impl<A, C> Gdcf<A, C>
@@ -178,6 +330,48 @@ where
         ProcessRequestFuture::new(self.clone(), request.into(), force_refresh)
     }
 
+    /// Processes the given [`LevelRequest`], returning the cached level and the future refreshing
+    /// it separately
+    ///
+    /// See [`ProcessRequestFuture::split`] for details.
+    pub fn level_split(
+        &self,
+        request: impl Into<LevelRequest>,
+        force_refresh: bool,
+    ) -> Result<
+        (
+            Option<CacheEntry<Level<Option<u64>, u64>, C::CacheEntryMeta>>,
+            Option<impl Future<Item = CacheEntry<Level<Option<u64>, u64>, C::CacheEntryMeta>, Error = Error<A::Err, C::Err>>>,
+        ),
+        C::Err,
+    >
+    where
+        A: MakeRequest<LevelRequest>,
+        C: CanCache<LevelRequest>,
+    {
+        Ok(self.level(request, force_refresh)?.split())
+    }
+
+    /// Processes the given [`LevelRequest`], giving `f` the chance to observe or transform the
+    /// cached level before the returned future is polled
+    ///
+    /// `f` is only called if a cached level exists. See [`Gdcf::peek`] for details.
+    pub fn level_peek<F>(
+        &self,
+        request: impl Into<LevelRequest>,
+        force_refresh: bool,
+        f: F,
+    ) -> Result<ProcessRequestFuture<LevelRequest, A, C>, Error<A::Err, C::Err>>
+    where
+        A: MakeRequest<LevelRequest>,
+        C: CanCache<LevelRequest>,
+        F: FnOnce(
+            CacheEntry<Level<Option<u64>, u64>, C::CacheEntryMeta>,
+        ) -> Result<CacheEntry<Level<Option<u64>, u64>, C::CacheEntryMeta>, Error<A::Err, C::Err>>,
+    {
+        self.peek(request.into(), force_refresh, f)
+    }
+
     /// Processes the given [`LevelsRequest`]
     ///
     /// The `User` and `Song` type parameters determine, which sequence of requests should be made
@@ -202,10 +396,144 @@ where
         A: MakeRequest<LevelsRequest>,
         C: CanCache<LevelsRequest>,
     {
-        ProcessRequestFuture::new(self.clone(), request.into(), force_refresh)
+        let request = request.into();
+        let ignored = request.ignored_fields();
+
+        if !ignored.is_empty() {
+            warn!("The fields {:?} of {} are ignored for request type {:?}", ignored, request, request.request_type);
+        }
+
+        ProcessRequestFuture::new(self.clone(), request, force_refresh)
+    }
+
+    /// Processes the given [`LevelsRequest`], additionally resolving the pagination information of
+    /// the response
+    ///
+    /// This allows rendering pagination controls without making a separate request for the total
+    /// amount of results. The pagination information is [`None`] if the request resulted in no
+    /// levels, or if the cache doesn't retain it (see [`Store::store_page_info`]).
+    pub fn levels_page(
+        &self,
+        request: impl Into<LevelsRequest>,
+        force_refresh: bool,
+    ) -> Result<
+        impl Future<
+            Item = (CacheEntry<Vec<PartialLevel<Option<u64>, u64>>, C::CacheEntryMeta>, Option<PageInfo>),
+            Error = Error<A::Err, C::Err>,
+        >,
+        C::Err,
+    >
+    where
+        A: MakeRequest<LevelsRequest>,
+        C: CanCache<LevelsRequest>,
+    {
+        let request = request.into();
+        let cache = self.cache();
+
+        Ok(self.levels(request.clone(), force_refresh)?.and_then(move |entry| {
+            // Looked up only after the future resolved, since it might have refreshed the page info
+            let page_info = cache.lookup_page_info(&request).map_err(Error::Cache)?;
+
+            Ok((entry, page_info))
+        }))
+    }
+
+    /// Retrieves the levels in the hall of fame, a curated list of levels RobTop considers
+    /// particularly noteworthy
+    ///
+    /// The returned stream yields the hall of fame page by page, starting at the first one.
+    pub fn hall_of_fame(&self, force_refresh: bool) -> Result<GdcfStream<A, C, ProcessRequestFuture<LevelsRequest, A, C>>, C::Err>
+    where
+        A: MakeRequest<LevelsRequest>,
+        C: CanCache<LevelsRequest>,
+    {
+        let request = LevelsRequest::default().request_type(LevelRequestType::HallOfFame);
+
+        Ok(self.levels(request, force_refresh)?.stream())
+    }
+
+    /// Processes the given [`LevelsRequest`] and resolves the custom songs and creators of all
+    /// returned levels
+    ///
+    /// The response to a `getGJLevels` request already contains the song and creator sections, which
+    /// get stored in the cache while the request is processed. This means that the upgrades done here
+    /// are satisfied from the cache and usually do not cause any additional requests, so this is
+    /// equivalent to `Gdcf::levels` followed by upgrading to
+    /// `PartialLevel<Option<NewgroundsSong>, Option<Creator>>`.
+    pub fn levels_full(
+        &self,
+        request: impl Into<LevelsRequest>,
+        force_refresh: bool,
+    ) -> Result<
+        UpgradeFuture<
+            A,
+            C,
+            UpgradeFuture<
+                A,
+                C,
+                ProcessRequestFuture<LevelsRequest, A, C>,
+                Vec<PartialLevel<Option<NewgroundsSong>, u64>>,
+                Vec<PartialLevel<Option<u64>, u64>>,
+            >,
+            Vec<PartialLevel<Option<NewgroundsSong>, Option<Creator>>>,
+            Vec<PartialLevel<Option<NewgroundsSong>, u64>>,
+        >,
+        C::Err,
+    >
+    where
+        A: MakeRequest<LevelsRequest>,
+        C: CanCache<LevelsRequest> + Lookup<NewgroundsSongKey> + Lookup<CreatorKey>,
+    {
+        Ok(self.levels(request, force_refresh)?.upgrade_all().upgrade_all())
+    }
+
+    /// Searches for a level by its name and the name of its creator
+    ///
+    /// This performs a search for the given name and only keeps levels whose name and creator name
+    /// match the given ones exactly (ignoring case). Since level names are not unique - not even
+    /// among the levels of a single creator - multiple levels might match. In that case, the one
+    /// with the most downloads is returned.
+    ///
+    /// Only the first page of search results is considered, so very common level names might not
+    /// yield a result even though a matching level exists.
+    pub fn find_level(
+        &self,
+        name: &str,
+        creator: &str,
+    ) -> Result<impl Future<Item = Option<PartialLevel<Option<NewgroundsSong>, Option<Creator>>>, Error = Error<A::Err, C::Err>>, C::Err>
+    where
+        A: MakeRequest<LevelsRequest>,
+        C: CanCache<LevelsRequest> + Lookup<NewgroundsSongKey> + Lookup<CreatorKey>,
+    {
+        let name = name.to_lowercase();
+        let creator = creator.to_lowercase();
+
+        Ok(self
+            .levels_full(LevelsRequest::default().search(name.clone()), false)?
+            .map(move |entry| {
+                match entry {
+                    CacheEntry::Cached(levels, _) =>
+                        levels
+                            .into_iter()
+                            .filter(|level| level.name.to_lowercase() == name)
+                            .filter(|level| {
+                                match level.creator {
+                                    Some(ref level_creator) => level_creator.name.to_lowercase() == creator,
+                                    None => false,
+                                }
+                            })
+                            .max_by_key(|level| level.downloads),
+                    _ => None,
+                }
+            }))
     }
 
     /// Processes the given [`UserRequest`]
+    ///
+    /// If no account with the requested ID exists (or the account is banned), the servers respond
+    /// with `-1`. This isn't treated as an error, instead the request resolves to
+    /// [`CacheEntry::MarkedAbsent`], allowing to distinguish non-existing users from failed
+    /// requests.
     pub fn user(&self, request: impl Into<UserRequest>, force_refresh: bool) -> Result<ProcessRequestFuture<UserRequest, A, C>, C::Err>
     where
         A: MakeRequest<UserRequest>,
@@ -214,6 +542,95 @@ where
         ProcessRequestFuture::new(self.clone(), request.into(), force_refresh)
     }
 
+    /// Processes the given [`SongRequest`]
+    ///
+    /// This retrieves the song directly, instead of searching for a level using it. Songs that
+    /// don't exist, or that aren't allowed to be used in Geometry Dash, resolve to
+    /// [`CacheEntry::MarkedAbsent`].
+    pub fn song(&self, request: impl Into<SongRequest>, force_refresh: bool) -> Result<ProcessRequestFuture<SongRequest, A, C>, C::Err>
+    where
+        A: MakeRequest<SongRequest>,
+        C: CanCache<SongRequest>,
+    {
+        ProcessRequestFuture::new(self.clone(), request.into(), force_refresh)
+    }
+
+    /// Resolves the [`Artist`] of the newgrounds song with the given id
+    ///
+    /// This processes a [`SongRequest`] and extracts the artist from the song. See [`Artist`] for
+    /// the (very limited) information available about artists.
+    pub fn song_artist(
+        &self,
+        song_id: u64,
+        force_refresh: bool,
+    ) -> Result<impl Future<Item = CacheEntry<Artist, C::CacheEntryMeta>, Error = Error<A::Err, C::Err>>, C::Err>
+    where
+        A: MakeRequest<SongRequest>,
+        C: CanCache<SongRequest>,
+    {
+        Ok(self.song(song_id, force_refresh)?.map(|entry| entry.map(|song| song.artist_info())))
+    }
+
+    /// Resolves the song the given level uses
+    ///
+    /// Main songs are resolved immediately. Custom songs are retrieved via a [`SongRequest`]
+    /// (which is served from the cache if the song was part of a cached `getGJLevels` response).
+    /// Resolves to [`None`] if the custom song doesn't exist (anymore), or if the level uses neither
+    /// kind of song.
+    pub fn resolve_song<User>(
+        &self,
+        level: &PartialLevel<Option<u64>, User>,
+        force_refresh: bool,
+    ) -> Result<impl Future<Item = Option<Song>, Error = Error<A::Err, C::Err>>, C::Err>
+    where
+        A: MakeRequest<SongRequest>,
+        C: CanCache<SongRequest>,
+    {
+        match level.custom_song {
+            Some(song_id) =>
+                Ok(Either::A(self.song(song_id, force_refresh)?.map(|entry| {
+                    match entry {
+                        CacheEntry::Cached(song, _) => Some(Song::Custom(song)),
+                        _ => None,
+                    }
+                }))),
+            None => Ok(Either::B(future_ok(level.main_song.map(Song::Main)))),
+        }
+    }
+
+    /// Processes a [`UserRequest`] for each of the given account IDs, making at most
+    /// `concurrency` requests at once
+    ///
+    /// Repeated account IDs are only requested once. Users with up-to-date cache entries are
+    /// yielded immediately, all others as soon as their request completes, meaning the order of the
+    /// returned stream doesn't match the order of the given IDs. A failing request doesn't end the
+    /// stream.
+    pub fn users(
+        &self,
+        account_ids: &[u64],
+        concurrency: usize,
+    ) -> impl Stream<Item = CacheEntry<User, C::CacheEntryMeta>, Error = Error<A::Err, C::Err>>
+    where
+        A: MakeRequest<UserRequest>,
+        C: CanCache<UserRequest>,
+    {
+        let mut seen = HashSet::new();
+        let account_ids: Vec<u64> = account_ids.iter().cloned().filter(|&account_id| seen.insert(account_id)).collect();
+
+        info!("Resolving {} users with a concurrency of {}", account_ids.len(), concurrency);
+
+        let gdcf = self.clone();
+
+        stream::iter_ok(account_ids)
+            .map(move |account_id| {
+                match gdcf.user(account_id, false) {
+                    Ok(future) => Either::A(future),
+                    Err(err) => Either::B(future_err(Error::Cache(err))),
+                }
+            })
+            .buffer_unordered(concurrency.max(1))
+    }
+
     pub fn search_user(
         &self,
         request: impl Into<UserSearchRequest>,
@@ -237,4 +654,219 @@ where
     {
         ProcessRequestFuture::new(self.clone(), request.into(), force_refresh)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use crate::{
+        api::{
+            client::{MakeRequest, Response},
+            request::{level::LevelRequestType, LevelRequest, LevelsRequest, Request, UserRequest},
+        },
+        cache::{CacheEntry, CacheUpdate, Store},
+        future::process::ProcessRequestFuture,
+        mock::{self, MockCache, MockClient, MockError},
+        Gdcf,
+    };
+    use futures::{future::FutureResult, Future, Stream};
+    use gdcf_model::{
+        level::{Level, Password},
+        user::User,
+    };
+    use std::sync::{mpsc, Mutex};
+
+    #[derive(Debug)]
+    struct UncachedRequest;
+
+    impl Request for UncachedRequest {
+        type Result = u64;
+
+        fn should_cache(&self) -> bool {
+            false
+        }
+    }
+
+    impl MakeRequest<UncachedRequest> for MockClient {
+        type Future = FutureResult<Response<u64>, MockError>;
+
+        fn make(&self, _: &UncachedRequest) -> Self::Future {
+            futures::future::ok(Response::Exact(42))
+        }
+    }
+
+    fn full_level(level_id: u64, name: &str) -> Level<Option<u64>, u64> {
+        let mut base = mock::level(level_id);
+        base.name = name.to_string();
+
+        Level {
+            base,
+            level_data: Vec::new().into(),
+            password: Password::NoCopy,
+            time_since_upload: String::new(),
+            time_since_update: String::new(),
+            index_36: String::new(),
+        }
+    }
+
+    impl MakeRequest<LevelRequest> for MockClient {
+        type Future = FutureResult<Response<Level<Option<u64>, u64>>, MockError>;
+
+        fn make(&self, request: &LevelRequest) -> Self::Future {
+            futures::future::ok(Response::Exact(full_level(request.level_id, "Refreshed")))
+        }
+    }
+
+    /// Behaves like the servers do for non-existing accounts, which respond with `-1`
+    impl MakeRequest<UserRequest> for MockClient {
+        type Future = FutureResult<Response<User>, MockError>;
+
+        fn make(&self, _: &UserRequest) -> Self::Future {
+            futures::future::err(MockError)
+        }
+    }
+
+    #[test]
+    fn uncached_request_leaves_cache_empty() {
+        let gdcf = Gdcf::new(MockClient::new(0), MockCache::default());
+
+        let entry = ProcessRequestFuture::new(gdcf.clone(), UncachedRequest, false).unwrap().wait().unwrap();
+
+        match entry {
+            CacheEntry::Cached(42, meta) => assert_eq!(meta, Default::default()),
+            entry => panic!("unexpected cache entry {:?}", entry),
+        }
+        assert_eq!(gdcf.cache().len(), 0);
+    }
+
+    #[test]
+    fn peek_skips_missing_entries_in_offline_mode() {
+        let client = MockClient::new(10);
+        let gdcf = Gdcf::new(client.clone(), MockCache::default()).offline(true);
+
+        let entry = gdcf
+            .peek(LevelsRequest::default(), false, |_| panic!("peeked at a missing entry"))
+            .unwrap()
+            .wait()
+            .unwrap();
+
+        match entry {
+            CacheEntry::Missing => (),
+            entry => panic!("unexpected cache entry {:?}", entry),
+        }
+        assert!(client.requests().is_empty());
+    }
+
+    #[test]
+    fn peek_transforms_cached_entries() {
+        let mut cache = MockCache::default();
+
+        cache.store(&vec![mock::level(0)], &LevelsRequest::default()).unwrap();
+
+        let gdcf = Gdcf::new(MockClient::new(10), cache);
+
+        let entry = gdcf
+            .peek(LevelsRequest::default(), false, |entry| Ok(entry.map(|_| vec![mock::level(1)])))
+            .unwrap()
+            .wait()
+            .unwrap();
+
+        match entry {
+            CacheEntry::Cached(levels, _) => assert_eq!(levels, vec![mock::level(1)]),
+            entry => panic!("unexpected cache entry {:?}", entry),
+        }
+    }
+
+    #[test]
+    fn level_split_returns_outdated_entry_and_refresher() {
+        let mut cache = MockCache::default();
+
+        cache.store(&full_level(5, "Cached"), &LevelRequest::new(5)).unwrap();
+        cache.expire(&LevelRequest::new(5));
+
+        let gdcf = Gdcf::new(MockClient::new(0), cache);
+
+        match gdcf.level_split(LevelRequest::new(5), false).unwrap() {
+            (Some(CacheEntry::Cached(level, _)), Some(refresher)) => {
+                assert_eq!(level.base.name, "Cached");
+
+                match refresher.wait().unwrap() {
+                    CacheEntry::Cached(level, _) => assert_eq!(level.base.name, "Refreshed"),
+                    entry => panic!("unexpected refreshed cache entry {:?}", entry),
+                }
+            },
+            (cached, refresher) => panic!("unexpected split {:?}, refresher present: {}", cached, refresher.is_some()),
+        }
+
+        // The refresher stored the new level, which is now up-to-date
+        match gdcf.level_split(LevelRequest::new(5), false).unwrap() {
+            (Some(CacheEntry::Cached(level, _)), None) => assert_eq!(level.base.name, "Refreshed"),
+            (cached, refresher) => panic!("unexpected split {:?}, refresher present: {}", cached, refresher.is_some()),
+        }
+    }
+
+    #[test]
+    fn cache_update_notifies_after_refresh() {
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+
+        let gdcf = Gdcf::new(MockClient::new(0), MockCache::default())
+            .on_cache_update(move |update| sender.lock().unwrap().send(update.clone()).unwrap());
+
+        gdcf.level(LevelRequest::new(5), false).unwrap().wait().unwrap();
+
+        let update = receiver.try_recv().unwrap();
+
+        assert_eq!(update.request, format!("{:?}", LevelRequest::new(5)));
+        assert!(!update.absent);
+
+        // Cache hits don't update the cache
+        gdcf.level(LevelRequest::new(5), false).unwrap().wait().unwrap();
+
+        assert!(receiver.try_recv().is_err());
+
+        gdcf.user(UserRequest::new(71), false).unwrap().wait().unwrap();
+
+        assert_eq!(receiver.try_recv().unwrap(), CacheUpdate {
+            request: format!("{:?}", UserRequest::new(71)),
+            absent: true,
+        });
+    }
+
+    #[test]
+    fn non_existing_user_is_marked_absent() {
+        let gdcf = Gdcf::new(MockClient::new(0), MockCache::default());
+
+        match gdcf.user(UserRequest::new(71), false).unwrap().wait().unwrap() {
+            CacheEntry::MarkedAbsent(meta) => assert!(meta.absent),
+            entry => panic!("unexpected cache entry {:?}", entry),
+        }
+
+        // The absence is cached, so asking again doesn't fail either
+        match gdcf.user(UserRequest::new(71), false).unwrap().wait().unwrap() {
+            CacheEntry::MarkedAbsent(_) => (),
+            entry => panic!("unexpected cache entry {:?}", entry),
+        }
+    }
+
+    #[test]
+    fn hall_of_fame_streams_every_page() {
+        let client = MockClient::new(15);
+        let gdcf = Gdcf::new(client.clone(), MockCache::default());
+
+        let level_ids: Vec<u64> = gdcf
+            .hall_of_fame(false)
+            .unwrap()
+            .wait()
+            .flat_map(|page| {
+                match page.unwrap() {
+                    CacheEntry::Cached(levels, _) => levels,
+                    entry => panic!("unexpected cache entry {:?}", entry),
+                }
+            })
+            .map(|level| level.level_id)
+            .collect();
+
+        assert_eq!(level_ids, (0..15).collect::<Vec<_>>());
+        assert_eq!(client.requested_pages(), vec![0, 1]);
+        assert!(client.requests().iter().all(|request| request.request_type == LevelRequestType::HallOfFame));
+    }
+}
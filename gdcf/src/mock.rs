@@ -0,0 +1,272 @@
+//! In-memory API client and cache for testing
+
+use crate::{
+    api::{
+        client::{MakeRequest, PageInfo, Response},
+        request::{level::DEFAULT_PAGE_SIZE, LevelsRequest},
+        ApiClient,
+    },
+    cache::{Cache, CacheEntry, CacheEntryMeta, Key, Lookup, Store},
+    error::{ApiError, CacheError},
+    Secondary,
+};
+use futures::future::{err, ok, FutureResult};
+use gdcf_model::{
+    level::{Featured, LevelLength, LevelRating, PartialLevel},
+    user::{Color, Creator, ModLevel, User},
+    GameVersion,
+};
+use std::{
+    any::Any,
+    collections::HashMap,
+    error::Error as StdError,
+    fmt::{Debug, Display, Formatter},
+    sync::{Arc, Mutex},
+};
+
+/// Error of the mock client and cache. For the client, it signals that there were no results.
+#[derive(Debug)]
+pub(crate) struct MockError;
+
+impl Display for MockError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.write_str("no result")
+    }
+}
+
+impl StdError for MockError {}
+
+impl ApiError for MockError {
+    fn is_no_result(&self) -> bool {
+        true
+    }
+}
+
+impl CacheError for MockError {}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct MockMeta {
+    pub(crate) expired: bool,
+    pub(crate) absent: bool,
+}
+
+impl CacheEntryMeta for MockMeta {
+    fn is_expired(&self) -> bool {
+        self.expired
+    }
+
+    fn is_absent(&self) -> bool {
+        self.absent
+    }
+}
+
+type Entries = HashMap<String, (Option<Arc<dyn Any + Send + Sync>>, MockMeta)>;
+
+/// Cache keeping its entries in a map, keyed by the debug representation of the key
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MockCache {
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl MockCache {
+    pub(crate) fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub(crate) fn remove<K: Key + Debug>(&self, key: &K) {
+        self.entries.lock().unwrap().remove(&format!("{:?}", key));
+    }
+
+    /// Marks the entry for the given key as expired, if it exists
+    pub(crate) fn expire<K: Key + Debug>(&self, key: &K) {
+        if let Some((_, meta)) = self.entries.lock().unwrap().get_mut(&format!("{:?}", key)) {
+            meta.expired = true;
+        }
+    }
+}
+
+impl Cache for MockCache {
+    type CacheEntryMeta = MockMeta;
+    type Err = MockError;
+}
+
+impl<K> Lookup<K> for MockCache
+where
+    K: Key + Debug,
+    K::Result: Clone + Send + Sync + 'static,
+{
+    fn lookup(&self, key: &K) -> Result<CacheEntry<K::Result, MockMeta>, MockError> {
+        Ok(match self.entries.lock().unwrap().get(&format!("{:?}", key)) {
+            None => CacheEntry::Missing,
+            Some((None, meta)) => CacheEntry::MarkedAbsent(*meta),
+            Some((Some(object), meta)) => CacheEntry::Cached(object.downcast_ref::<K::Result>().unwrap().clone(), *meta),
+        })
+    }
+}
+
+impl<K> Store<K> for MockCache
+where
+    K: Key + Debug,
+    K::Result: Clone + Send + Sync + 'static,
+{
+    fn store(&mut self, obj: &K::Result, key: &K) -> Result<MockMeta, MockError> {
+        let meta = MockMeta::default();
+
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(format!("{:?}", key), (Some(Arc::new(obj.clone())), meta));
+
+        Ok(meta)
+    }
+
+    fn mark_absent(&mut self, key: &K) -> Result<MockMeta, MockError> {
+        let meta = MockMeta { expired: false, absent: true };
+
+        self.entries.lock().unwrap().insert(format!("{:?}", key), (None, meta));
+
+        Ok(meta)
+    }
+}
+
+/// API client answering [`LevelsRequest`]s with pages of levels numbered `0..levels`, recording
+/// every request it gets
+///
+/// All levels are by the creator with user ID `0`, who is included in every response. Their name
+/// is the amount of requests made so far, which allows telling apart data from different requests.
+///
+/// Tests implement [`MakeRequest`] for any other requests they need.
+#[derive(Debug, Clone)]
+pub(crate) struct MockClient {
+    levels: u64,
+    requests: Arc<Mutex<Vec<LevelsRequest>>>,
+}
+
+impl MockClient {
+    pub(crate) fn new(levels: u64) -> MockClient {
+        MockClient {
+            levels,
+            requests: Arc::default(),
+        }
+    }
+
+    pub(crate) fn requests(&self) -> Vec<LevelsRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub(crate) fn requested_pages(&self) -> Vec<u32> {
+        self.requests().iter().map(|request| request.page).collect()
+    }
+}
+
+impl ApiClient for MockClient {
+    type Err = MockError;
+}
+
+impl MakeRequest<LevelsRequest> for MockClient {
+    type Future = FutureResult<Response<Vec<PartialLevel<Option<u64>, u64>>>, MockError>;
+
+    fn make(&self, request: &LevelsRequest) -> Self::Future {
+        let request_count = {
+            let mut requests = self.requests.lock().unwrap();
+
+            requests.push(request.clone());
+            requests.len()
+        };
+
+        let page_size = u64::from(request.page_size.unwrap_or(DEFAULT_PAGE_SIZE));
+        let start = u64::from(request.page) * page_size;
+        let end = self.levels.min(start + page_size);
+
+        if start >= end {
+            return err(MockError)
+        }
+
+        let page_info = PageInfo {
+            total: self.levels as u32,
+            offset: start as u32,
+            page_size: page_size as u32,
+        };
+
+        let creator = Creator {
+            user_id: 0,
+            name: request_count.to_string(),
+            account_id: None,
+        };
+
+        ok(Response::Paged(
+            Box::new(Response::More((start..end).map(level).collect(), vec![Secondary::Creator(creator)])),
+            page_info,
+        ))
+    }
+}
+
+/// Creates a level with the given id and otherwise arbitrary data
+pub(crate) fn level(level_id: u64) -> PartialLevel<Option<u64>, u64> {
+    PartialLevel {
+        level_id,
+        name: format!("Level {}", level_id),
+        description: None,
+        version: 1,
+        creator: 0,
+        difficulty: LevelRating::NotAvailable,
+        downloads: 0,
+        main_song: None,
+        gd_version: GameVersion::Unknown,
+        binary_version: None,
+        likes: 0,
+        length: LevelLength::Tiny,
+        stars: 0,
+        featured: Featured::NotFeatured,
+        copy_of: None,
+        two_player: false,
+        custom_song: None,
+        coin_amount: 0,
+        coins_verified: false,
+        stars_requested: None,
+        low_detail_available: false,
+        is_epic: false,
+        index_43: String::new(),
+        object_amount: None,
+        editor_time: None,
+        editor_time_copies: None,
+        song_ids: Vec::new(),
+        sfx_ids: Vec::new(),
+    }
+}
+
+/// Creates a user with the given account id and otherwise arbitrary data
+pub(crate) fn user(account_id: u64) -> User {
+    User {
+        name: format!("User {}", account_id),
+        user_id: account_id,
+        stars: 0,
+        demons: 0,
+        creator_points: 0,
+        primary_color: Color::Known(0, 0, 0),
+        secondary_color: Color::Known(0, 0, 0),
+        secret_coins: 0,
+        account_id,
+        user_coins: 0,
+        index_18: String::new(),
+        index_19: String::new(),
+        youtube_url: None,
+        cube_index: 0,
+        ship_index: 0,
+        ball_index: 0,
+        ufo_index: 0,
+        wave_index: 0,
+        robot_index: 0,
+        has_glow: false,
+        index_29: String::new(),
+        global_rank: None,
+        index_31: String::new(),
+        spider_index: 0,
+        twitter_url: None,
+        twitch_url: None,
+        diamonds: 0,
+        death_effect_index: 0,
+        mod_level: ModLevel::None,
+        index_50: String::new(),
+    }
+}
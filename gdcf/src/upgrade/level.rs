@@ -1,6 +1,6 @@
 use crate::{
     api::request::{LevelRequest, LevelRequestType, LevelsRequest, Request, SearchFilters, UserRequest},
-    cache::{Cache, CacheEntry, CreatorKey, Lookup, NewgroundsSongKey},
+    cache::{Cache, CacheEntry, CreatorKey, Lookup, NewgroundsSongKey, UserKey},
     upgrade::{Upgradable, UpgradeError, UpgradeQuery},
 };
 use gdcf_model::{
@@ -28,6 +28,10 @@ impl<Song, User> Upgradable<Level<Song, User>> for PartialLevel<Song, User> {
         )
     }
 
+    fn lookup_key(&self) -> Option<Self::LookupKey> {
+        Some(LevelRequest::new(self.level_id))
+    }
+
     fn process_query_result<C: Cache + Lookup<Self::LookupKey>>(
         &self,
         _cache: &C,
@@ -99,6 +103,10 @@ impl<User> Upgradable<Level<Option<NewgroundsSong>, User>> for Level<Option<u64>
         }
     }
 
+    fn lookup_key(&self) -> Option<Self::LookupKey> {
+        self.base.custom_song.map(NewgroundsSongKey)
+    }
+
     fn process_query_result<C: Cache + Lookup<Self::LookupKey>>(
         &self,
         cache: &C,
@@ -159,6 +167,10 @@ impl<User> Upgradable<PartialLevel<Option<NewgroundsSong>, User>> for PartialLev
         }
     }
 
+    fn lookup_key(&self) -> Option<Self::LookupKey> {
+        self.custom_song.map(NewgroundsSongKey)
+    }
+
     fn process_query_result<C: Cache + Lookup<Self::LookupKey>>(
         &self,
         cache: &C,
@@ -215,6 +227,10 @@ impl<Song> Upgradable<Level<Song, Option<Creator>>> for Level<Song, u64> {
         )
     }
 
+    fn lookup_key(&self) -> Option<Self::LookupKey> {
+        Some(CreatorKey(self.base.creator))
+    }
+
     fn process_query_result<C: Cache + Lookup<Self::LookupKey>>(
         &self,
         cache: &C,
@@ -271,6 +287,10 @@ impl<Song> Upgradable<PartialLevel<Song, Option<Creator>>> for PartialLevel<Song
         )
     }
 
+    fn lookup_key(&self) -> Option<Self::LookupKey> {
+        Some(CreatorKey(self.creator))
+    }
+
     fn process_query_result<C: Cache + Lookup<Self::LookupKey>>(
         &self,
         cache: &C,
@@ -308,7 +328,7 @@ impl<Song> Upgradable<PartialLevel<Song, Option<Creator>>> for PartialLevel<Song
 
 impl<Song> Upgradable<Level<Song, Option<User>>> for Level<Song, Option<Creator>> {
     type From = Option<Creator>;
-    type LookupKey = UserRequest;
+    type LookupKey = UserKey;
     type Request = UserRequest;
     type Upgrade = Option<User>;
 
@@ -318,11 +338,15 @@ impl<Song> Upgradable<Level<Song, Option<User>>> for Level<Song, Option<Creator>
         ignored_cached: bool,
     ) -> Result<UpgradeQuery<Self::Request, Self::Upgrade>, UpgradeError<C::Err>> {
         match self.base.creator.as_ref().and_then(|creator| creator.account_id) {
-            Some(account_id) => query_upgrade_option!(cache, UserRequest::new(account_id), UserRequest::new(account_id), ignored_cached),
+            Some(account_id) => query_upgrade_option!(cache, UserKey(account_id), UserRequest::new(account_id), ignored_cached),
             None => Ok(UpgradeQuery::One(None, Some(None))),
         }
     }
 
+    fn lookup_key(&self) -> Option<Self::LookupKey> {
+        self.base.creator.as_ref().and_then(|creator| creator.account_id).map(UserKey)
+    }
+
     fn process_query_result<C: Cache + Lookup<Self::LookupKey>>(
         &self,
         _cache: &C,
@@ -354,7 +378,7 @@ impl<Song> Upgradable<Level<Song, Option<User>>> for Level<Song, Option<Creator>
 
 impl<Song> Upgradable<PartialLevel<Song, Option<User>>> for PartialLevel<Song, Option<Creator>> {
     type From = Option<Creator>;
-    type LookupKey = UserRequest;
+    type LookupKey = UserKey;
     type Request = UserRequest;
     type Upgrade = Option<User>;
 
@@ -364,11 +388,15 @@ impl<Song> Upgradable<PartialLevel<Song, Option<User>>> for PartialLevel<Song, O
         ignored_cached: bool,
     ) -> Result<UpgradeQuery<Self::Request, Self::Upgrade>, UpgradeError<C::Err>> {
         match self.creator.as_ref().and_then(|creator| creator.account_id) {
-            Some(account_id) => query_upgrade_option!(cache, UserRequest::new(account_id), UserRequest::new(account_id), ignored_cached),
+            Some(account_id) => query_upgrade_option!(cache, UserKey(account_id), UserRequest::new(account_id), ignored_cached),
             None => Ok(UpgradeQuery::One(None, Some(None))),
         }
     }
 
+    fn lookup_key(&self) -> Option<Self::LookupKey> {
+        self.creator.as_ref().and_then(|creator| creator.account_id).map(UserKey)
+    }
+
     fn process_query_result<C: Cache + Lookup<Self::LookupKey>>(
         &self,
         _cache: &C,
@@ -414,21 +442,24 @@ fn change_partial_level_song<OldSong, NewSong, User>(
         downloads,
         main_song,
         gd_version,
+        binary_version,
         likes,
         length,
         stars,
         featured,
-        index_31,
+        two_player,
         copy_of,
         coin_amount,
         coins_verified,
         stars_requested,
-        index_40,
+        low_detail_available,
         is_epic,
         index_43,
         object_amount,
-        index_46,
-        index_47,
+        editor_time,
+        editor_time_copies,
+        song_ids,
+        sfx_ids,
         creator,
         custom_song,
     } = partial_level;
@@ -446,21 +477,24 @@ fn change_partial_level_song<OldSong, NewSong, User>(
             downloads,
             main_song,
             gd_version,
+            binary_version,
             likes,
             length,
             stars,
             featured,
-            index_31,
+            two_player,
             copy_of,
             coin_amount,
             coins_verified,
             stars_requested,
-            index_40,
+            low_detail_available,
             is_epic,
             index_43,
             object_amount,
-            index_46,
-            index_47,
+            editor_time,
+            editor_time_copies,
+            song_ids,
+            sfx_ids,
         },
         custom_song,
     )
@@ -479,21 +513,24 @@ fn change_partial_level_user<OldUser, NewUser, Song>(
         downloads,
         main_song,
         gd_version,
+        binary_version,
         likes,
         length,
         stars,
         featured,
-        index_31,
+        two_player,
         copy_of,
         coin_amount,
         coins_verified,
         stars_requested,
-        index_40,
+        low_detail_available,
         is_epic,
         index_43,
         object_amount,
-        index_46,
-        index_47,
+        editor_time,
+        editor_time_copies,
+        song_ids,
+        sfx_ids,
         custom_song,
         creator,
     } = partial_level;
@@ -511,21 +548,24 @@ fn change_partial_level_user<OldUser, NewUser, Song>(
             downloads,
             main_song,
             gd_version,
+            binary_version,
             likes,
             length,
             stars,
             featured,
-            index_31,
+            two_player,
             copy_of,
             coin_amount,
             coins_verified,
             stars_requested,
-            index_40,
+            low_detail_available,
             is_epic,
             index_43,
             object_amount,
-            index_46,
-            index_47,
+            editor_time,
+            editor_time_copies,
+            song_ids,
+            sfx_ids,
         },
         creator,
     )
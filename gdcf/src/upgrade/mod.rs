@@ -2,13 +2,18 @@
 
 use crate::{
     api::{client::MakeRequest, request::Request},
-    cache::{Cache, CacheEntry, CanCache, CreatorKey, Key, Lookup, NewgroundsSongKey, Store},
+    cache::{Cache, CacheEntry, CacheEntryMeta, CanCache, CreatorKey, Key, Lookup, NewgroundsSongKey, Store},
     error::{ApiError, CacheError, Error},
     future::refresh::RefreshCacheFuture,
     Gdcf,
 };
 use futures::{Async, Future};
-use std::fmt::Debug;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    hash::Hash,
+    sync::Arc,
+};
 
 pub mod level;
 pub mod user;
@@ -16,7 +21,12 @@ pub mod user;
 #[derive(Debug)]
 pub enum UpgradeQuery<R, S> {
     One(Option<R>, Option<S>),
-    Many(Vec<UpgradeQuery<R, S>>),
+
+    /// The queries for the elements of a [`Vec`]
+    ///
+    /// The second field marks the elements whose request isn't made, since an earlier element needs
+    /// an equal request. These elements are queried again once that request has updated the cache.
+    Many(Vec<UpgradeQuery<R, S>>, Vec<bool>),
 }
 
 impl<R, S> UpgradeQuery<R, S> {
@@ -26,28 +36,29 @@ impl<R, S> UpgradeQuery<R, S> {
     {
         match self {
             UpgradeQuery::One(_, s) => UpgradeQuery::One(None, s.clone()),
-            UpgradeQuery::Many(inner_queries) => UpgradeQuery::Many(inner_queries.iter().map(UpgradeQuery::clone_upgrades).collect()),
+            UpgradeQuery::Many(inner_queries, deduplicated) =>
+                UpgradeQuery::Many(inner_queries.iter().map(UpgradeQuery::clone_upgrades).collect(), deduplicated.clone()),
         }
     }
 
     fn one(self) -> (Option<R>, Option<S>) {
         match self {
             UpgradeQuery::One(r, s) => (r, s),
-            UpgradeQuery::Many(_) => panic!("Expected UpgradeQuery::One"),
+            UpgradeQuery::Many(..) => panic!("Expected UpgradeQuery::One"),
         }
     }
 
     pub(crate) fn upgrade_cached(&self) -> bool {
         match self {
             UpgradeQuery::One(_, upgrade) => upgrade.is_some(),
-            UpgradeQuery::Many(inner_queries) => inner_queries.iter().all(|query| query.upgrade_cached()),
+            UpgradeQuery::Many(inner_queries, _) => inner_queries.iter().all(|query| query.upgrade_cached()),
         }
     }
 
     fn mitosis(self) -> (UpgradeQuery<R, ()>, UpgradeQuery<(), S>) {
         match self {
             UpgradeQuery::One(left, right) => (UpgradeQuery::One(left, None), UpgradeQuery::One(None, right)),
-            UpgradeQuery::Many(inner_queries) => {
+            UpgradeQuery::Many(inner_queries, deduplicated) => {
                 let mut lefts = Vec::new();
                 let mut rights = Vec::new();
 
@@ -58,7 +69,7 @@ impl<R, S> UpgradeQuery<R, S> {
                     rights.push(right);
                 }
 
-                (UpgradeQuery::Many(lefts), UpgradeQuery::Many(rights))
+                (UpgradeQuery::Many(lefts, deduplicated.clone()), UpgradeQuery::Many(rights, deduplicated))
             },
         }
     }
@@ -68,13 +79,14 @@ impl<R> UpgradeQuery<R, ()> {
     fn recombination<S>(self, other: UpgradeQuery<(), S>) -> UpgradeQuery<R, S> {
         match (self, other) {
             (UpgradeQuery::One(left, _), UpgradeQuery::One(_, right)) => UpgradeQuery::One(left, right),
-            (UpgradeQuery::Many(lefts), UpgradeQuery::Many(rights)) =>
+            (UpgradeQuery::Many(lefts, deduplicated), UpgradeQuery::Many(rights, _)) =>
                 UpgradeQuery::Many(
                     lefts
                         .into_iter()
                         .zip(rights)
                         .map(|(left, right)| left.recombination(right))
                         .collect(),
+                    deduplicated,
                 ),
             _ => panic!("Invalid recombination paramers. Can only combine when both upgrade query objects have the same structure"),
         }
@@ -88,14 +100,30 @@ impl<R: Request, S> UpgradeQuery<R, S> {
         C: Cache + CanCache<R> + Store<CreatorKey> + Store<NewgroundsSongKey>,
     {
         match self {
+            // In offline mode, we make do with whatever (possibly expired) data is cached
             UpgradeQuery::One(request, data) =>
-                UpgradeQueryFuture::One(request.map(|req| FutureState::Pending(RefreshCacheFuture::new(gdcf, req))), data),
-            UpgradeQuery::Many(inner) =>
+                UpgradeQueryFuture::One(
+                    request
+                        .filter(|_| !gdcf.is_offline() || data.is_none())
+                        .map(|req| FutureState::Pending(RefreshCacheFuture::new(gdcf, req))),
+                    data,
+                ),
+            UpgradeQuery::Many(inner, deduplicated) =>
                 UpgradeQueryFuture::Many(
                     inner
                         .into_iter()
-                        .map(|inner_query| FutureState::Pending(inner_query.futurize(gdcf)))
+                        .zip(&deduplicated)
+                        .map(|(inner_query, &deduplicated)| {
+                            // The request of a deduplicated element is made for an earlier element
+                            let inner_query = match inner_query {
+                                UpgradeQuery::One(_, data) if deduplicated => UpgradeQuery::One(None, data),
+                                inner_query => inner_query,
+                            };
+
+                            FutureState::Pending(inner_query.futurize(gdcf))
+                        })
                         .collect(),
+                    deduplicated,
                 ),
         }
     }
@@ -109,7 +137,7 @@ pub(crate) enum FutureState<F: Future> {
 
 pub(crate) enum UpgradeQueryFuture<F: Future, S> {
     One(Option<FutureState<F>>, Option<S>),
-    Many(Vec<FutureState<UpgradeQueryFuture<F, S>>>),
+    Many(Vec<FutureState<UpgradeQueryFuture<F, S>>>, Vec<bool>),
 }
 
 impl<F: Future, S> Debug for UpgradeQueryFuture<F, S>
@@ -121,7 +149,7 @@ where
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             UpgradeQueryFuture::One(fut, s) => f.debug_tuple("One").field(fut).field(s).finish(),
-            UpgradeQueryFuture::Many(inners) => f.debug_tuple("Many").field(inners).finish(),
+            UpgradeQueryFuture::Many(inners, deduplicated) => f.debug_tuple("Many").field(inners).field(deduplicated).finish(),
         }
     }
 }
@@ -130,7 +158,7 @@ impl<F: Future> UpgradeQueryFuture<F, ()> {
     pub(crate) fn recombination<S>(self, other: UpgradeQuery<(), S>) -> UpgradeQueryFuture<F, S> {
         match (self, other) {
             (UpgradeQueryFuture::One(left, _), UpgradeQuery::One(_, right)) => UpgradeQueryFuture::One(left, right),
-            (UpgradeQueryFuture::Many(lefts), UpgradeQuery::Many(rights)) =>
+            (UpgradeQueryFuture::Many(lefts, deduplicated), UpgradeQuery::Many(rights, _)) =>
                 UpgradeQueryFuture::Many(
                     lefts
                         .into_iter()
@@ -142,6 +170,7 @@ impl<F: Future> UpgradeQueryFuture<F, ()> {
                             }
                         })
                         .collect(),
+                    deduplicated,
                 ),
             _ => panic!("Invalid recombination parameters. Can only combine when both upgrade query objects have the same structure"),
         }
@@ -155,7 +184,7 @@ impl<F: Future, S> UpgradeQueryFuture<F, S> {
     {
         match self {
             UpgradeQueryFuture::One(_, s) => UpgradeQuery::One(None, s.clone()),
-            UpgradeQueryFuture::Many(inner_queries) =>
+            UpgradeQueryFuture::Many(inner_queries, deduplicated) =>
                 UpgradeQuery::Many(
                     inner_queries
                         .iter()
@@ -166,6 +195,7 @@ impl<F: Future, S> UpgradeQueryFuture<F, S> {
                             }
                         })
                         .collect(),
+                    deduplicated.clone(),
                 ),
         }
     }
@@ -173,7 +203,7 @@ impl<F: Future, S> UpgradeQueryFuture<F, S> {
     pub(crate) fn mitosis(self) -> (UpgradeQueryFuture<F, ()>, UpgradeQuery<(), S>) {
         match self {
             UpgradeQueryFuture::One(future, data) => (UpgradeQueryFuture::One(future, None), UpgradeQuery::One(None, data)),
-            UpgradeQueryFuture::Many(inner_futures) => {
+            UpgradeQueryFuture::Many(inner_futures, deduplicated) => {
                 let mut futures = Vec::new();
                 let mut upgrades = Vec::new();
 
@@ -195,7 +225,7 @@ impl<F: Future, S> UpgradeQueryFuture<F, S> {
                     upgrades.push(upgrade);
                 }
 
-                (UpgradeQueryFuture::Many(futures), UpgradeQuery::Many(upgrades))
+                (UpgradeQueryFuture::Many(futures, deduplicated.clone()), UpgradeQuery::Many(upgrades, deduplicated))
             },
         }
     }
@@ -215,7 +245,7 @@ impl<F: Future, S> Future for UpgradeQueryFuture<F, S> {
             UpgradeQueryFuture::One(Some(FutureState::Done(_)), _) => unreachable!(), /* can be constructed, but we don't poll this */
             // anymore! (see below)
             UpgradeQueryFuture::One(None, data) => Ok(Async::Ready(UpgradeQuery::One(None, data.take()))),
-            UpgradeQueryFuture::Many(inner) => {
+            UpgradeQueryFuture::Many(inner, deduplicated) => {
                 let mut all_done = true;
 
                 for i in 0..inner.len() {
@@ -235,7 +265,7 @@ impl<F: Future, S> Future for UpgradeQueryFuture<F, S> {
                     log::debug!("All requests of upgrade query future done!");
 
                     Ok(Async::Ready(UpgradeQuery::Many(
-                        std::mem::replace(inner, Vec::new())
+                        std::mem::take(inner)
                             .into_iter()
                             .map(|future_state| {
                                 match future_state {
@@ -244,6 +274,7 @@ impl<F: Future, S> Future for UpgradeQueryFuture<F, S> {
                                 }
                             })
                             .collect(),
+                        std::mem::take(deduplicated),
                     )))
                 } else {
                     Ok(Async::NotReady)
@@ -274,6 +305,70 @@ impl<C: CacheError> From<C> for UpgradeError<C> {
     }
 }
 
+impl<R, S> UpgradeQuery<R, S> {
+    /// Turns this query into a resolved one without making any of its requests
+    ///
+    /// Whatever upgrade is cached is used, upgrades without anything cached are processed as if
+    /// their request had resulted in [`CacheEntry::Missing`].
+    fn resolve_cached<T, M: CacheEntryMeta>(self) -> UpgradeQuery<CacheEntry<T, M>, S> {
+        match self {
+            UpgradeQuery::One(_, Some(upgrade)) => UpgradeQuery::One(None, Some(upgrade)),
+            UpgradeQuery::One(_, None) => UpgradeQuery::One(Some(CacheEntry::Missing), None),
+            UpgradeQuery::Many(inner_queries, deduplicated) =>
+                UpgradeQuery::Many(
+                    inner_queries.into_iter().map(UpgradeQuery::resolve_cached).collect(),
+                    vec![false; deduplicated.len()],
+                ),
+        }
+    }
+}
+
+/// Queries the upgrade of an element of a [`Vec`] whose request was made for an earlier element
+///
+/// At this point, the request has updated the cache, so whatever is cached now is used.
+fn requery<Into, U, C>(
+    to_upgrade: &U,
+    cache: &C,
+) -> Result<UpgradeQuery<CacheEntry<<U::Request as Request>::Result, C::CacheEntryMeta>, U::Upgrade>, UpgradeError<C::Err>>
+where
+    U: Upgradable<Into>,
+    C: Cache + Lookup<U::LookupKey>,
+{
+    Ok(to_upgrade.query_upgrade(cache, false)?.resolve_cached())
+}
+
+/// Cache serving lookups from entries that were looked up beforehand, and delegating everything
+/// else to the wrapped cache
+///
+/// Every lookup of a prefetched key is served a copy of its entry. See [`Lookup::lookup_many`].
+struct Prefetched<C: Cache, K: Key> {
+    cache: C,
+    entries: Arc<HashMap<K, CacheEntry<K::Result, C::CacheEntryMeta>>>,
+}
+
+impl<C: Cache, K: Key> Clone for Prefetched<C, K> {
+    fn clone(&self) -> Self {
+        Prefetched {
+            cache: self.cache.clone(),
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+impl<C: Cache, K: Key> Cache for Prefetched<C, K> {
+    type CacheEntryMeta = C::CacheEntryMeta;
+    type Err = C::Err;
+}
+
+impl<C: Lookup<K>, K: Key + Eq + Hash> Lookup<K> for Prefetched<C, K> {
+    fn lookup(&self, key: &K) -> Result<CacheEntry<K::Result, Self::CacheEntryMeta>, Self::Err> {
+        match self.entries.get(key) {
+            Some(entry) => Ok(entry.clone()),
+            None => self.cache.lookup(key),
+        }
+    }
+}
+
 /// Trait for upgrading objects
 ///
 /// Implementing this trait for some type means that instances of that type can be upgraded into
@@ -291,7 +386,9 @@ pub trait Upgradable<Into>: Sized {
     type From;
 
     /// The request that has to be made for the upgrade to work
-    type Request: Request;
+    ///
+    /// When upgrading a [`Vec`], elements requiring equal requests share a single request.
+    type Request: Request + Eq;
 
     /// The object [`Self::From`] is being replaced by. If the whole object is upgraded, this should
     /// be `Into`
@@ -302,7 +399,7 @@ pub trait Upgradable<Into>: Sized {
     ///
     /// If no lookup beyond one of [`Upgrdable::Request`] is required, set this to the never type or
     /// `Upgradable::Request`.
-    type LookupKey: Key;
+    type LookupKey: Key + Eq + Hash;
 
     /// Determines how this upgrade has to be done by either producing the request that needs to be
     /// made to retrieve the data needed, or returning the [`Upgradable::Upgrade`] object.
@@ -330,6 +427,24 @@ pub trait Upgradable<Into>: Sized {
         ignored_cached: bool,
     ) -> Result<UpgradeQuery<Self::Request, Self::Upgrade>, UpgradeError<C::Err>>;
 
+    /// The key [`Upgradable::query_upgrade`] looks up in the cache, if any
+    ///
+    /// When upgrading a [`Vec`], the keys of all elements are looked up at once (see
+    /// [`Lookup::lookup_many`]). Defaults to [`None`], in which case the element's lookups are done
+    /// individually.
+    fn lookup_key(&self) -> Option<Self::LookupKey> {
+        None
+    }
+
+    /// The amount of objects on the page of results this is, if it is one
+    ///
+    /// Upgrading doesn't change the amount of objects on a page, so this is what tells streams of
+    /// upgraded pages whether they reached the last page. Defaults to [`None`], [`Vec`]s return their
+    /// length.
+    fn page_len(&self) -> Option<usize> {
+        None
+    }
+
     fn process_query_result<C: Cache + Lookup<Self::LookupKey>>(
         &self,
         cache: &C,
@@ -359,15 +474,47 @@ where
         // element to upgrade. We store this information in the upgrade list, which will contain a [`None`]
         // entry whenever an upgrade needed a request and we rely on the invariant that neither the upgrades
         // vector, nor the request vector, gets reordered.
-        let mut queries = Vec::new();
+        //
+        // The cache lookups of all elements are done at once, looking up every key only once
+        let keys: Vec<_> = self
+            .iter()
+            .filter_map(Upgradable::lookup_key)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let entries = cache.lookup_many(&keys)?;
+        let cache = Prefetched {
+            cache: cache.clone(),
+            entries: Arc::new(keys.into_iter().zip(entries).collect()),
+        };
+
+        // Often, multiple elements need the same request (for instance, when upgrading a page of levels by
+        // the same creator). It is only made for the first of them, the others are marked as deduplicated
+        // and get queried again once the request has updated the cache (see `process_query_result`).
+        let mut queries: Vec<UpgradeQuery<Self::Request, Self::Upgrade>> = Vec::new();
+        let mut deduplicated = Vec::new();
 
         for to_query in self.iter() {
-            let query = to_query.query_upgrade(cache, ignore_cached)?;
-
+            let query = to_query.query_upgrade(&cache, ignore_cached)?;
+
+            deduplicated.push(match query {
+                UpgradeQuery::One(Some(ref request), _) =>
+                    queries.iter().any(|earlier| {
+                        match earlier {
+                            UpgradeQuery::One(Some(earlier), _) => earlier == request,
+                            _ => false,
+                        }
+                    }),
+                _ => false,
+            });
             queries.push(query);
         }
 
-        Ok(UpgradeQuery::Many(queries))
+        Ok(UpgradeQuery::Many(queries, deduplicated))
+    }
+
+    fn page_len(&self) -> Option<usize> {
+        Some(self.len())
     }
 
     fn process_query_result<C: Cache + Lookup<Self::LookupKey>>(
@@ -377,18 +524,26 @@ where
     ) -> Result<UpgradeQuery<(), Self::Upgrade>, UpgradeError<C::Err>> {
         match resolved_query {
             UpgradeQuery::One(..) => panic!(),
-            UpgradeQuery::Many(inner_queries) =>
+            UpgradeQuery::Many(inner_queries, deduplicated) =>
                 Ok(UpgradeQuery::Many(
                     self.iter()
                         .zip(inner_queries)
-                        .map(|(result, query)| result.process_query_result(cache, query))
+                        .zip(&deduplicated)
+                        .map(|((to_upgrade, query), &deduplicated)| {
+                            if deduplicated {
+                                to_upgrade.process_query_result(cache, requery(to_upgrade, cache)?)
+                            } else {
+                                to_upgrade.process_query_result(cache, query)
+                            }
+                        })
                         .collect::<Result<_, _>>()?,
+                    vec![false; deduplicated.len()],
                 )),
         }
     }
 
     fn upgrade<State>(self, upgrade: UpgradeQuery<State, Self::Upgrade>) -> (Vec<Into>, UpgradeQuery<State, Self::From>) {
-        if let UpgradeQuery::Many(upgrades) = upgrade {
+        if let UpgradeQuery::Many(upgrades, deduplicated) = upgrade {
             let mut upgraded = Vec::new();
             let mut downgrades = Vec::new();
 
@@ -399,14 +554,14 @@ where
                 downgrades.push(downgrade);
             }
 
-            (upgraded, UpgradeQuery::Many(downgrades))
+            (upgraded, UpgradeQuery::Many(downgrades, deduplicated))
         } else {
             panic!("Attempt to upgrade list of upgradables with a single upgrade")
         }
     }
 
     fn downgrade<State>(upgraded: Vec<Into>, downgrade: UpgradeQuery<State, Self::From>) -> (Self, UpgradeQuery<State, Self::Upgrade>) {
-        if let UpgradeQuery::Many(downgrades) = downgrade {
+        if let UpgradeQuery::Many(downgrades, deduplicated) = downgrade {
             let mut downgraded = Vec::new();
             let mut upgrades = Vec::new();
 
@@ -417,9 +572,176 @@ where
                 upgrades.push(upgrade);
             }
 
-            (downgraded, UpgradeQuery::Many(upgrades))
+            (downgraded, UpgradeQuery::Many(upgrades, deduplicated))
         } else {
             panic!("Attempt to downgrade list of upgradables with a single downgrade")
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Upgradable, UpgradeQuery};
+    use crate::{
+        api::{
+            client::{MakeRequest, Response},
+            request::{LevelRequest, LevelsRequest, UserRequest},
+            ApiClient,
+        },
+        cache::{Cache, CacheEntry, CreatorKey, Lookup, Store, UserKey},
+        mock::{self, MockCache, MockClient, MockError, MockMeta},
+        Gdcf,
+    };
+    use futures::{future::FutureResult, Future};
+    use gdcf_model::{
+        level::{Level, PartialLevel},
+        user::{Creator, User},
+    };
+    use std::sync::{Arc, Mutex};
+
+    type UpgradedLevel = PartialLevel<Option<u64>, Option<Creator>>;
+
+    fn upgrade_page(gdcf: &Gdcf<MockClient, MockCache>, force_refresh: bool) -> Vec<UpgradedLevel> {
+        match gdcf
+            .levels(LevelsRequest::default(), force_refresh)
+            .unwrap()
+            .upgrade_all::<UpgradedLevel>()
+            .wait()
+            .unwrap()
+        {
+            CacheEntry::Cached(levels, _) => levels,
+            entry => panic!("unexpected cache entry {:?}", entry),
+        }
+    }
+
+    #[test]
+    fn page_by_one_creator_makes_one_request() {
+        let client = MockClient::new(10);
+        let cache = MockCache::default();
+        let gdcf = Gdcf::new(client.clone(), cache.clone());
+
+        // Pretend the levels request didn't contain the creator, so that upgrading needs a request
+        gdcf.levels(LevelsRequest::default(), false).unwrap().wait().unwrap();
+        cache.remove(&CreatorKey(0));
+
+        let levels = upgrade_page(&gdcf, false);
+
+        assert_eq!(levels.len(), 10);
+        assert!(levels.iter().all(|level| level.creator.as_ref().map(|creator| &creator.name[..]) == Some("2")));
+        assert_eq!(client.requests().len(), 2);
+    }
+
+    #[test]
+    fn forced_refresh_updates_every_deduplicated_element() {
+        let client = MockClient::new(10);
+        let gdcf = Gdcf::new(client.clone(), MockCache::default());
+
+        let levels = upgrade_page(&gdcf, true);
+
+        // The levels request caches the creator as "1", which the forced creator request refreshes to "2"
+        assert!(levels.iter().all(|level| level.creator.as_ref().map(|creator| &creator.name[..]) == Some("2")));
+        assert_eq!(client.requests().len(), 2);
+    }
+
+    /// Client panicking on every request, for checking that something is served from the cache
+    #[derive(Debug, Clone)]
+    struct OfflineClient;
+
+    impl ApiClient for OfflineClient {
+        type Err = MockError;
+    }
+
+    impl MakeRequest<LevelsRequest> for OfflineClient {
+        type Future = FutureResult<Response<Vec<PartialLevel<Option<u64>, u64>>>, MockError>;
+
+        fn make(&self, request: &LevelsRequest) -> Self::Future {
+            panic!("Unexpected request {}", request)
+        }
+    }
+
+    impl MakeRequest<UserRequest> for OfflineClient {
+        type Future = FutureResult<Response<User>, MockError>;
+
+        fn make(&self, request: &UserRequest) -> Self::Future {
+            panic!("Unexpected request {}", request)
+        }
+    }
+
+    #[test]
+    fn cached_user_upgrades_without_request() {
+        let mut cache = MockCache::default();
+        let creator = Creator {
+            user_id: 0,
+            name: "creator".to_string(),
+            account_id: Some(5),
+        };
+
+        cache.store(&vec![mock::level(0), mock::level(1)], &LevelsRequest::default()).unwrap();
+        cache.store(&creator, &CreatorKey(0)).unwrap();
+        cache.store(&mock::user(5), &UserKey(5)).unwrap();
+
+        let gdcf = Gdcf::new(OfflineClient, cache);
+
+        let levels = match gdcf
+            .levels(LevelsRequest::default(), false)
+            .unwrap()
+            .upgrade_all::<UpgradedLevel>()
+            .upgrade_all::<PartialLevel<Option<u64>, Option<User>>>()
+            .wait()
+            .unwrap()
+        {
+            CacheEntry::Cached(levels, _) => levels,
+            entry => panic!("unexpected cache entry {:?}", entry),
+        };
+
+        assert_eq!(levels.len(), 2);
+        assert!(levels.iter().all(|level| level.creator == Some(mock::user(5))));
+    }
+
+    /// Cache that has nothing cached, recording the keys passed to [`Lookup::lookup_many`]
+    #[derive(Debug, Clone, Default)]
+    struct RecordingCache {
+        lookup_many_keys: Arc<Mutex<Vec<Vec<LevelRequest>>>>,
+    }
+
+    impl Cache for RecordingCache {
+        type CacheEntryMeta = MockMeta;
+        type Err = MockError;
+    }
+
+    impl Lookup<LevelRequest> for RecordingCache {
+        fn lookup(&self, key: &LevelRequest) -> Result<CacheEntry<Level<Option<u64>, u64>, MockMeta>, MockError> {
+            panic!("Unexpected lookup of {}, which should have been prefetched", key)
+        }
+
+        fn lookup_many(&self, keys: &[LevelRequest]) -> Result<Vec<CacheEntry<Level<Option<u64>, u64>, MockMeta>>, MockError> {
+            self.lookup_many_keys.lock().unwrap().push(keys.to_vec());
+
+            Ok(keys.iter().map(|_| CacheEntry::Missing).collect())
+        }
+    }
+
+    #[test]
+    fn shared_lookup_keys_are_looked_up_once() {
+        let cache = RecordingCache::default();
+        let levels = vec![mock::level(1), mock::level(2), mock::level(1), mock::level(1)];
+
+        let query = Upgradable::<Vec<Level<Option<u64>, u64>>>::query_upgrade(&levels, &cache, false).unwrap();
+
+        let mut looked_up = cache.lookup_many_keys.lock().unwrap().clone();
+
+        assert_eq!(looked_up.len(), 1);
+
+        looked_up[0].sort_by_key(|request| request.level_id);
+
+        assert_eq!(looked_up[0], vec![LevelRequest::new(1), LevelRequest::new(2)]);
+
+        match query {
+            UpgradeQuery::Many(queries, deduplicated) => {
+                assert_eq!(queries.len(), 4);
+                assert_eq!(deduplicated, vec![false, false, true, true]);
+            },
+            query => panic!("unexpected query {:?}", query),
+        }
+    }
+}
@@ -1,13 +1,13 @@
 use crate::{
     api::request::UserRequest,
-    cache::{Cache, CacheEntry, Lookup},
+    cache::{Cache, CacheEntry, Lookup, UserKey},
     upgrade::{Upgradable, UpgradeError, UpgradeQuery},
 };
 use gdcf_model::user::{SearchedUser, User};
 
 impl Upgradable<User> for SearchedUser {
     type From = SearchedUser;
-    type LookupKey = UserRequest;
+    type LookupKey = UserKey;
     type Request = UserRequest;
     type Upgrade = User;
 
@@ -18,12 +18,16 @@ impl Upgradable<User> for SearchedUser {
     ) -> Result<UpgradeQuery<Self::Request, Self::Upgrade>, UpgradeError<C::Err>> {
         query_upgrade!(
             cache,
-            UserRequest::new(self.account_id),
+            UserKey(self.account_id),
             UserRequest::new(self.account_id),
             ignored_cached
         )
     }
 
+    fn lookup_key(&self) -> Option<Self::LookupKey> {
+        Some(UserKey(self.account_id))
+    }
+
     fn process_query_result<C: Cache + Lookup<Self::LookupKey>>(
         &self,
         _cache: &C,
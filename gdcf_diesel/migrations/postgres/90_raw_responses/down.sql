@@ -0,0 +1 @@
+DROP TABLE raw_response;
\ No newline at end of file
@@ -0,0 +1,6 @@
+CREATE TABLE raw_response (
+    request_kind TEXT NOT NULL,
+    request_key BIGINT NOT NULL,
+    body TEXT NOT NULL,
+    PRIMARY KEY (request_kind, request_key)
+);
@@ -0,0 +1,7 @@
+ALTER TABLE partial_level ADD COLUMN index_31 TEXT;
+ALTER TABLE partial_level ADD COLUMN index_40 TEXT;
+UPDATE partial_level SET index_31 = CASE WHEN two_player THEN '1' ELSE '0' END, index_40 = CASE WHEN low_detail_available THEN '1' ELSE '0' END;
+ALTER TABLE partial_level DROP COLUMN two_player;
+ALTER TABLE partial_level DROP COLUMN low_detail_available;
+ALTER TABLE partial_level DROP COLUMN song_ids;
+ALTER TABLE partial_level DROP COLUMN sfx_ids;
@@ -0,0 +1,7 @@
+ALTER TABLE partial_level ADD COLUMN two_player BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE partial_level ADD COLUMN low_detail_available BOOLEAN NOT NULL DEFAULT FALSE;
+UPDATE partial_level SET two_player = COALESCE(index_31 = '1', FALSE), low_detail_available = COALESCE(index_40 = '1', FALSE);
+ALTER TABLE partial_level DROP COLUMN index_31;
+ALTER TABLE partial_level DROP COLUMN index_40;
+ALTER TABLE partial_level ADD COLUMN song_ids TEXT;
+ALTER TABLE partial_level ADD COLUMN sfx_ids TEXT;
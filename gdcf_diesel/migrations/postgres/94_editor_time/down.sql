@@ -0,0 +1,5 @@
+ALTER TABLE partial_level ADD COLUMN index_46 TEXT;
+ALTER TABLE partial_level ADD COLUMN index_47 TEXT;
+UPDATE partial_level SET index_46 = CAST(COALESCE(editor_time, 0) AS TEXT), index_47 = CAST(COALESCE(editor_time_copies, 0) AS TEXT);
+ALTER TABLE partial_level DROP COLUMN editor_time;
+ALTER TABLE partial_level DROP COLUMN editor_time_copies;
@@ -0,0 +1,5 @@
+ALTER TABLE partial_level ADD COLUMN editor_time BIGINT;
+ALTER TABLE partial_level ADD COLUMN editor_time_copies BIGINT;
+UPDATE partial_level SET editor_time = NULLIF(CAST(NULLIF(index_46, '') AS BIGINT), 0), editor_time_copies = NULLIF(CAST(NULLIF(index_47, '') AS BIGINT), 0);
+ALTER TABLE partial_level DROP COLUMN index_46;
+ALTER TABLE partial_level DROP COLUMN index_47;
@@ -0,0 +1 @@
+ALTER TABLE level_list_meta DROP COLUMN key_version;
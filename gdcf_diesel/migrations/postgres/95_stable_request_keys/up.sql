@@ -0,0 +1,4 @@
+-- Keys of level lists used to be derived with a hasher whose output may change between Rust
+-- releases. The rows stored under these keys are marked with version 0, and discarded when the cache
+-- is initialized (see `REQUEST_KEY_VERSION`)
+ALTER TABLE level_list_meta ADD COLUMN key_version BIGINT NOT NULL DEFAULT 0;
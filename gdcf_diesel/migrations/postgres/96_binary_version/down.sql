@@ -0,0 +1 @@
+ALTER TABLE partial_level DROP COLUMN binary_version;
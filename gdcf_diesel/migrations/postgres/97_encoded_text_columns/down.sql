@@ -0,0 +1,2 @@
+ALTER TABLE partial_level ALTER COLUMN description TYPE TEXT USING convert_from(description, 'UTF8');
+ALTER TABLE raw_response ALTER COLUMN body TYPE TEXT USING convert_from(body, 'UTF8');
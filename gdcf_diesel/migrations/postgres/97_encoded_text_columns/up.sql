@@ -0,0 +1,3 @@
+-- Descriptions and raw responses are passed through the cache's BlobCodec, so they are stored as blobs
+ALTER TABLE partial_level ALTER COLUMN description TYPE BYTEA USING convert_to(description, 'UTF8');
+ALTER TABLE raw_response ALTER COLUMN body TYPE BYTEA USING convert_to(body, 'UTF8');
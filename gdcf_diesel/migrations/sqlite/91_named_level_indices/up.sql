@@ -0,0 +1,6 @@
+-- SQLite cannot drop columns, so index_31 and index_40 are left in place (but are no longer used)
+ALTER TABLE partial_level ADD COLUMN two_player BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE partial_level ADD COLUMN low_detail_available BOOLEAN NOT NULL DEFAULT FALSE;
+UPDATE partial_level SET two_player = COALESCE(index_31 = '1', FALSE), low_detail_available = COALESCE(index_40 = '1', FALSE);
+ALTER TABLE partial_level ADD COLUMN song_ids TEXT;
+ALTER TABLE partial_level ADD COLUMN sfx_ids TEXT;
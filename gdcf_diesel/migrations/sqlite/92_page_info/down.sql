@@ -0,0 +1 @@
+DROP TABLE page_info;
\ No newline at end of file
@@ -0,0 +1,6 @@
+CREATE TABLE page_info (
+    request_key INTEGER PRIMARY KEY NOT NULL,
+    total INTEGER NOT NULL,
+    page_offset INTEGER NOT NULL,
+    page_size INTEGER NOT NULL
+);
@@ -0,0 +1 @@
+DROP INDEX creator_account_id;
\ No newline at end of file
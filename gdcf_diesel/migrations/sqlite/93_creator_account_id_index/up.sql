@@ -0,0 +1 @@
+CREATE INDEX creator_account_id ON creator(account_id);
\ No newline at end of file
@@ -0,0 +1,4 @@
+-- SQLite cannot drop columns, so index_46 and index_47 are left in place (but are no longer used)
+ALTER TABLE partial_level ADD COLUMN editor_time INTEGER;
+ALTER TABLE partial_level ADD COLUMN editor_time_copies INTEGER;
+UPDATE partial_level SET editor_time = NULLIF(CAST(index_46 AS INTEGER), 0), editor_time_copies = NULLIF(CAST(index_47 AS INTEGER), 0);
@@ -0,0 +1 @@
+-- SQLite cannot drop columns, so key_version is left in place
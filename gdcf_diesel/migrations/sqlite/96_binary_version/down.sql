@@ -0,0 +1 @@
+-- SQLite cannot drop columns, so binary_version is left in place
@@ -0,0 +1 @@
+ALTER TABLE partial_level ADD COLUMN binary_version INTEGER;
@@ -0,0 +1,2 @@
+UPDATE partial_level SET description = CAST(description AS TEXT);
+UPDATE raw_response SET body = CAST(body AS TEXT);
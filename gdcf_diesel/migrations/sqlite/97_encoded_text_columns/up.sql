@@ -0,0 +1,4 @@
+-- Descriptions and raw responses are passed through the cache's BlobCodec, so they are stored as blobs.
+-- SQLite doesn't enforce column types, so only the existing values need converting.
+UPDATE partial_level SET description = CAST(description AS BLOB);
+UPDATE raw_response SET body = CAST(body AS BLOB);
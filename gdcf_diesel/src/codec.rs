@@ -0,0 +1,60 @@
+//! Module containing the hook for transforming blobs and large text columns before they are written
+//! to the database
+
+use std::{borrow::Cow, fmt::Debug};
+
+/// Transformation applied to level data, level descriptions and raw response bodies right before
+/// they are stored, and undone right after they are looked up
+///
+/// The servers already provide level data `DEFLATE` compressed, so by default everything is stored
+/// as-is. Implementations can be used to, for example, compress descriptions and raw responses, or
+/// to recompress level data using a stronger algorithm.
+///
+/// Since the database doesn't record which codec wrote a row, a cache must always be used with the
+/// same codec.
+pub trait BlobCodec: Debug + Send + Sync {
+    /// Transforms the given data into the form it is stored in
+    fn encode<'a>(&self, data: &'a [u8]) -> Cow<'a, [u8]>;
+
+    /// Restores data previously transformed by [`BlobCodec::encode`]
+    fn decode(&self, data: Vec<u8>) -> std::io::Result<Vec<u8>>;
+}
+
+/// The [`BlobCodec`] used by default, which stores everything as provided by the servers
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Identity;
+
+impl BlobCodec for Identity {
+    fn encode<'a>(&self, data: &'a [u8]) -> Cow<'a, [u8]> {
+        Cow::Borrowed(data)
+    }
+
+    fn decode(&self, data: Vec<u8>) -> std::io::Result<Vec<u8>> {
+        Ok(data)
+    }
+}
+
+/// Encodes the given text using the given codec
+pub(crate) fn encode_text<'a>(codec: &dyn BlobCodec, text: &'a str) -> Cow<'a, [u8]> {
+    codec.encode(text.as_bytes())
+}
+
+/// Restores text previously encoded by [`encode_text`]
+pub(crate) fn decode_text(codec: &dyn BlobCodec, data: Vec<u8>) -> std::io::Result<String> {
+    String::from_utf8(codec.decode(data)?).map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+}
+
+/// Conversion between an object and the row it is stored as
+///
+/// Most objects are stored exactly as they are (see `stored_as_is!`). Objects with columns that are
+/// passed through the cache's [`BlobCodec`] are converted into a separate row type instead.
+pub(crate) trait Stored: Sized {
+    /// The type of the row this object is stored as
+    type Row: Clone;
+
+    /// Converts this object into the row it is stored as
+    fn to_row(&self, codec: &dyn BlobCodec) -> Cow<'_, Self::Row>;
+
+    /// Restores an object from the row it was stored as
+    fn from_row(row: Self::Row, codec: &dyn BlobCodec) -> std::io::Result<Self>;
+}
@@ -26,5 +26,66 @@ diesel_stuff! {
 }
 meta_table!(creator_meta, user_id);
 
+stored_as_is!(Creator);
+
 store_simply!(CreatorKey, creator, creator_meta, user_id);
 lookup_simply!(CreatorKey, creator, creator_meta, user_id);
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::creator;
+    use crate::tests::test_cache;
+    use diesel::RunQueryDsl;
+    use gdcf::cache::{CacheEntry, CreatorKey, Lookup, Store};
+    use gdcf_model::user::Creator;
+
+    #[test]
+    fn contains_only_checks_metadata() {
+        let mut cache = test_cache();
+        let creator = Creator {
+            user_id: 1,
+            name: "Riot".to_string(),
+            account_id: Some(37415),
+        };
+
+        assert!(!cache.contains(&CreatorKey(1)).unwrap());
+
+        cache.store(&creator, &CreatorKey(1)).unwrap();
+        cache.mark_absent(&CreatorKey(2)).unwrap();
+
+        assert!(cache.contains(&CreatorKey(1)).unwrap());
+        assert!(cache.contains(&CreatorKey(2)).unwrap());
+        assert!(!cache.contains(&CreatorKey(3)).unwrap());
+
+        // Without the stored object, a lookup can't produce the cached entry anymore, but the
+        // existence check never looks at it
+        diesel::delete(creator::table).execute(&cache.pool.get().unwrap()).unwrap();
+
+        assert!(cache.contains(&CreatorKey(1)).unwrap());
+
+        if let Ok(CacheEntry::Cached(..)) = cache.lookup(&CreatorKey(1)) {
+            panic!("lookup found the deleted creator")
+        }
+    }
+
+    #[test]
+    fn lookup_many_serves_every_key_in_order() {
+        let mut cache = test_cache();
+        let creator = Creator {
+            user_id: 1,
+            name: "Riot".to_string(),
+            account_id: Some(37415),
+        };
+
+        cache.store(&creator, &CreatorKey(1)).unwrap();
+        cache.mark_absent(&CreatorKey(2)).unwrap();
+
+        let entries = cache.lookup_many(&[CreatorKey(1), CreatorKey(3), CreatorKey(2), CreatorKey(1)]).unwrap();
+
+        assert_eq!(entries.len(), 4);
+        assert!(matches!(&entries[0], CacheEntry::Cached(cached, _) if cached.name == "Riot"));
+        assert!(matches!(entries[1], CacheEntry::Missing));
+        assert!(matches!(entries[2], CacheEntry::MarkedAbsent(_)));
+        assert!(matches!(&entries[3], CacheEntry::Cached(cached, _) if cached.name == "Riot"));
+    }
+}
@@ -1,14 +1,142 @@
 use crate::level::SemiLevel;
 use derive_more::Display;
 use gdcf::{
-    api::request::{LevelRequest, LevelsRequest, UserRequest},
-    cache::{CreatorKey, Key, NewgroundsSongKey},
-};
-use gdcf_model::level::PartialLevel;
-use std::{
-    collections::hash_map::DefaultHasher,
-    hash::{Hash, Hasher},
+    api::request::{
+        level::{CompletionFilter, SearchFilters, SongFilter},
+        LevelRequest, LevelsRequest, SongRequest, UserRequest,
+    },
+    cache::{CreatorKey, Key, NewgroundsSongKey, UserKey},
 };
+use gdcf_model::level::{DemonRating, LevelLength, LevelRating, PartialLevel};
+
+/// The version of the scheme used to derive database keys from requests
+///
+/// Requests without a natural key (like [`LevelsRequest`]) are stored under a hash of the values
+/// they send to the servers. Whenever the fields that go into that hash, or the way it's computed,
+/// change, this needs to be bumped. The version a row's key was derived with is stored alongside it,
+/// so rows stored under an older scheme can be told apart from current ones.
+///
+/// Rows stored under an older scheme (including rows stored before keys were versioned, which have
+/// version `0`) are discarded when the cache is initialized.
+pub const REQUEST_KEY_VERSION: u64 = 1;
+
+/// 64-bit FNV-1a hash over explicitly encoded values
+///
+/// Unlike `DefaultHasher`, whose algorithm may change between Rust releases, this always produces
+/// the same hash for the same input. All integers are written as little-endian bytes, so keys are
+/// also identical across platforms. This deliberately doesn't implement [`Hasher`], whose integer
+/// methods use native endianness, and into which `Hash` impls write implementation details like
+/// enum discriminants.
+struct StableHasher(u64);
+
+impl StableHasher {
+    fn new() -> StableHasher {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn write_bool(&mut self, value: bool) {
+        self.write(&[value as u8])
+    }
+
+    fn write_i32(&mut self, value: i32) {
+        self.write(&value.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes())
+    }
+
+    /// Writes the given bytes prefixed with their length, so that adjacent variable length values
+    /// cannot be confused with each other
+    fn write_prefixed(&mut self, bytes: &[u8]) {
+        self.write_u64(bytes.len() as u64);
+        self.write(bytes)
+    }
+}
+
+/// The value of the given [`LevelRating`] in requests
+fn rating_value(rating: LevelRating) -> i32 {
+    match rating {
+        LevelRating::Auto => -3,
+        LevelRating::Demon(_) => -2,
+        LevelRating::NotAvailable => -1,
+        LevelRating::Easy => 1,
+        LevelRating::Normal => 2,
+        LevelRating::Hard => 3,
+        LevelRating::Harder => 4,
+        LevelRating::Insane => 5,
+        LevelRating::Unknown(value) => value,
+    }
+}
+
+/// The value of the given [`DemonRating`] in requests
+fn demon_rating_value(rating: DemonRating) -> i32 {
+    match rating {
+        DemonRating::Easy => 1,
+        DemonRating::Medium => 2,
+        DemonRating::Hard => 3,
+        DemonRating::Insane => 4,
+        DemonRating::Extreme => 5,
+        DemonRating::Unknown(value) => value,
+    }
+}
+
+/// The value of the given [`LevelLength`] in requests
+fn length_value(length: LevelLength) -> i32 {
+    match length {
+        LevelLength::Tiny => 0,
+        LevelLength::Short => 1,
+        LevelLength::Medium => 2,
+        LevelLength::Long => 3,
+        LevelLength::ExtraLong => 4,
+        LevelLength::Unknown(value) => value,
+    }
+}
+
+fn hash_search_filters(filters: &SearchFilters, state: &mut StableHasher) {
+    match &filters.completion {
+        CompletionFilter::None => state.write(&[0]),
+        CompletionFilter::List { ids, include } => {
+            state.write(&[if *include { 1 } else { 2 }]);
+            state.write_u64(ids.len() as u64);
+
+            for id in ids {
+                state.write_u64(*id)
+            }
+        },
+    }
+
+    state.write_bool(filters.featured);
+    state.write_bool(filters.original);
+    state.write_bool(filters.two_player);
+    state.write_bool(filters.coins);
+    state.write_bool(filters.epic);
+    state.write_bool(filters.rated);
+
+    match filters.song {
+        None => state.write(&[0]),
+        Some(SongFilter::Main(id)) => state.write(&[1, id]),
+        Some(SongFilter::Custom(id)) => {
+            state.write(&[2]);
+            state.write_u64(id)
+        },
+    }
+}
 
 pub(crate) trait DatabaseKey: Key {
     fn database_key(&self) -> i64;
@@ -58,25 +186,74 @@ impl DatabaseKey for NewgroundsSongKey {
     }
 }
 
+impl DatabaseKey for SongRequest {
+    fn database_key(&self) -> i64 {
+        self.song_id as i64
+    }
+}
+
 impl DatabaseKey for UserRequest {
     fn database_key(&self) -> i64 {
         self.user as i64
     }
 }
 
+impl DatabaseKey for UserKey {
+    fn database_key(&self) -> i64 {
+        self.0 as i64
+    }
+}
+
 impl DatabaseKey for LevelsRequest {
     fn database_key(&self) -> i64 {
-        let mut state = DefaultHasher::new();
+        let mut state = StableHasher::new();
+
+        state.write_u64(REQUEST_KEY_VERSION);
+        hash_search_filters(&self.search_filters, &mut state);
+        state.write_i32(self.total);
+
+        match self.demon_rating {
+            None => state.write(&[0]),
+            Some(rating) => {
+                state.write(&[1]);
+                state.write_i32(demon_rating_value(rating))
+            },
+        }
 
-        self.search_filters.hash(&mut state);
-        self.total.hash(&mut state);
-        self.demon_rating.hash(&mut state);
-        self.ratings.hash(&mut state);
-        self.lengths.hash(&mut state);
-        self.search_string.hash(&mut state);
-        self.request_type.hash(&mut state);
-        self.page.hash(&mut state);
+        state.write_u64(self.ratings.len() as u64);
+        for rating in &self.ratings {
+            state.write_i32(rating_value(*rating))
+        }
+
+        state.write_u64(self.lengths.len() as u64);
+        for length in &self.lengths {
+            state.write_i32(length_value(*length))
+        }
+
+        state.write_prefixed(self.search_string.as_bytes());
+        state.write_i32(i32::from(self.request_type));
+        state.write_u32(self.page);
 
         state.finish() as i64
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::DatabaseKey;
+    use gdcf::api::request::{level::LevelRequestType, LevelsRequest};
+
+    // These values are stored in existing databases. If this test fails, the key scheme changed and
+    // REQUEST_KEY_VERSION needs to be bumped (with a migration path for the old rows).
+    #[test]
+    fn levels_request_keys_are_stable() {
+        assert_eq!(LevelsRequest::default().database_key(), -5827449652289487958);
+        assert_eq!(
+            LevelsRequest::default()
+                .request_type(LevelRequestType::Search)
+                .search("Bloodbath".to_string())
+                .database_key(),
+            -2782490931918996730
+        );
+    }
+}
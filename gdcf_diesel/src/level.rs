@@ -11,7 +11,7 @@ use gdcf::{
 };
 use gdcf_model::level::{Level, Password};
 use log::{debug, warn};
-use std::fmt::Display;
+use std::{borrow::Cow, fmt::Display};
 
 #[derive(Debug, Clone)]
 pub(crate) struct SemiLevel {
@@ -23,6 +23,47 @@ pub(crate) struct SemiLevel {
     index_36: String,
 }
 
+/// Prefix marking the value of the `level_password` column as the raw value of a
+/// [`Password::Invalid`]
+///
+/// Without it, invalid passwords whose raw value happens to be numeric (or empty) would be loaded
+/// as [`Password::PasswordCopy`]. The prefix cannot occur in the other stored values, which are all
+/// numeric.
+const INVALID_PASSWORD_PREFIX: &str = "invalid:";
+
+/// Converts a password into the value stored in the `level_password` column
+///
+/// Levels that cannot be copied are stored as `NULL`, free copies as `1` and password protected
+/// copies as their (numeric) password.
+pub(crate) fn password_column(password: &Password) -> Option<String> {
+    match password {
+        Password::NoCopy => None,
+        Password::FreeCopy => Some("1".to_string()),
+        Password::PasswordCopy(password) => Some(password.clone()),
+        Password::Invalid(raw) => Some(format!("{}{}", INVALID_PASSWORD_PREFIX, raw)),
+    }
+}
+
+/// Converts the value of the `level_password` column back into a password, see
+/// [`password_column`]
+pub(crate) fn password_from_column(value: Option<String>) -> Password {
+    match value {
+        None => Password::NoCopy,
+        Some(value) =>
+            if let Some(raw) = value.strip_prefix(INVALID_PASSWORD_PREFIX) {
+                Password::Invalid(raw.to_string())
+            } else if value == "1" {
+                Password::FreeCopy
+            } else if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) {
+                Password::PasswordCopy(value)
+            } else {
+                // Invalid passwords stored before they were prefixed. Actual passwords are always
+                // numeric, so anything else is the still encrypted value of an invalid password
+                Password::Invalid(value)
+            },
+    }
+}
+
 diesel_stuff! {
     level (level_id, SemiLevel) {
         (level_id, level_id, u64),
@@ -43,11 +84,7 @@ impl<'a> diesel::Insertable<level::table> for &'a Level<Option<u64>, u64> {
         (
             level_id.eq(self.base.level_id as i64),
             level_data.eq(&self.level_data[..]),
-            level_password.eq(match self.password {
-                Password::NoCopy => None,
-                Password::FreeCopy => Some("1"),
-                Password::PasswordCopy(ref password) => Some(password.as_ref()),
-            }),
+            level_password.eq(password_column(&self.password)),
             time_since_upload.eq(&self.time_since_upload[..]),
             time_since_update.eq(&self.time_since_update[..]),
             index_36.eq(&self.index_36[..]),
@@ -68,11 +105,7 @@ impl<'a> diesel::query_builder::AsChangeset for Wrapped<&'a Level<Option<u64>, u
         (
             level_id.eq(lvl.base.level_id as i64),
             level_data.eq(&lvl.level_data[..]),
-            level_password.eq(match lvl.password {
-                Password::NoCopy => None,
-                Password::FreeCopy => Some("1"),
-                Password::PasswordCopy(ref password) => Some(password.as_ref()),
-            }),
+            level_password.eq(password_column(&lvl.password)),
             time_since_upload.eq(&lvl.time_since_upload[..]),
             time_since_update.eq(&lvl.time_since_update[..]),
             index_36.eq(&lvl.index_36[..]),
@@ -89,6 +122,8 @@ impl Display for SemiLevel {
 
 meta_table!(level_meta, level_id);
 
+stored_as_is!(SemiLevel);
+
 lookup_simply!(SemiLevelKey, level, level_meta, level_id);
 
 impl Lookup<LevelRequest> for Cache {
@@ -102,7 +137,7 @@ impl Lookup<LevelRequest> for Cache {
                         Ok(CacheEntry::Cached(
                             Level {
                                 base: partial,
-                                level_data: semi_level.level_data,
+                                level_data: self.blob_codec.decode(semi_level.level_data)?.into(),
                                 password: semi_level.level_password,
                                 time_since_upload: semi_level.time_since_upload,
                                 time_since_update: semi_level.time_since_update,
@@ -118,6 +153,18 @@ impl Lookup<LevelRequest> for Cache {
             CacheEntry::Missing => Ok(CacheEntry::Missing),
         }
     }
+
+    fn contains(&self, key: &LevelRequest) -> Result<bool, Self::Err> {
+        self.contains(&SemiLevelKey(key.level_id))
+    }
+
+    fn lookup_meta(&self, key: &LevelRequest) -> Result<Option<Entry>, Self::Err> {
+        self.lookup_meta(&SemiLevelKey(key.level_id))
+    }
+
+    fn lookup_raw(&self, key: &LevelRequest) -> Result<Option<String>, Self::Err> {
+        self.lookup_raw_response("LevelRequest", key.database_key())
+    }
 }
 
 impl Store<LevelRequest> for Cache {
@@ -126,10 +173,27 @@ impl Store<LevelRequest> for Cache {
 
         debug!("Storing {} under key {}", obj, key);
 
+        let encoded;
+        let obj = match self.blob_codec.encode(&obj.level_data) {
+            Cow::Borrowed(_) => obj,
+            Cow::Owned(level_data) => {
+                encoded = Level {
+                    base: obj.base.clone(),
+                    level_data: level_data.into(),
+                    password: obj.password.clone(),
+                    time_since_upload: obj.time_since_upload.clone(),
+                    time_since_update: obj.time_since_update.clone(),
+                    index_36: obj.index_36.clone(),
+                };
+                &encoded
+            },
+        };
+
         let entry = Entry::new(key.database_key());
+        let connection = self.pool.get()?;
 
-        update_entry!(self, entry, level_meta::table, level_meta::level_id);
-        upsert!(self, obj, level::table, level::level_id);
+        update_entry!(&connection, entry, level_meta::table, level_meta::level_id);
+        upsert!(&connection, obj, level::table, level::level_id);
 
         Ok(entry)
     }
@@ -138,7 +202,319 @@ impl Store<LevelRequest> for Cache {
         warn!("Marking Level with key {} as absent!", key.database_key());
 
         let entry = Entry::absent(key.database_key());
-        update_entry!(self, entry, level_meta::table, level_meta::level_id);
+        update_entry!(&self.pool.get()?, entry, level_meta::table, level_meta::level_id);
         Ok(entry)
     }
+
+    fn transient_meta(&self, key: &LevelRequest) -> Entry {
+        Entry::new(key.database_key())
+    }
+
+    fn store_raw(&mut self, key: &LevelRequest, raw: &str) -> Result<(), Self::Err> {
+        self.store_raw_response("LevelRequest", key.database_key(), raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{password_column, password_from_column};
+    use gdcf_model::level::Password;
+
+    #[cfg(feature = "sqlite")]
+    mod codec {
+        use crate::{
+            codec::{BlobCodec, Identity},
+            level::level,
+            partial_level::partial_level,
+            raw::raw_response,
+            tests::{partial_level, test_cache, TestCache},
+        };
+        use diesel::{dsl::sql, sql_types::BigInt, ExpressionMethods, QueryDsl, RunQueryDsl};
+        use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+        use gdcf::{
+            api::request::{LevelRequest, LevelsRequest},
+            cache::{CacheEntry, Lookup, Store},
+        };
+        use gdcf_model::level::{Level, Password};
+        use std::{
+            borrow::Cow,
+            io::{Read, Write},
+        };
+
+        /// Codec storing level data back to front, so that the stored blob differs from the data
+        #[derive(Debug)]
+        struct Reversed;
+
+        impl BlobCodec for Reversed {
+            fn encode<'a>(&self, data: &'a [u8]) -> Cow<'a, [u8]> {
+                Cow::Owned(data.iter().rev().cloned().collect())
+            }
+
+            fn decode(&self, mut data: Vec<u8>) -> std::io::Result<Vec<u8>> {
+                data.reverse();
+                Ok(data)
+            }
+        }
+
+        /// Codec `DEFLATE` compressing everything, like an application concerned with the size of its
+        /// cache would use
+        #[derive(Debug)]
+        struct Deflate;
+
+        impl BlobCodec for Deflate {
+            fn encode<'a>(&self, data: &'a [u8]) -> Cow<'a, [u8]> {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+
+                encoder.write_all(data).unwrap();
+
+                Cow::Owned(encoder.finish().unwrap())
+            }
+
+            fn decode(&self, data: Vec<u8>) -> std::io::Result<Vec<u8>> {
+                let mut decoded = Vec::new();
+
+                DeflateDecoder::new(&data[..]).read_to_end(&mut decoded)?;
+
+                Ok(decoded)
+            }
+        }
+
+        pub(super) fn level_with_data(level_data: Vec<u8>) -> Level<Option<u64>, u64> {
+            Level {
+                base: partial_level(10_565_740),
+                level_data: level_data.into(),
+                password: Password::NoCopy,
+                time_since_upload: "5 years".to_string(),
+                time_since_update: "3 years".to_string(),
+                index_36: String::new(),
+            }
+        }
+
+        #[test]
+        fn level_data_passes_through_codec() {
+            let mut cache = test_cache().blob_codec(Reversed);
+            let request = LevelRequest::new(10_565_740);
+
+            cache.store(&level_with_data(vec![1, 2, 3]), &request).unwrap();
+
+            let stored: Vec<u8> = level::table
+                .filter(level::level_id.eq(10_565_740i64))
+                .select(level::level_data)
+                .get_result(&cache.pool.get().unwrap())
+                .unwrap();
+
+            assert_eq!(stored, vec![3, 2, 1]);
+
+            match cache.lookup(&request).unwrap() {
+                CacheEntry::Cached(level, _) => assert_eq!(&level.level_data[..], &[1, 2, 3][..]),
+                entry => panic!("unexpected cache entry {:?}", entry),
+            }
+        }
+
+        #[test]
+        fn descriptions_and_raw_responses_pass_through_codec() {
+            let mut cache = test_cache().blob_codec(Reversed).retain_raw_responses(true);
+            let request = LevelRequest::new(10_565_740);
+            let mut level = level_with_data(vec![1, 2, 3]);
+
+            level.base.description = Some("abc".to_string());
+
+            cache.store(&level, &request).unwrap();
+            cache.store_raw(&request, "1:10565740").unwrap();
+
+            let connection = cache.pool.get().unwrap();
+            let description: Option<Vec<u8>> = partial_level::table.select(partial_level::description).get_result(&connection).unwrap();
+            let body: Vec<u8> = raw_response::table.select(raw_response::body).get_result(&connection).unwrap();
+
+            assert_eq!(description, Some(b"cba".to_vec()));
+            assert_eq!(body, b"04756501:1".to_vec());
+
+            match cache.lookup(&request).unwrap() {
+                CacheEntry::Cached(level, _) => assert_eq!(level.base.description, Some("abc".to_string())),
+                entry => panic!("unexpected cache entry {:?}", entry),
+            }
+
+            assert_eq!(cache.lookup_raw(&request).unwrap(), Some("1:10565740".to_string()));
+            assert_eq!(cache.search_levels("Level 10565740").unwrap()[0].description, Some("abc".to_string()));
+        }
+
+        /// Deterministically generates a sentence of `words` words, like the ones found in level
+        /// descriptions
+        fn sentence(seed: u64, words: usize) -> String {
+            const WORDS: &[&str] = &[
+                "my", "first", "level", "hope", "you", "enjoy", "it", "took", "me", "months", "to", "build", "thanks", "for",
+                "playing", "the", "hardest", "part", "is", "at", "end", "rate", "please", "verified", "by", "collab", "with",
+                "friends", "new", "update", "fixed", "bugs", "and", "sync", "song",
+            ];
+
+            let mut state = seed;
+
+            (0..words)
+                .map(|_| {
+                    state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+                    WORDS[(state >> 33) as usize % WORDS.len()]
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+
+        /// Fills the given cache with `pages` pages of search results, storing every level on them,
+        /// including their (gzipped) level data, and the raw responses of all requests
+        fn fill(cache: &mut TestCache, pages: u32) {
+            let mut levels = Vec::new();
+
+            for page in 0..pages {
+                let request = LevelsRequest {
+                    page,
+                    ..Default::default()
+                };
+                let mut raw_levels = Vec::new();
+                let mut partial_levels = Vec::new();
+
+                for index in 0..10 {
+                    let level_id = u64::from(page * 10 + index) + 1;
+                    let description = sentence(level_id, 5 + (level_id % 20) as usize);
+                    let objects: String = (0..500 + level_id * 7)
+                        .map(|object| format!("1,{},2,{},3,{};", 1 + object % 40, object * 30, (object * level_id) % 300))
+                        .collect();
+
+                    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Compression::default());
+                    encoder.write_all(objects.as_bytes()).unwrap();
+
+                    let mut level = level_with_data(encoder.finish().unwrap());
+
+                    level.base.level_id = level_id;
+                    level.base.name = sentence(level_id * 31, 2);
+                    level.base.description = Some(description);
+
+                    let raw_level = format!(
+                        "1:{}:2:{}:3:{}:5:1:6:503085:8:10:9:30:10:{}:12:0:13:21:14:{}:17::43:3:25::18:0:19:0:42:0:45:0:15:3:30:0:31:0:\
+                         37:0:38:0:39:0:46:1:47:2:35:467339",
+                        level_id,
+                        level.base.name,
+                        base64::encode(level.base.description.as_ref().unwrap()),
+                        level_id * 1337,
+                        level_id * 42
+                    );
+
+                    let level_request = LevelRequest::new(level_id);
+
+                    cache.store(&level, &level_request).unwrap();
+                    cache
+                        .store_raw(&level_request, &format!("{}:4:{}#hash", raw_level, base64::encode(&level.level_data[..])))
+                        .unwrap();
+
+                    raw_levels.push(raw_level);
+                    partial_levels.push(level.base.clone());
+                    levels.push(level);
+                }
+
+                cache.store(&partial_levels, &request).unwrap();
+                cache
+                    .store_raw(&request, &format!("{}#503085:Bloodbath:37415#1~|~467339#9999:{}:10#hash", raw_levels.join("|"), page * 10))
+                    .unwrap();
+            }
+        }
+
+        /// Returns the total sizes of the stored level data, descriptions and raw responses, and the
+        /// size of the database file
+        fn stored_sizes(cache: &TestCache) -> (i64, i64, i64, i64) {
+            let connection = cache.pool.get().unwrap();
+            let total = |query: &str| diesel::select(sql::<BigInt>(query)).get_result::<i64>(&connection).unwrap();
+
+            (
+                total("(SELECT SUM(LENGTH(level_data)) FROM level)"),
+                total("(SELECT SUM(LENGTH(description)) FROM partial_level)"),
+                total("(SELECT SUM(LENGTH(body)) FROM raw_response)"),
+                total("(SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size())"),
+            )
+        }
+
+        /// Prints how much space a compressing codec saves compared to the default one
+        ///
+        /// Run with `cargo test -p gdcf_diesel -- --ignored --nocapture print_compressed_sizes`
+        #[test]
+        #[ignore]
+        fn print_compressed_sizes() {
+            let mut identity = test_cache().blob_codec(Identity).retain_raw_responses(true);
+            let mut deflate = test_cache().blob_codec(Deflate).retain_raw_responses(true);
+
+            fill(&mut identity, 50);
+            fill(&mut deflate, 50);
+
+            let identity = stored_sizes(&identity);
+            let deflate = stored_sizes(&deflate);
+
+            println!("\n{:<16}{:>12}{:>12}{:>8}", "bytes", "Identity", "Deflate", "ratio");
+
+            for &(name, identity, deflate) in &[
+                ("level data", identity.0, deflate.0),
+                ("descriptions", identity.1, deflate.1),
+                ("raw responses", identity.2, deflate.2),
+                ("database file", identity.3, deflate.3),
+            ] {
+                println!("{:<16}{:>12}{:>12}{:>8.2}", name, identity, deflate, deflate as f64 / identity as f64);
+            }
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    mod raw {
+        use super::codec::level_with_data;
+        use crate::tests::test_cache;
+        use gdcf::{
+            api::request::LevelRequest,
+            cache::{Lookup, Store},
+        };
+
+        const RAW: &str = "1:10565740:2:Bloodbath:3:T3VyIG1vc3QgZGlmZmljdWx0IGxldmVsIHlldA==:4:H4sIAAAAAAAAC#hash";
+
+        #[test]
+        fn raw_response_is_retrieved_unchanged() {
+            let mut cache = test_cache().retain_raw_responses(true);
+            let request = LevelRequest::new(10_565_740);
+
+            cache.store(&level_with_data(vec![1, 2, 3]), &request).unwrap();
+            cache.store_raw(&request, RAW).unwrap();
+
+            assert_eq!(cache.lookup_raw(&request).unwrap(), Some(RAW.to_string()));
+            assert_eq!(cache.lookup_raw(&LevelRequest::new(1)).unwrap(), None);
+        }
+
+        #[test]
+        fn raw_response_is_dropped_unless_retained() {
+            let mut cache = test_cache();
+            let request = LevelRequest::new(10_565_740);
+
+            cache.store(&level_with_data(vec![1, 2, 3]), &request).unwrap();
+            cache.store_raw(&request, RAW).unwrap();
+
+            assert_eq!(cache.lookup_raw(&request).unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn passwords_survive_storage() {
+        let passwords = vec![
+            Password::NoCopy,
+            Password::FreeCopy,
+            Password::PasswordCopy("123456".to_string()),
+            Password::Invalid("AwQBBQ==".to_string()),
+            Password::Invalid("1234".to_string()),
+            Password::Invalid("1".to_string()),
+            Password::Invalid(String::new()),
+        ];
+
+        for password in passwords {
+            assert_eq!(password_from_column(password_column(&password)), password);
+        }
+    }
+
+    #[test]
+    fn unprefixed_invalid_passwords_still_load() {
+        assert_eq!(
+            password_from_column(Some("AwQBBQ==".to_string())),
+            Password::Invalid("AwQBBQ==".to_string())
+        );
+    }
 }
@@ -5,11 +5,14 @@
 mod meta;
 #[macro_use]
 mod macros;
+pub mod codec;
 mod creator;
 mod key;
 mod level;
+mod page_info;
 mod partial_level;
 mod profile;
+mod raw;
 mod song;
 mod wrap;
 
@@ -21,30 +24,126 @@ extern crate diesel;
 #[macro_use]
 extern crate diesel_migrations;
 
-use crate::{key::DatabaseKey, meta::DatabaseEntry, wrap::Wrapped};
-use chrono::{DateTime, Duration, Utc};
-use diesel::{query_dsl::QueryDsl, r2d2::ConnectionManager, ExpressionMethods, RunQueryDsl};
-use failure::Fail;
+use crate::{
+    codec::{BlobCodec, Stored},
+    key::DatabaseKey,
+    meta::DatabaseEntry,
+    wrap::Wrapped,
+};
+use chrono::Duration;
+use diesel::{
+    backend::Backend,
+    query_builder::{AstPass, QueryFragment, QueryId},
+    query_dsl::QueryDsl,
+    r2d2::ConnectionManager,
+    Connection, ExpressionMethods, OptionalExtension, QueryResult, RunQueryDsl, TextExpressionMethods,
+};
 use gdcf::{
-    cache::{CacheEntry, Lookup, Store},
+    cache::{CacheEntry, CreatorKey, Lookup, NewgroundsSongKey, Store},
     error::CacheError,
+    Secondary,
 };
-use gdcf_model::level::PartialLevel;
-use log::{debug, warn};
+use gdcf_model::{level::PartialLevel, song::NewgroundsSong, user::Creator};
+use log::{debug, info, warn};
 use r2d2::Pool;
+use std::{
+    fmt::{Display, Formatter},
+    sync::Arc,
+};
 
-pub use crate::meta::Entry;
+pub use crate::{key::REQUEST_KEY_VERSION, meta::Entry};
 
 // this means we cannot enable two features at once. Since diesel doesn't allow writing database
 // agnostic code, the alternative to this is wrapping everything in macros (like we used to do in
 // gdcf_dbcache). That's a crappy alternative. We dont do that there
 #[cfg(feature = "pg")]
 use diesel::pg::PgConnection;
+#[cfg(feature = "pg")]
+type DbConnection = PgConnection;
 
 use crate::key::PartialLevelKey;
 #[cfg(feature = "sqlite")]
 use diesel::sqlite::SqliteConnection;
-use gdcf::api::request::LevelsRequest;
+#[cfg(feature = "sqlite")]
+type DbConnection = SqliteConnection;
+use gdcf::api::{client::PageInfo, request::LevelsRequest};
+
+/// The version of the latest migration, which is the schema version this crate expects the database
+/// to have
+///
+/// This needs to be bumped whenever a migration is added.
+///
+/// Diesel orders migrations by comparing their versions as strings, so `11` would run before `2`.
+/// Migrations after `9` are therefore numbered `90`, `91`, and so on.
+pub const SCHEMA_VERSION: u32 = 97;
+
+#[derive(QueryableByName, PartialEq)]
+pub(crate) struct MigrationVersion {
+    #[sql_type = "diesel::sql_types::Text"]
+    pub(crate) version: String,
+}
+
+/// Wraps a query into an `EXPLAIN` statement of the given flavor, keeping the query's bind
+/// parameters
+pub(crate) struct Explain<'a, Q> {
+    prefix: &'static str,
+    query: &'a Q,
+}
+
+impl<DB: Backend, Q: QueryFragment<DB>> QueryFragment<DB> for Explain<'_, Q> {
+    fn walk_ast(&self, mut out: AstPass<DB>) -> QueryResult<()> {
+        out.push_sql(self.prefix);
+        out.push_sql(" ");
+        self.query.walk_ast(out)
+    }
+}
+
+impl<Q> QueryId for Explain<'_, Q> {
+    type QueryId = ();
+
+    const HAS_STATIC_QUERY_ID: bool = false;
+}
+
+/// Discards the cached level lists whose keys were derived with an outdated key scheme
+///
+/// Such keys can't be converted to the current scheme, since the requests they were derived from
+/// aren't stored. The affected lists are simply requested again the next time they are needed.
+/// This is done once by `Cache::initialize`, so lookups never have to deal with outdated keys. See
+/// [`REQUEST_KEY_VERSION`].
+fn discard_outdated_level_lists(connection: &DbConnection) -> QueryResult<()> {
+    use crate::{
+        page_info::page_info,
+        partial_level::{level_list_key_version::level_list_meta, level_request_results},
+        raw::raw_response,
+    };
+
+    let outdated: Vec<i64> = level_list_meta::table
+        .filter(level_list_meta::key_version.lt(REQUEST_KEY_VERSION as i64))
+        .select(level_list_meta::request_hash)
+        .load(connection)?;
+
+    if outdated.is_empty() {
+        return Ok(())
+    }
+
+    info!("Discarding {} cached level lists stored under outdated keys", outdated.len());
+
+    connection.transaction(|| {
+        for keys in outdated.chunks(500) {
+            diesel::delete(level_request_results::table.filter(level_request_results::request_hash.eq_any(keys))).execute(connection)?;
+            diesel::delete(page_info::table.filter(page_info::request_key.eq_any(keys))).execute(connection)?;
+            diesel::delete(
+                raw_response::table
+                    .filter(raw_response::request_kind.eq("LevelsRequest"))
+                    .filter(raw_response::request_key.eq_any(keys)),
+            )
+            .execute(connection)?;
+            diesel::delete(level_list_meta::table.filter(level_list_meta::request_hash.eq_any(keys))).execute(connection)?;
+        }
+
+        Ok(())
+    })
+}
 
 pub struct Cache {
     #[cfg(feature = "pg")]
@@ -52,13 +151,108 @@ pub struct Cache {
     #[cfg(feature = "sqlite")]
     pool: Pool<ConnectionManager<SqliteConnection>>,
     expire_after: Duration,
+    retain_raw_responses: bool,
+    blob_codec: Arc<dyn BlobCodec>,
 }
 
 impl Cache {
+    /// Sets whether the raw response bodies of requests should be stored alongside the parsed
+    /// objects
+    ///
+    /// Retaining raw responses allows re-parsing previously cached data, for example after a parser
+    /// upgrade, at the cost of roughly doubling the size of the database. Disabled by default.
+    pub fn retain_raw_responses(mut self, retain: bool) -> Self {
+        self.retain_raw_responses = retain;
+        self
+    }
+
+    /// Sets the [`BlobCodec`] level data, level descriptions and raw responses are passed through
+    /// before being stored
+    ///
+    /// Defaults to [`codec::Identity`], which stores everything exactly as the servers provide it.
+    pub fn blob_codec(mut self, codec: impl BlobCodec + 'static) -> Self {
+        self.blob_codec = Arc::new(codec);
+        self
+    }
+
+    /// Checks whether the schema of the database matches the one this crate expects
+    ///
+    /// This fails with [`Error::SchemaVersionMismatch`] if the database hasn't been initialized,
+    /// hasn't been migrated to the latest version (see `Cache::initialize`), or has been migrated
+    /// by a newer version of this crate. Using a cache with a mismatched schema results in cryptic
+    /// SQL errors, so applications that don't initialize the cache themselves should call this
+    /// before using it.
+    pub fn check_schema(&self) -> Result<(), Error> {
+        let connection = self.pool.get()?;
+
+        // If the migrations table doesn't exist, the database hasn't been initialized at all
+        let found = diesel::sql_query("SELECT version FROM __diesel_schema_migrations")
+            .load::<MigrationVersion>(&connection)
+            .ok()
+            .and_then(|versions| versions.iter().filter_map(|version| version.version.parse().ok()).max());
+
+        if found != Some(SCHEMA_VERSION) {
+            return Err(Error::SchemaVersionMismatch {
+                expected: SCHEMA_VERSION,
+                found,
+            })
+        }
+
+        Ok(())
+    }
+
+    /// Closes this cache
+    ///
+    /// Every connection this cache uses is checked out of its pool for a single operation only and
+    /// returned afterwards, so simply dropping a cache never leaks connections either. This method
+    /// merely makes the shutdown explicit and logs connections that are unexpectedly still in use.
+    ///
+    /// Clones of a cache share their pool. The pool, and with it all its connections, is only shut
+    /// down once the last clone has been closed or dropped.
+    pub fn close(self) {
+        let state = self.pool.state();
+        let in_use = state.connections - state.idle_connections;
+
+        if in_use != 0 {
+            warn!("Closing cache while {} of its connections are still in use", in_use);
+        }
+
+        debug!("Closing cache handle, pool has {} open connections", state.connections);
+    }
+
+    /// Lists the tables this cache stores objects in, together with the amount of entries currently
+    /// cached in each of them
+    ///
+    /// Some tables serve more than one key type, so counting per key would count their entries
+    /// multiple times. The tables, and the keys they serve, are:
+    /// + `level_meta` - `LevelRequest`
+    /// + `level_list_meta` - `LevelsRequest`
+    /// + `partial_level_meta` - `PartialLevelKey`
+    /// + `profile_meta` - `UserRequest` and `UserKey`
+    /// + `creator_meta` - `CreatorKey`
+    /// + `song_meta` - `SongRequest` and `NewgroundsSongKey`
+    ///
+    /// Entries marked as absent are included in the counts.
+    pub fn cached_tables(&self) -> Result<Vec<(&'static str, usize)>, Error> {
+        use crate::{creator::creator_meta, level::level_meta, partial_level::*, profile::profile_meta, song::song_meta};
+
+        let connection = self.pool.get()?;
+
+        macro_rules! count {
+            ($($table: ident),*) => {
+                vec![$((stringify!($table), $table::table.count().get_result::<i64>(&connection)? as usize)),*]
+            };
+        }
+
+        Ok(count!(level_meta, level_list_meta, partial_level_meta, profile_meta, creator_meta, song_meta))
+    }
+
     fn entry(&self, db_entry: DatabaseEntry) -> Entry {
-        let now = Utc::now();
-        let then = DateTime::<Utc>::from_utc(db_entry.cached_at, Utc);
-        let expired = now - then > self.expire_after;
+        // Uses the same age `Entry` reports via `CacheEntryMeta::age`, so the two never disagree
+        let expired = self
+            .expire_after
+            .to_std()
+            .map_or(true, |expire_after| Entry::age_now(db_entry.cached_at) > expire_after);
 
         Entry {
             expired,
@@ -67,6 +261,70 @@ impl Cache {
             absent: db_entry.absent,
         }
     }
+
+    /// Searches the cached partial levels for levels whose name matches the given `LIKE` pattern
+    ///
+    /// The pattern is passed to the database as a bound parameter, so it is never interpolated into
+    /// the query itself. Use `%` and `_` as wildcards, e.g. `%bloodbath%`.
+    ///
+    /// This doesn't do any requests to the servers and only ever considers what has already been
+    /// cached, regardless of whether the entries are expired or not.
+    pub fn search_levels(&self, pattern: &str) -> Result<Vec<PartialLevel<Option<u64>, u64>>, Error> {
+        use crate::partial_level::*;
+
+        let connection = self.pool.get()?;
+
+        let levels = partial_level::table
+            .filter(partial_level::level_name.like(pattern))
+            .load(&connection)?
+            .into_iter()
+            .map(|row: Wrapped<_>| Stored::from_row(row.0, &*self.blob_codec))
+            .collect::<Result<_, _>>()?;
+
+        Ok(levels)
+    }
+
+    /// Combines the cached results of the given [`LevelsRequest`]s
+    ///
+    /// Each level is only returned once, even if it is part of the results of multiple requests
+    /// (like SQL's `UNION`, as opposed to `UNION ALL`). Levels are ordered by their ID. Requests
+    /// that haven't been cached don't contribute any levels. Like [`Cache::search_levels`], this
+    /// doesn't care whether the entries are expired or not.
+    pub fn union_levels(&self, requests: &[LevelsRequest]) -> Result<Vec<PartialLevel<Option<u64>, u64>>, Error> {
+        use crate::partial_level::*;
+
+        let connection = self.pool.get()?;
+
+        let request_hashes: Vec<i64> = requests.iter().map(DatabaseKey::database_key).collect();
+
+        let levels = partial_level::table
+            .filter(
+                partial_level::level_id.eq_any(
+                    level_request_results::table
+                        .select(level_request_results::level_id)
+                        .filter(level_request_results::request_hash.eq_any(request_hashes)),
+                ),
+            )
+            .order(partial_level::level_id)
+            .load(&connection)?
+            .into_iter()
+            .map(|row: Wrapped<_>| Stored::from_row(row.0, &*self.blob_codec))
+            .collect::<Result<_, _>>()?;
+
+        Ok(levels)
+    }
+
+    /// Records that the `level_list_meta` row with the given key was derived with the current key
+    /// scheme
+    fn mark_current_key_version(&self, key: i64) -> Result<(), Error> {
+        use crate::partial_level::level_list_key_version::level_list_meta;
+
+        diesel::update(level_list_meta::table.filter(level_list_meta::request_hash.eq(key)))
+            .set(level_list_meta::key_version.eq(REQUEST_KEY_VERSION as i64))
+            .execute(&self.pool.get()?)?;
+
+        Ok(())
+    }
 }
 
 impl Clone for Cache {
@@ -74,47 +332,104 @@ impl Clone for Cache {
         Cache {
             pool: self.pool.clone(),
             expire_after: self.expire_after,
+            retain_raw_responses: self.retain_raw_responses,
+            blob_codec: self.blob_codec.clone(),
         }
     }
 }
 
 #[cfg(feature = "pg")]
 mod postgres {
-    use super::Cache;
+    use super::{codec::Identity, discard_outdated_level_lists, Cache, Error, Explain};
     use chrono::Duration;
-    use diesel::r2d2::ConnectionManager;
+    use diesel::{
+        deserialize::{self, QueryableByName},
+        pg::Pg,
+        query_builder::QueryFragment,
+        r2d2::ConnectionManager,
+        row::NamedRow,
+        sql_types::Text,
+        Connection,
+    };
     use r2d2::Pool;
+    use std::sync::Arc;
 
     embed_migrations!("migrations/postgres");
 
+    struct PlanLine {
+        plan: String,
+    }
+
+    // The derive only supports columns whose names are valid identifiers
+    impl QueryableByName<Pg> for PlanLine {
+        fn build<R: NamedRow<Pg>>(row: &R) -> deserialize::Result<Self> {
+            Ok(PlanLine {
+                plan: row.get::<Text, String>("QUERY PLAN")?,
+            })
+        }
+    }
+
     impl Cache {
         pub fn postgres(database_url: impl Into<String>) -> Result<Self, r2d2::Error> {
             Ok(Cache {
                 pool: Pool::new(ConnectionManager::new(database_url.into()))?,
                 expire_after: Duration::minutes(60),
+                retain_raw_responses: false,
+                blob_codec: Arc::new(Identity),
             })
         }
 
         pub fn initialize(&self) -> Result<(), diesel_migrations::RunMigrationsError> {
-            embedded_migrations::run_with_output(&self.pool.get().unwrap(), &mut std::io::stdout())
+            let connection = self.pool.get().unwrap();
+
+            embedded_migrations::run_with_output(&connection, &mut std::io::stdout())?;
+
+            Ok(discard_outdated_level_lists(&connection)?)
+        }
+
+        /// Returns the plan postgres would use to execute the given query, as returned by `EXPLAIN`
+        ///
+        /// This is meant for debugging slow cache operations. Since only a plain `EXPLAIN` is issued
+        /// (and not `EXPLAIN ANALYZE`), the query isn't executed.
+        pub fn explain<Q: QueryFragment<Pg>>(&self, query: &Q) -> Result<String, Error> {
+            let plan: Vec<PlanLine> = self.pool.get()?.query_by_name(&Explain { prefix: "EXPLAIN", query })?;
+
+            Ok(plan.into_iter().map(|line| line.plan).collect::<Vec<_>>().join("\n"))
         }
     }
 }
 
 #[cfg(feature = "sqlite")]
 mod sqlite {
-    use super::Cache;
+    use super::{codec::Identity, discard_outdated_level_lists, Cache, Error, Explain, MigrationVersion};
     use chrono::Duration;
-    use diesel::r2d2::ConnectionManager;
+    use diesel::{query_builder::QueryFragment, r2d2::ConnectionManager, sql_types::Text, sqlite::Sqlite, Connection, RunQueryDsl};
+    use log::{info, warn};
     use r2d2::Pool;
+    use std::sync::Arc;
+    use std::path::Path;
 
     embed_migrations!("migrations/sqlite");
 
+    #[derive(QueryableByName)]
+    struct TableName {
+        #[sql_type = "Text"]
+        name: String,
+    }
+
+    #[derive(QueryableByName)]
+    struct PlanLine {
+        #[sql_type = "Text"]
+        detail: String,
+    }
+
     impl Cache {
         pub fn in_memory() -> Result<Self, r2d2::Error> {
             Ok(Self {
                 pool: Pool::new(ConnectionManager::new(":memory:"))?,
                 expire_after: Duration::seconds(60),
+                retain_raw_responses: false,
+                blob_codec: Arc::new(Identity),
             })
         }
 
@@ -122,22 +437,150 @@ mod sqlite {
             Ok(Self {
                 pool: Pool::new(ConnectionManager::new(path.into()))?,
                 expire_after: Duration::seconds(60),
+                retain_raw_responses: false,
+                blob_codec: Arc::new(Identity),
             })
         }
 
         pub fn initialize(&self) -> Result<(), diesel_migrations::RunMigrationsError> {
-            embedded_migrations::run(&self.pool.get().unwrap())
+            let connection = self.pool.get().unwrap();
+
+            embedded_migrations::run(&connection)?;
+
+            Ok(discard_outdated_level_lists(&connection)?)
+        }
+
+        /// Returns the plan sqlite would use to execute the given query, as returned by
+        /// `EXPLAIN QUERY PLAN`
+        ///
+        /// This is meant for debugging slow cache operations, for example to check whether a lookup
+        /// uses an index. The query isn't executed.
+        pub fn explain<Q: QueryFragment<Sqlite>>(&self, query: &Q) -> Result<String, Error> {
+            let plan: Vec<PlanLine> = self.pool.get()?.query_by_name(&Explain {
+                prefix: "EXPLAIN QUERY PLAN",
+                query,
+            })?;
+
+            Ok(plan.into_iter().map(|line| line.detail).collect::<Vec<_>>().join("\n"))
+        }
+
+        /// Writes a snapshot of this cache's database to the given path, replacing any file that
+        /// might already exist there
+        pub fn save_to(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+            let path = path.as_ref();
+            let temporary = path.with_extension("tmp");
+
+            info!("Saving cache snapshot to {:?}", path);
+
+            if temporary.exists() {
+                std::fs::remove_file(&temporary)?;
+            }
+
+            // VACUUM INTO refuses to overwrite existing files, so we go via a temporary file
+            diesel::sql_query("VACUUM INTO ?")
+                .bind::<Text, _>(temporary.to_string_lossy())
+                .execute(&self.pool.get()?)?;
+
+            std::fs::rename(temporary, path)?;
+
+            Ok(())
+        }
+
+        /// Loads a snapshot previously written by [`Cache::save_to`] into this cache, overwriting
+        /// entries that exist in both
+        ///
+        /// Snapshots are only loaded if they were taken with the exact same schema version as this
+        /// cache's database. Incompatible (or missing) snapshots are discarded, in which case
+        /// `Ok(false)` is returned. This cache needs to be [initialized](Cache::initialize) first.
+        pub fn load_from(&self, path: impl AsRef<Path>) -> Result<bool, Error> {
+            let path = path.as_ref();
+
+            if !path.exists() {
+                warn!("Cache snapshot {:?} does not exist, not loading anything", path);
+
+                return Ok(false)
+            }
+
+            info!("Loading cache snapshot from {:?}", path);
+
+            let connection = self.pool.get()?;
+
+            diesel::sql_query("ATTACH DATABASE ? AS snapshot")
+                .bind::<Text, _>(path.to_string_lossy())
+                .execute(&connection)?;
+
+            let result = connection.transaction(|| {
+                let ours: Vec<MigrationVersion> =
+                    diesel::sql_query("SELECT version FROM main.__diesel_schema_migrations ORDER BY version").load(&connection)?;
+                let theirs: Result<Vec<MigrationVersion>, _> =
+                    diesel::sql_query("SELECT version FROM snapshot.__diesel_schema_migrations ORDER BY version").load(&connection);
+
+                // A snapshot without migration information isn't one of ours
+                if theirs.map(|theirs| theirs != ours).unwrap_or(true) {
+                    warn!("Cache snapshot {:?} has an incompatible schema version, discarding it", path);
+
+                    return Ok(false)
+                }
+
+                let tables: Vec<TableName> = diesel::sql_query(
+                    "SELECT name FROM snapshot.sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' AND name != \
+                     '__diesel_schema_migrations'",
+                )
+                .load(&connection)?;
+
+                for table in tables {
+                    diesel::sql_query(format!(
+                        "INSERT OR REPLACE INTO main.\"{0}\" SELECT * FROM snapshot.\"{0}\"",
+                        table.name.replace('"', "\"\"")
+                    ))
+                    .execute(&connection)?;
+                }
+
+                Ok(true)
+            });
+
+            diesel::sql_query("DETACH DATABASE snapshot").execute(&connection)?;
+
+            result
         }
     }
 }
 
-#[derive(Debug, Fail)]
+// Implemented by hand instead of deriving `Fail` so that the wrapped error is exposed via `source()`
+#[derive(Debug)]
 pub enum Error {
-    #[fail(display = "Database error: {}", _0)]
-    Database(#[cause] diesel::result::Error),
+    Database(diesel::result::Error),
+
+    R2D2(r2d2::Error),
+
+    Io(std::io::Error),
+
+    /// The database schema doesn't have the version this crate expects. `found` is [`None`] if the
+    /// database hasn't been initialized at all.
+    SchemaVersionMismatch { expected: u32, found: Option<u32> },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Error::Database(err) => write!(f, "Database error: {}", err),
+            Error::R2D2(err) => write!(f, "Connection pool error: {}", err),
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::SchemaVersionMismatch { expected, found } =>
+                write!(f, "Database schema version mismatch: expected {}, found {:?}", expected, found),
+        }
+    }
+}
 
-    #[fail(display = "Connection pool error: {}", _0)]
-    R2D2(#[cause] r2d2::Error),
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Database(err) => Some(err),
+            Error::R2D2(err) => Some(err),
+            Error::Io(err) => Some(err),
+            Error::SchemaVersionMismatch { .. } => None,
+        }
+    }
 }
 
 impl From<r2d2::Error> for Error {
@@ -146,6 +589,12 @@ impl From<r2d2::Error> for Error {
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
 impl From<diesel::result::Error> for Error {
     fn from(err: diesel::result::Error) -> Self {
         Error::Database(err)
@@ -157,6 +606,57 @@ impl CacheError for Error {}
 impl gdcf::cache::Cache for Cache {
     type CacheEntryMeta = Entry;
     type Err = Error;
+
+    fn store_batch(&mut self, objects: &[Secondary]) -> Result<(), Self::Err> {
+        use crate::{creator::*, song::*};
+
+        debug!("Storing {} objects in a single transaction", objects.len());
+
+        let mut songs = Vec::new();
+        let mut creators = Vec::new();
+        let mut song_entries = Vec::new();
+        let mut creator_entries = Vec::new();
+
+        for object in objects {
+            match object {
+                Secondary::NewgroundsSong(song) => {
+                    song_entries.push(Entry::new(NewgroundsSongKey(song.song_id).database_key()));
+                    songs.push(song);
+                },
+                Secondary::Creator(creator) => {
+                    creator_entries.push(Entry::new(CreatorKey(creator.user_id).database_key()));
+                    creators.push(creator);
+                },
+                Secondary::MissingNewgroundsSong(nid) => {
+                    warn!("Marking NewgroundsSongKey with key {} as absent!", nid);
+
+                    song_entries.push(Entry::absent(NewgroundsSongKey(*nid).database_key()));
+                },
+                Secondary::MissingCreator(cid) => {
+                    warn!("Marking CreatorKey with key {} as absent!", cid);
+
+                    creator_entries.push(Entry::absent(CreatorKey(*cid).database_key()));
+                },
+            }
+        }
+
+        let connection = self.pool.get()?;
+
+        connection.transaction(|| {
+            update_entries!(&connection, song_entries, song_meta::table, song_meta::song_id);
+            update_entries!(&connection, creator_entries, creator_meta::table, creator_meta::user_id);
+            upsert_batch!(
+                &connection,
+                songs,
+                |song: &NewgroundsSong| song.song_id,
+                newgrounds_song::table,
+                newgrounds_song::song_id
+            );
+            upsert_batch!(&connection, creators, |creator: &Creator| creator.user_id, creator::table, creator::user_id);
+
+            Ok(())
+        })
+    }
 }
 
 // TODO: in the future we can probably make these macro-generated as well, but for now we only have
@@ -185,11 +685,42 @@ impl Lookup<LevelsRequest> for Cache {
             .select(partial_level::all_columns)
             .load(&connection))
         .into_iter()
-        .map(|row: Wrapped<_>| row.0)
-        .collect();
+        .map(|row: Wrapped<_>| Stored::from_row(row.0, &*self.blob_codec))
+        .collect::<Result<_, _>>()?;
 
         Ok(CacheEntry::Cached(levels, entry))
     }
+
+    fn contains(&self, key: &LevelsRequest) -> Result<bool, Self::Err> {
+        use crate::partial_level::*;
+
+        let connection = self.pool.get()?;
+
+        Ok(diesel::select(diesel::dsl::exists(
+            level_list_meta::table.filter(level_list_meta::request_hash.eq(key.database_key())),
+        ))
+        .get_result(&connection)?)
+    }
+
+    fn lookup_meta(&self, key: &LevelsRequest) -> Result<Option<Entry>, Self::Err> {
+        use crate::partial_level::*;
+
+        let connection = self.pool.get()?;
+
+        Ok(level_list_meta::table
+            .filter(level_list_meta::request_hash.eq(key.database_key()))
+            .get_result(&connection)
+            .optional()?
+            .map(|entry| self.entry(entry)))
+    }
+
+    fn lookup_raw(&self, key: &LevelsRequest) -> Result<Option<String>, Self::Err> {
+        self.lookup_raw_response("LevelsRequest", key.database_key())
+    }
+
+    fn lookup_page_info(&self, key: &LevelsRequest) -> Result<Option<PageInfo>, Self::Err> {
+        self.lookup_page_info_for(key.database_key())
+    }
 }
 
 impl Store<LevelsRequest> for Cache {
@@ -200,7 +731,8 @@ impl Store<LevelsRequest> for Cache {
 
         let entry = Entry::absent(key.database_key());
 
-        update_entry!(self, entry, level_list_meta::table, level_list_meta::request_hash);
+        update_entry!(&self.pool.get()?, entry, level_list_meta::table, level_list_meta::request_hash);
+        self.mark_current_key_version(entry.key)?;
 
         Ok(entry)
     }
@@ -232,8 +764,379 @@ impl Store<LevelsRequest> for Cache {
 
         let entry = Entry::new(db_key);
 
-        update_entry!(self, entry, level_list_meta::table, level_list_meta::request_hash);
+        update_entry!(&conn, entry, level_list_meta::table, level_list_meta::request_hash);
+        self.mark_current_key_version(db_key)?;
 
         Ok(entry)
     }
+
+    fn transient_meta(&self, key: &LevelsRequest) -> Entry {
+        Entry::new(key.database_key())
+    }
+
+    fn store_raw(&mut self, key: &LevelsRequest, raw: &str) -> Result<(), Self::Err> {
+        self.store_raw_response("LevelsRequest", key.database_key(), raw)
+    }
+
+    fn store_page_info(&mut self, key: &LevelsRequest, page_info: &PageInfo) -> Result<(), Self::Err> {
+        self.store_page_info_for(key.database_key(), page_info)
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use super::*;
+    use std::{
+        ops::{Deref, DerefMut},
+        path::PathBuf,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    static DATABASE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    /// A cache backed by a sqlite database file, which is deleted once the cache is dropped
+    pub(crate) struct TestCache {
+        cache: Cache,
+        path: PathBuf,
+    }
+
+    impl TestCache {
+        pub(crate) fn retain_raw_responses(mut self, retain: bool) -> Self {
+            self.cache.retain_raw_responses = retain;
+            self
+        }
+
+        pub(crate) fn blob_codec(mut self, codec: impl BlobCodec + 'static) -> Self {
+            self.cache.blob_codec = Arc::new(codec);
+            self
+        }
+    }
+
+    impl Deref for TestCache {
+        type Target = Cache;
+
+        fn deref(&self) -> &Cache {
+            &self.cache
+        }
+    }
+
+    impl DerefMut for TestCache {
+        fn deref_mut(&mut self) -> &mut Cache {
+            &mut self.cache
+        }
+    }
+
+    impl Drop for TestCache {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    /// Creates an initialized cache backed by a fresh sqlite database in the temp directory
+    ///
+    /// In-memory databases can't be used here, since every pooled connection would get its own.
+    pub(crate) fn test_cache() -> TestCache {
+        let path = std::env::temp_dir().join(format!(
+            "gdcf_diesel_test_{}_{}.db",
+            std::process::id(),
+            DATABASE_COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+
+        let _ = std::fs::remove_file(&path);
+
+        let cache = Cache::sqlite(path.to_string_lossy().into_owned()).unwrap();
+        cache.initialize().unwrap();
+
+        TestCache { cache, path }
+    }
+
+    #[test]
+    fn check_schema_detects_outdated_database() {
+        let cache = test_cache();
+
+        assert!(cache.check_schema().is_ok());
+
+        // Simulate a database that hasn't been migrated to the latest version yet
+        diesel::sql_query(format!("DELETE FROM __diesel_schema_migrations WHERE version = '{}'", SCHEMA_VERSION))
+            .execute(&cache.pool.get().unwrap())
+            .unwrap();
+
+        match cache.check_schema() {
+            Err(Error::SchemaVersionMismatch { expected, found }) => {
+                assert_eq!(expected, SCHEMA_VERSION);
+                assert_eq!(found, Some(SCHEMA_VERSION - 1));
+            },
+            other => panic!("expected schema version mismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn initialize_discards_level_lists_with_outdated_keys() {
+        let mut cache = test_cache().retain_raw_responses(true);
+        let outdated = LevelsRequest::default().search("Bloodbath".to_string());
+        let current = LevelsRequest::default().search("Sonic Wave".to_string());
+
+        for &request in &[&outdated, &current] {
+            cache.store(&vec![partial_level(10)], request).unwrap();
+            cache.store_raw(request, "raw").unwrap();
+        }
+
+        // Simulate a row written before request keys were versioned
+        diesel::sql_query(format!(
+            "UPDATE level_list_meta SET key_version = 0 WHERE request_hash = {}",
+            outdated.database_key()
+        ))
+        .execute(&cache.pool.get().unwrap())
+        .unwrap();
+
+        // Outdated keys are only dealt with once, not on every lookup
+        assert!(cache.contains(&outdated).unwrap());
+
+        cache.initialize().unwrap();
+
+        assert!(!cache.contains(&outdated).unwrap());
+        assert_eq!(cache.lookup_raw(&outdated).unwrap(), None);
+        assert_eq!(cache.union_levels(&[outdated]).unwrap(), Vec::new());
+
+        assert!(cache.contains(&current).unwrap());
+        assert_eq!(cache.lookup_raw(&current).unwrap(), Some("raw".to_string()));
+    }
+
+    #[test]
+    fn explain_shows_index_usage() {
+        use crate::creator::creator;
+
+        let cache = test_cache();
+        let plan = cache.explain(&creator::table.filter(creator::account_id.eq(71))).unwrap();
+
+        assert!(plan.contains("USING INDEX creator_account_id"), "unexpected query plan {}", plan);
+    }
+
+    #[test]
+    fn cached_tables_counts_shared_tables_once() {
+        use gdcf::api::request::SongRequest;
+
+        let mut cache = test_cache();
+
+        cache.mark_absent(&NewgroundsSongKey(1)).unwrap();
+        cache.mark_absent(&SongRequest::new(2)).unwrap();
+        cache.mark_absent(&CreatorKey(1)).unwrap();
+        cache.mark_absent(&LevelsRequest::default()).unwrap();
+
+        let tables = cache.cached_tables().unwrap();
+
+        assert_eq!(tables, vec![
+            ("level_meta", 0),
+            ("level_list_meta", 1),
+            ("partial_level_meta", 0),
+            ("profile_meta", 0),
+            ("creator_meta", 1),
+            ("song_meta", 2)
+        ]);
+    }
+
+    /// A partial level with the given ID, and all other fields set to their default values
+    pub(crate) fn partial_level(level_id: u64) -> PartialLevel<Option<u64>, u64> {
+        use gdcf_model::{
+            level::{Featured, LevelLength, LevelRating},
+            GameVersion,
+        };
+
+        PartialLevel {
+            level_id,
+            name: format!("Level {}", level_id),
+            description: None,
+            version: 1,
+            creator: 503_085,
+            difficulty: LevelRating::NotAvailable,
+            downloads: 0,
+            main_song: None,
+            gd_version: GameVersion::Unknown,
+            binary_version: None,
+            likes: 0,
+            length: LevelLength::Short,
+            stars: 0,
+            featured: Featured::NotFeatured,
+            copy_of: None,
+            two_player: false,
+            custom_song: None,
+            coin_amount: 0,
+            coins_verified: false,
+            stars_requested: None,
+            low_detail_available: false,
+            is_epic: false,
+            index_43: String::new(),
+            object_amount: None,
+            editor_time: None,
+            editor_time_copies: None,
+            song_ids: Vec::new(),
+            sfx_ids: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn search_levels_matches_substring_pattern() {
+        let mut cache = test_cache();
+
+        for (level_id, name) in vec![(1, "Bloodbath"), (2, "Sonic Wave"), (3, "Bloodlust")] {
+            let mut level = partial_level(level_id);
+            level.name = name.to_string();
+
+            cache.store(&level, &PartialLevelKey(level_id)).unwrap();
+        }
+
+        let mut ids: Vec<_> = cache.search_levels("%lood%").unwrap().into_iter().map(|level| level.level_id).collect();
+        ids.sort();
+
+        assert_eq!(ids, vec![1, 3]);
+        assert!(cache.search_levels("%Acu%").unwrap().is_empty());
+
+        // The pattern is bound as a parameter, so quotes in it can't break out of the query
+        assert!(cache.search_levels("%'; DROP TABLE partial_level; --%").unwrap().is_empty());
+        assert_eq!(cache.search_levels("%").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn union_levels_returns_each_level_once_in_id_order() {
+        let mut cache = test_cache();
+        let first = LevelsRequest::default().search("Bloodbath".to_string());
+        let second = LevelsRequest::default().search("Bloodlust".to_string());
+        let uncached = LevelsRequest::default().search("Sonic Wave".to_string());
+
+        cache.store(&vec![partial_level(30), partial_level(10), partial_level(20)], &first).unwrap();
+        cache.store(&vec![partial_level(20), partial_level(40), partial_level(10)], &second).unwrap();
+
+        let ids = |levels: Vec<PartialLevel<Option<u64>, u64>>| levels.into_iter().map(|level| level.level_id).collect::<Vec<_>>();
+
+        assert_eq!(ids(cache.union_levels(&[first, second.clone(), uncached]).unwrap()), vec![10, 20, 30, 40]);
+        assert_eq!(ids(cache.union_levels(&[second]).unwrap()), vec![10, 20, 40]);
+        assert!(cache.union_levels(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn store_batch_stores_every_object() {
+        use gdcf::cache::Cache as _;
+
+        let mut cache = test_cache();
+
+        let songs: Vec<_> = (1..=3)
+            .map(|song_id| {
+                NewgroundsSong {
+                    song_id,
+                    name: format!("Song {}", song_id),
+                    artist_id: 0,
+                    artist: "Artist".to_string(),
+                    filesize: 1.5,
+                    index_6: None,
+                    index_7: None,
+                    index_8: "1".to_string(),
+                    link: "http://audio.ngfiles.com".to_string(),
+                }
+            })
+            .collect();
+        let creators: Vec<_> = (1..=3)
+            .map(|user_id| {
+                Creator {
+                    user_id,
+                    name: format!("Creator {}", user_id),
+                    account_id: Some(user_id + 100),
+                }
+            })
+            .collect();
+
+        // Creator 1 is already cached, the batch needs to replace it
+        cache
+            .store(
+                &Creator {
+                    user_id: 1,
+                    name: "Outdated".to_string(),
+                    account_id: None,
+                },
+                &CreatorKey(1),
+            )
+            .unwrap();
+
+        let mut batch: Vec<Secondary> = songs.iter().cloned().map(Secondary::from).collect();
+
+        batch.extend(creators.iter().cloned().map(Secondary::from));
+        batch.push(Secondary::MissingCreator(4));
+        batch.push(Secondary::MissingNewgroundsSong(4));
+
+        cache.store_batch(&batch).unwrap();
+
+        for song in &songs {
+            match cache.lookup(&NewgroundsSongKey(song.song_id)).unwrap() {
+                CacheEntry::Cached(cached, _) => assert_eq!(&cached, song),
+                other => panic!("expected song {} to be cached, got {:?}", song.song_id, other),
+            }
+        }
+
+        for creator in &creators {
+            match cache.lookup(&CreatorKey(creator.user_id)).unwrap() {
+                CacheEntry::Cached(cached, _) => assert_eq!(&cached, creator),
+                other => panic!("expected creator {} to be cached, got {:?}", creator.user_id, other),
+            }
+        }
+
+        match cache.lookup(&CreatorKey(4)).unwrap() {
+            CacheEntry::MarkedAbsent(_) => (),
+            other => panic!("expected creator 4 to be marked absent, got {:?}", other),
+        }
+
+        match cache.lookup(&NewgroundsSongKey(4)).unwrap() {
+            CacheEntry::MarkedAbsent(_) => (),
+            other => panic!("expected song 4 to be marked absent, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn database_error_is_exposed_as_source() {
+        use std::error::Error as _;
+
+        let cache = test_cache();
+        let err: Error = diesel::sql_query("SELECT * FROM no_such_table")
+            .execute(&cache.pool.get().unwrap())
+            .unwrap_err()
+            .into();
+
+        let source = err.source().expect("database error has no source");
+
+        match source.downcast_ref::<diesel::result::Error>() {
+            Some(diesel::result::Error::DatabaseError(..)) => (),
+            other => panic!("expected the wrapped diesel error, got {:?}", other),
+        }
+        assert_eq!(err.to_string(), format!("Database error: {}", source));
+
+        let err = Error::SchemaVersionMismatch {
+            expected: SCHEMA_VERSION,
+            found: None,
+        };
+
+        assert!(err.source().is_none());
+    }
+
+    #[test]
+    fn migrations_run_in_numeric_order() {
+        for &backend in &["sqlite", "postgres"] {
+            let directory = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("migrations").join(backend);
+
+            let mut versions = std::fs::read_dir(directory)
+                .unwrap()
+                .map(|entry| {
+                    let name = entry.unwrap().file_name().into_string().unwrap();
+                    name.split('_').next().unwrap().to_string()
+                })
+                .collect::<Vec<_>>();
+
+            // This is the order diesel runs the migrations in. `10` is a postgres-only fix that
+            // does nothing on sqlite, so it running early doesn't matter.
+            versions.sort();
+            versions.retain(|version| version != "10");
+
+            let numeric = versions.iter().map(|version| version.parse::<u32>().unwrap()).collect::<Vec<_>>();
+
+            assert!(numeric.windows(2).all(|pair| pair[0] < pair[1]), "{} migrations run out of order: {:?}", backend, versions);
+            assert_eq!(numeric.last(), Some(&SCHEMA_VERSION));
+        }
+    }
 }
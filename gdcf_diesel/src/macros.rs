@@ -1,28 +1,72 @@
 #[cfg(feature = "pg")]
 macro_rules! upsert {
-    ($self: expr, $object: expr, $table: expr, $column: expr) => {{
+    ($connection: expr, $object: expr, $table: expr, $column: expr) => {{
         diesel::insert_into($table)
             .values($object)
             .on_conflict($column)
             .do_update()
             .set(Wrapped($object))
-            .execute(&$self.pool.get()?)?;
+            .execute($connection)?;
     }};
 }
 
 macro_rules! update_entry {
-    ($self: expr, $entry: expr, $table: expr, $column: expr) => {{
+    ($connection: expr, $entry: expr, $table: expr, $column: expr) => {{
         use diesel::{ExpressionMethods, QueryDsl};
 
-        diesel::delete($table.filter($column.eq($entry.key as i64))).execute(&$self.pool.get()?)?;
-        diesel::insert_into($table).values($entry).execute(&$self.pool.get()?)?;
+        let connection = $connection;
+
+        diesel::delete($table.filter($column.eq($entry.key as i64))).execute(connection)?;
+        diesel::insert_into($table).values($entry).execute(connection)?;
+    }};
+}
+
+/// Like `update_entry!`, but replaces the entries of many objects at once
+///
+/// The new entries are written with a single multi-row insert (on sqlite, which doesn't support
+/// those for our tables, diesel falls back to one insert per row). Callers are expected to wrap this
+/// in a transaction.
+macro_rules! update_entries {
+    ($connection: expr, $entries: expr, $table: expr, $column: expr) => {{
+        use diesel::{ExpressionMethods, QueryDsl};
+
+        // Diesel only implements batch inserts for sqlite on the raw connection, not the pooled one
+        let connection = &**$connection;
+        let entries: Vec<crate::meta::Entry> = $entries;
+
+        if !entries.is_empty() {
+            let keys: Vec<i64> = entries.iter().map(|entry| entry.key).collect();
+
+            diesel::delete($table.filter($column.eq_any(keys))).execute(connection)?;
+            diesel::insert_into($table).values(entries).execute(connection)?;
+        }
+    }};
+}
+
+/// Like `upsert!`, but for many objects at once, using a single multi-row insert
+///
+/// Objects are identified by the value `$key` returns for them. Callers are expected to wrap this in
+/// a transaction.
+macro_rules! upsert_batch {
+    ($connection: expr, $objects: expr, $key: expr, $table: expr, $column: expr) => {{
+        use diesel::{ExpressionMethods, QueryDsl};
+
+        let connection = &**$connection;
+        let objects: Vec<_> = $objects;
+
+        if !objects.is_empty() {
+            let keys: Vec<i64> = objects.iter().map(|object| $key(*object) as i64).collect();
+
+            diesel::delete($table.filter($column.eq_any(keys))).execute(connection)?;
+            diesel::insert_into($table).values(objects).execute(connection)?;
+        }
     }};
 }
 
 #[cfg(feature = "sqlite")]
 macro_rules! upsert {
-    ($self: expr, $object: expr, $table: expr, $_: expr) => {
-        diesel::replace_into($table).values($object).execute(&$self.pool.get()?)?;
+    ($connection: expr, $object: expr, $table: expr, $_: expr) => {
+        diesel::replace_into($table).values($object).execute($connection)?;
     };
 }
 
@@ -38,8 +82,10 @@ macro_rules! __diesel_type {
     (f64) => {Double};
     (bool) => {Bool};
     (String) => {Text};
+    (Option<Vec<u8>>) => {Nullable<Binary>};
     (Option<$t: ident>) => {Nullable<__diesel_type!($t)>};
     (Vec<u8>) => {Binary};
+    (Vec<u64>) => {Nullable<Text>};
     (LevelRating) => {Text};
     (LevelLength) => {Text};
     (Password) => {Nullable<Text>};
@@ -48,6 +94,7 @@ macro_rules! __diesel_type {
     (MainSong) => {Int2};
     (ModLevel) => {Int2};
     (Color) => {Int4};
+    (Duration) => {Int8};
 }
 
 macro_rules! __ref_if_not_copy {
@@ -64,16 +111,19 @@ macro_rules! __ref_if_not_copy {
     (bool) => {bool};
     (String) => {&'a str};
     (Option<String>) => {Option<&'a str>};
+    (Option<Vec<u8>>) => {Option<&'a [u8]>};
     (Option<$t: ident>) => {Option<__ref_if_not_copy!($t)>};
     (Vec<u8>) => {&'a [u8]};
+    (Vec<u64>) => {Option<String>};
     (LevelRating) => {String};
     (LevelLength) => {String};
-    (Password) => {Option<&'a str>};
+    (Password) => {Option<String>};
     (Featured) => {i32};
     (GameVersion) => {i16};
     (MainSong) => {i16};
     (ModLevel) => {i16};
     (Color) => {i32};
+    (Duration) => {i64};
 }
 
 macro_rules! __row_type {
@@ -89,8 +139,10 @@ macro_rules! __row_type {
     (f32)  => {f32};
     (bool) => {bool};
     (String) => {String};
+    (Option<Vec<u8>>) => {Option<Vec<u8>>};
     (Option<$t: ident>) => {Option<__row_type!($t)>};
     (Vec<u8>) => {Vec<u8>};
+    (Vec<u64>) => {Option<String>};
     (LevelRating) => {String};
     (LevelLength) => {String};
     (Password) => {Option<String>};
@@ -99,6 +151,7 @@ macro_rules! __row_type {
     (MainSong) => {i16};
     (ModLevel) => {i16};
     (Color) => {i32};
+    (Duration) => {i64};
 }
 
 macro_rules! __for_queryable {
@@ -127,15 +180,7 @@ macro_rules! __for_queryable {
         LevelLength::from($value)
     };
     ($value: expr, Password) => {
-        match $value {
-            None => Password::NoCopy,
-            Some(pw) =>
-                if pw == "1" {
-                    Password::FreeCopy
-                } else {
-                    Password::PasswordCopy(pw)
-                },
-        }
+        crate::level::password_from_column($value)
     };
     ($value: expr, Featured) => {{
         Featured::from($value)
@@ -153,6 +198,15 @@ macro_rules! __for_queryable {
             Color::Known($value as u8, ($value >> 8) as u8, ($value >> 16) as u8)
         }
     }};
+    ($value: expr, Duration) => {
+        Duration::from_secs($value as u64)
+    };
+    ($value: expr, Vec<u64>) => {
+        // Stored as a comma separated list, the same format the servers use
+        $value
+            .map(|ids: String| ids.split(',').filter_map(|id| id.parse().ok()).collect())
+            .unwrap_or_default()
+    };
     ($value: expr, $($t:tt)*) => {
         $value
     };
@@ -203,6 +257,10 @@ macro_rules! __for_values {
         let v: Option<&String> = v.as_ref();
         v.map(|inner: &String| &inner[..])
     }};
+    ($value: expr, Option<Vec<u8>>) => {{
+        let v: &Option<Vec<u8>> = &$value;
+        v.as_ref().map(|inner: &Vec<u8>| &inner[..])
+    }};
     ($value: expr, Option<MainSong>) => {
         $value.map(|song| song.main_song_id as i16)
     };
@@ -212,6 +270,15 @@ macro_rules! __for_values {
     ($value: expr, Vec<u8>) => {
         &$value[..]
     };
+    ($value: expr, Vec<u64>) => {{
+        let ids: &Vec<u64> = &$value;
+
+        if ids.is_empty() {
+            None
+        } else {
+            Some(ids.iter().map(ToString::to_string).collect::<Vec<_>>().join(","))
+        }
+    }};
     ($value: expr, LevelRating) => {
         $value.to_string()
     };
@@ -219,11 +286,7 @@ macro_rules! __for_values {
         $value.to_string()
     };
     ($value: expr, Password) => {
-        match $value {
-            Password::NoCopy => None,
-            Password::FreeCopy => Some("1"),
-            Password::PasswordCopy(ref password) => Some(password.as_ref()),
-        }
+        crate::level::password_column(&$value)
     };
     ($value: expr, Featured) => {{
         let value: i32 = $value.into();
@@ -243,15 +306,37 @@ macro_rules! __for_values {
             Color::Known(r, g, b) => r as i32 | (g as i32) << 8 | (b as i32) << 16,
         }
     }};
+    ($value: expr, Duration) => {
+        $value.as_secs() as i64
+    };
     ($value: expr, $($t:tt)*) => {
         &$value
     };
 }
 
+/// Implements [`Stored`](crate::codec::Stored) for objects that are stored exactly as they are
+macro_rules! stored_as_is {
+    ($($object_type: ty),*) => {
+        $(
+            impl crate::codec::Stored for $object_type {
+                type Row = Self;
+
+                fn to_row(&self, _: &dyn crate::codec::BlobCodec) -> std::borrow::Cow<'_, Self> {
+                    std::borrow::Cow::Borrowed(self)
+                }
+
+                fn from_row(row: Self, _: &dyn crate::codec::BlobCodec) -> std::io::Result<Self> {
+                    Ok(row)
+                }
+            }
+        )*
+    };
+}
+
 macro_rules! store_simply {
     ($key_type: ty, $table: ident, $meta: ident, $primary: ident) => {
         fn __impl_store() {
-            use crate::{key::DatabaseKey, meta::Entry, Cache};
+            use crate::{codec::Stored, key::DatabaseKey, meta::Entry, Cache};
             use diesel::RunQueryDsl;
             use gdcf::cache::{Key, Store};
             use log::{debug, warn};
@@ -262,7 +347,7 @@ macro_rules! store_simply {
 
                     let entry = Entry::absent(key.database_key());
 
-                    update_entry!(&self, entry, $meta::table, $meta::$primary);
+                    update_entry!(&self.pool.get()?, entry, $meta::table, $meta::$primary);
 
                     Ok(entry)
                 }
@@ -271,12 +356,22 @@ macro_rules! store_simply {
                     debug!("Storing {} under key {}", object, key);
 
                     let entry = Entry::new(key.database_key());
+                    let connection = self.pool.get()?;
+                    let row = object.to_row(&*self.blob_codec);
 
-                    update_entry!(self, entry, $meta::table, $meta::$primary);
-                    upsert!(self, object, $table::table, $table::$primary);
+                    update_entry!(&connection, entry, $meta::table, $meta::$primary);
+                    upsert!(&connection, &*row, $table::table, $table::$primary);
 
                     Ok(entry)
                 }
+
+                fn store_raw(&mut self, key: &$key_type, raw: &str) -> Result<(), Self::Err> {
+                    self.store_raw_response(stringify!($key_type), key.database_key(), raw)
+                }
+
+                fn transient_meta(&self, key: &$key_type) -> Entry {
+                    Entry::new(key.database_key())
+                }
             }
         }
     };
@@ -284,11 +379,18 @@ macro_rules! store_simply {
 
 macro_rules! lookup_simply {
     ($key_type: ty, $object_table: ident,  $meta_table: ident, $primary_column: ident) => {
+        lookup_simply!($key_type, $object_table, $meta_table, $primary_column, stringify!($key_type));
+    };
+
+    // The last argument is the kind raw responses for this key are stored under, for keys that share
+    // their raw responses with a request type
+    ($key_type: ty, $object_table: ident,  $meta_table: ident, $primary_column: ident, $raw_kind: expr) => {
         fn __impl_lookup() {
-            use crate::{key::DatabaseKey, wrap::Wrapped, Cache, Entry};
-            use diesel::{QueryDsl, RunQueryDsl};
+            use crate::{codec::Stored, key::DatabaseKey, meta::DatabaseEntry, wrap::Wrapped, Cache, Entry};
+            use diesel::{OptionalExtension, QueryDsl, RunQueryDsl};
             use gdcf::cache::{CacheEntry, Key, Lookup};
             use log::{debug, trace};
+            use std::collections::HashMap;
 
             impl Lookup<$key_type> for Cache {
                 fn lookup(&self, key: &$key_type) -> Result<CacheEntry<<$key_type as Key>::Result, Entry>, Self::Err> {
@@ -314,11 +416,74 @@ macro_rules! lookup_simply {
                         return Ok(CacheEntry::MarkedAbsent(entry))
                     }
 
-                    let wrapped: Wrapped<<$key_type as Key>::Result> = handle_missing!($object_table::table
+                    let wrapped: Wrapped<<<$key_type as Key>::Result as Stored>::Row> = handle_missing!($object_table::table
                         .filter($object_table::$primary_column.eq(key.database_key()))
                         .get_result(&connection));
 
-                    Ok(CacheEntry::Cached(wrapped.0, entry))
+                    Ok(CacheEntry::Cached(Stored::from_row(wrapped.0, &*self.blob_codec)?, entry))
+                }
+
+                fn lookup_many(&self, keys: &[$key_type]) -> Result<Vec<CacheEntry<<$key_type as Key>::Result, Entry>>, Self::Err> {
+                    trace!("Performing look up of {} keys in table {}", keys.len(), stringify!($object_table));
+
+                    let connection = self.pool.get()?;
+                    let database_keys: Vec<i64> = keys.iter().map(DatabaseKey::database_key).collect();
+
+                    let entries: HashMap<i64, DatabaseEntry> = $meta_table::table
+                        .filter($meta_table::$primary_column.eq_any(&database_keys))
+                        .load::<DatabaseEntry>(&connection)?
+                        .into_iter()
+                        .map(|entry| (entry.key, entry))
+                        .collect();
+                    let objects: HashMap<i64, Wrapped<<<$key_type as Key>::Result as Stored>::Row>> = $object_table::table
+                        .filter($object_table::$primary_column.eq_any(&database_keys))
+                        .select(($object_table::$primary_column, $object_table::all_columns))
+                        .load(&connection)?
+                        .into_iter()
+                        .collect();
+
+                    database_keys
+                        .iter()
+                        .map(|key| {
+                            let entry = match entries.get(key) {
+                                Some(entry) => self.entry(*entry),
+                                None => return Ok(CacheEntry::Missing),
+                            };
+
+                            if entry.absent {
+                                return Ok(CacheEntry::MarkedAbsent(entry))
+                            }
+
+                            match objects.get(key) {
+                                Some(wrapped) =>
+                                    Ok(CacheEntry::Cached(Stored::from_row(wrapped.0.clone(), &*self.blob_codec)?, entry)),
+                                None => Ok(CacheEntry::Missing),
+                            }
+                        })
+                        .collect()
+                }
+
+                fn contains(&self, key: &$key_type) -> Result<bool, Self::Err> {
+                    let connection = self.pool.get()?;
+
+                    Ok(diesel::select(diesel::dsl::exists(
+                        $meta_table::table.filter($meta_table::$primary_column.eq(key.database_key())),
+                    ))
+                    .get_result(&connection)?)
+                }
+
+                fn lookup_meta(&self, key: &$key_type) -> Result<Option<Entry>, Self::Err> {
+                    let connection = self.pool.get()?;
+
+                    Ok($meta_table::table
+                        .filter($meta_table::$primary_column.eq(key.database_key()))
+                        .get_result(&connection)
+                        .optional()?
+                        .map(|entry| self.entry(entry)))
+                }
+
+                fn lookup_raw(&self, key: &$key_type) -> Result<Option<String>, Self::Err> {
+                    self.lookup_raw_response($raw_kind, key.database_key())
                 }
             }
         }
@@ -355,6 +520,9 @@ macro_rules! diesel_stuff {
             }
         }
 
+        // Allows inserting many objects with a single statement
+        impl diesel::query_builder::UndecoratedInsertRecord<$table_name::table> for $rust_ty {}
+
         #[allow(unused_imports)]
         use diesel::sql_types::*;
 
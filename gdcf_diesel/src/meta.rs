@@ -6,7 +6,10 @@ use diesel::{
     Queryable,
 };
 use gdcf::cache::CacheEntryMeta;
-use std::fmt::{Display, Formatter};
+use std::{
+    fmt::{Display, Formatter},
+    time::Duration,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Entry {
@@ -46,6 +49,19 @@ impl Entry {
     pub fn cached_at(&self) -> NaiveDateTime {
         self.cached_at
     }
+
+    /// How long ago this entry was stored, as of now
+    ///
+    /// Entries stored "in the future" (for example due to clock changes) have an age of zero.
+    pub(crate) fn age_now(cached_at: NaiveDateTime) -> Duration {
+        (Utc::now().naive_utc() - cached_at).to_std().unwrap_or_default()
+    }
+}
+
+impl Default for Entry {
+    fn default() -> Self {
+        Entry::new(0)
+    }
 }
 
 impl Display for Entry {
@@ -66,6 +82,10 @@ impl CacheEntryMeta for Entry {
     fn is_absent(&self) -> bool {
         self.absent
     }
+
+    fn age(&self) -> Option<Duration> {
+        Some(Entry::age_now(self.cached_at))
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -118,5 +138,7 @@ macro_rules! meta_table {
                     .values()
             }
         }
+
+        impl diesel::query_builder::UndecoratedInsertRecord<$name::table> for crate::meta::Entry {}
     };
 }
@@ -0,0 +1,54 @@
+use crate::{Cache, Error};
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+use gdcf::api::client::PageInfo;
+use log::trace;
+
+table! {
+    page_info(request_key) {
+        request_key -> Int8,
+        total -> Int4,
+        page_offset -> Int4,
+        page_size -> Int4,
+    }
+}
+
+impl Cache {
+    /// Stores the pagination information of the response to the request with the given key,
+    /// replacing any previously stored one
+    pub(crate) fn store_page_info_for(&self, key: i64, info: &PageInfo) -> Result<(), Error> {
+        trace!("Storing {:?} for request with key {}", info, key);
+
+        let connection = self.pool.get()?;
+
+        diesel::delete(page_info::table.filter(page_info::request_key.eq(key))).execute(&connection)?;
+
+        diesel::insert_into(page_info::table)
+            .values((
+                page_info::request_key.eq(key),
+                page_info::total.eq(info.total as i32),
+                page_info::page_offset.eq(info.offset as i32),
+                page_info::page_size.eq(info.page_size as i32),
+            ))
+            .execute(&connection)?;
+
+        Ok(())
+    }
+
+    pub(crate) fn lookup_page_info_for(&self, key: i64) -> Result<Option<PageInfo>, Error> {
+        let connection = self.pool.get()?;
+
+        let row: Option<(i32, i32, i32)> = page_info::table
+            .filter(page_info::request_key.eq(key))
+            .select((page_info::total, page_info::page_offset, page_info::page_size))
+            .get_result(&connection)
+            .optional()?;
+
+        Ok(row.map(|(total, offset, page_size)| {
+            PageInfo {
+                total: total as u32,
+                offset: offset as u32,
+                page_size: page_size as u32,
+            }
+        }))
+    }
+}
@@ -1,37 +1,214 @@
-use crate::{key::PartialLevelKey, wrap::Wrapped};
+use crate::{
+    codec::{decode_text, encode_text, BlobCodec, Stored},
+    key::PartialLevelKey,
+    wrap::Wrapped,
+};
 use diesel::{backend::Backend, deserialize::FromSqlRow, insertable::Insertable, ExpressionMethods, Queryable};
 use gdcf_model::{
     level::{Featured, LevelLength, LevelRating, PartialLevel},
+    song::MainSong,
     GameVersion,
 };
+use std::{borrow::Cow, time::Duration};
+
+/// A [`PartialLevel`] in the form it is stored in the database
+///
+/// Its description is passed through the [`BlobCodec`] of the cache storing it, so it is stored as
+/// a blob instead of as text.
+#[derive(Debug, Clone)]
+pub(crate) struct StoredPartialLevel {
+    level_id: u64,
+    name: String,
+    description: Option<Vec<u8>>,
+    version: u32,
+    creator: u64,
+    difficulty: LevelRating,
+    downloads: u32,
+    main_song: Option<&'static MainSong>,
+    gd_version: GameVersion,
+    binary_version: Option<GameVersion>,
+    likes: i32,
+    length: LevelLength,
+    stars: u8,
+    featured: Featured,
+    two_player: bool,
+    copy_of: Option<u64>,
+    custom_song: Option<u64>,
+    coin_amount: u8,
+    coins_verified: bool,
+    stars_requested: Option<u8>,
+    low_detail_available: bool,
+    is_epic: bool,
+    index_43: String,
+    object_amount: Option<u32>,
+    editor_time: Option<Duration>,
+    editor_time_copies: Option<Duration>,
+    song_ids: Vec<u64>,
+    sfx_ids: Vec<u64>,
+}
 
 diesel_stuff! {
-    partial_level (level_id, PartialLevel<Option<u64>, u64>) {
+    partial_level (level_id, StoredPartialLevel) {
         (level_id, level_id, u64),
         (level_name, name, String),
-        (description, description, Option<String>),
+        (description, description, Option<Vec<u8>>),
         (level_version, version, u32),
         (creator_id, creator, u64),
         (difficulty, difficulty, LevelRating),
         (downloads, downloads, u32),
         (main_song, main_song, Option<MainSong>),
         (gd_version, gd_version, GameVersion),
+        (binary_version, binary_version, Option<GameVersion>),
         (likes, likes, i32),
         (level_length, length, LevelLength),
         (stars, stars, u8),
         (featured, featured, Featured),
-        (index_31, index_31, Option<String>),
+        (two_player, two_player, bool),
         (copy_of, copy_of, Option<u64>),
         (custom_song_id, custom_song, Option<u64>),
         (coin_amount, coin_amount, u8),
         (coins_verified, coins_verified, bool),
         (stars_requested, stars_requested, Option<u8>),
-        (index_40, index_40, Option<String>),
+        (low_detail_available, low_detail_available, bool),
         (is_epic, is_epic, bool),
         (index_43, index_43, String),
         (object_amount, object_amount, Option<u32>),
-        (index_46, index_46, Option<String>),
-        (index_47, index_47, Option<String>)
+        (editor_time, editor_time, Option<Duration>),
+        (editor_time_copies, editor_time_copies, Option<Duration>),
+        (song_ids, song_ids, Vec<u64>),
+        (sfx_ids, sfx_ids, Vec<u64>)
+    }
+}
+
+impl Stored for PartialLevel<Option<u64>, u64> {
+    type Row = StoredPartialLevel;
+
+    fn to_row(&self, codec: &dyn BlobCodec) -> Cow<'_, StoredPartialLevel> {
+        let PartialLevel {
+            level_id,
+            name,
+            description,
+            version,
+            creator,
+            difficulty,
+            downloads,
+            main_song,
+            gd_version,
+            binary_version,
+            likes,
+            length,
+            stars,
+            featured,
+            two_player,
+            copy_of,
+            custom_song,
+            coin_amount,
+            coins_verified,
+            stars_requested,
+            low_detail_available,
+            is_epic,
+            index_43,
+            object_amount,
+            editor_time,
+            editor_time_copies,
+            song_ids,
+            sfx_ids,
+        } = self.clone();
+
+        Cow::Owned(StoredPartialLevel {
+            level_id,
+            name,
+            description: description.map(|description| encode_text(codec, &description).into_owned()),
+            version,
+            creator,
+            difficulty,
+            downloads,
+            main_song,
+            gd_version,
+            binary_version,
+            likes,
+            length,
+            stars,
+            featured,
+            two_player,
+            copy_of,
+            custom_song,
+            coin_amount,
+            coins_verified,
+            stars_requested,
+            low_detail_available,
+            is_epic,
+            index_43,
+            object_amount,
+            editor_time,
+            editor_time_copies,
+            song_ids,
+            sfx_ids,
+        })
+    }
+
+    fn from_row(row: StoredPartialLevel, codec: &dyn BlobCodec) -> std::io::Result<Self> {
+        let StoredPartialLevel {
+            level_id,
+            name,
+            description,
+            version,
+            creator,
+            difficulty,
+            downloads,
+            main_song,
+            gd_version,
+            binary_version,
+            likes,
+            length,
+            stars,
+            featured,
+            two_player,
+            copy_of,
+            custom_song,
+            coin_amount,
+            coins_verified,
+            stars_requested,
+            low_detail_available,
+            is_epic,
+            index_43,
+            object_amount,
+            editor_time,
+            editor_time_copies,
+            song_ids,
+            sfx_ids,
+        } = row;
+
+        Ok(PartialLevel {
+            level_id,
+            name,
+            description: description.map(|description| decode_text(codec, description)).transpose()?,
+            version,
+            creator,
+            difficulty,
+            downloads,
+            main_song,
+            gd_version,
+            binary_version,
+            likes,
+            length,
+            stars,
+            featured,
+            two_player,
+            copy_of,
+            custom_song,
+            coin_amount,
+            coins_verified,
+            stars_requested,
+            low_detail_available,
+            is_epic,
+            index_43,
+            object_amount,
+            editor_time,
+            editor_time_copies,
+            song_ids,
+            sfx_ids,
+        })
     }
 }
 
@@ -69,6 +246,50 @@ impl Insertable<level_request_results::table> for (i64, i64) {
 // Metadata table storing information about when a whole request result set was cached
 meta_table!(level_list_meta, request_hash);
 
+// The version of the scheme the keys in `level_list_meta` were derived with (see
+// `REQUEST_KEY_VERSION`). This column isn't part of the generic meta table definition above.
+pub(crate) mod level_list_key_version {
+    table! {
+        level_list_meta(request_hash) {
+            request_hash -> Int8,
+            key_version -> Int8,
+        }
+    }
+}
+
 allow_tables_to_appear_in_same_query!(level_request_results, partial_level);
 
 joinable!(level_request_results -> partial_level(level_id));
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use crate::{
+        key::PartialLevelKey,
+        tests::{partial_level, test_cache},
+    };
+    use gdcf::cache::{CacheEntry, Lookup, Store};
+    use gdcf_model::level::PartialLevel;
+
+    #[test]
+    fn song_and_sfx_ids_survive_storage() {
+        let mut cache = test_cache();
+
+        for (song_ids, sfx_ids) in vec![(vec![10_003_479, 10_002_843], vec![7, 29]), (vec![], vec![])] {
+            let level = PartialLevel {
+                song_ids: song_ids.clone(),
+                sfx_ids: sfx_ids.clone(),
+                ..partial_level(10_565_740)
+            };
+
+            cache.store(&level, &PartialLevelKey(10_565_740)).unwrap();
+
+            match cache.lookup(&PartialLevelKey(10_565_740)).unwrap() {
+                CacheEntry::Cached(level, _) => {
+                    assert_eq!(level.song_ids, song_ids);
+                    assert_eq!(level.sfx_ids, sfx_ids);
+                },
+                entry => panic!("unexpected cache entry {:?}", entry),
+            }
+        }
+    }
+}
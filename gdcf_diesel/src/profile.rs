@@ -54,5 +54,159 @@ diesel_stuff! {
 
 meta_table!(profile_meta, account_id);
 
-store_simply!(UserRequest, profile, profile_meta, account_id);
+stored_as_is!(User);
+
 lookup_simply!(UserRequest, profile, profile_meta, account_id);
+
+// Looking up a profile by its key reads the same tables as looking up the response to a
+// `UserRequest`, including the raw response. The macro needs its own module, since it generates
+// module-level items.
+mod key {
+    use super::{profile, profile_meta};
+    use diesel::ExpressionMethods;
+    use gdcf::cache::UserKey;
+
+    lookup_simply!(UserKey, profile, profile_meta, account_id, "UserRequest");
+}
+
+// Storing a profile also stores the creator it corresponds to. This way, resolving the creator of a
+// level whose creator's profile has already been looked at doesn't need a search request.
+mod store {
+    use super::{profile, profile_meta};
+    #[cfg(feature = "pg")]
+    use crate::wrap::Wrapped;
+    use crate::{
+        creator::{creator, creator_meta},
+        key::DatabaseKey,
+        meta::Entry,
+        Cache,
+    };
+    use diesel::{Connection, RunQueryDsl};
+    use gdcf::{
+        api::request::UserRequest,
+        cache::{CreatorKey, Store},
+    };
+    use gdcf_model::user::{Creator, User};
+    use log::{debug, warn};
+
+    impl Store<UserRequest> for Cache {
+        fn mark_absent(&mut self, key: &UserRequest) -> Result<Entry, Self::Err> {
+            warn!("Marking UserRequest with key {} as absent!", key);
+
+            let entry = Entry::absent(key.database_key());
+
+            update_entry!(&self.pool.get()?, entry, profile_meta::table, profile_meta::account_id);
+
+            Ok(entry)
+        }
+
+        fn store(&mut self, user: &User, key: &UserRequest) -> Result<Entry, Self::Err> {
+            debug!("Storing {} under key {}", user, key);
+
+            let entry = Entry::new(key.database_key());
+            let creator = Creator {
+                user_id: user.user_id,
+                name: user.name.clone(),
+                account_id: Some(user.account_id),
+            };
+            let connection = self.pool.get()?;
+
+            connection.transaction(|| {
+                update_entry!(&connection, entry, profile_meta::table, profile_meta::account_id);
+                upsert!(&connection, user, profile::table, profile::account_id);
+
+                update_entry!(
+                    &connection,
+                    Entry::new(CreatorKey(creator.user_id).database_key()),
+                    creator_meta::table,
+                    creator_meta::user_id
+                );
+                upsert!(&connection, &creator, creator::table, creator::user_id);
+
+                Ok(entry)
+            })
+        }
+
+        fn store_raw(&mut self, key: &UserRequest, raw: &str) -> Result<(), Self::Err> {
+            self.store_raw_response("UserRequest", key.database_key(), raw)
+        }
+
+        fn transient_meta(&self, key: &UserRequest) -> Entry {
+            Entry::new(key.database_key())
+        }
+    }
+}
+
+#[cfg(all(test, feature = "sqlite"))]
+mod tests {
+    use crate::tests::test_cache;
+    use gdcf::{
+        api::request::UserRequest,
+        cache::{CacheEntry, CreatorKey, Lookup, Store, UserKey},
+    };
+    use gdcf_model::user::{Color, Creator, ModLevel, User};
+
+    #[test]
+    fn user_key_finds_raw_user_request_response() {
+        let mut cache = test_cache().retain_raw_responses(true);
+
+        cache.store_raw(&UserRequest::new(71), "1:Alphalaneous").unwrap();
+
+        assert_eq!(cache.lookup_raw(&UserKey(71)).unwrap(), Some("1:Alphalaneous".to_string()));
+    }
+
+    #[test]
+    fn storing_profile_caches_its_creator() {
+        let mut cache = test_cache();
+        let user = User {
+            name: "Alphalaneous".to_string(),
+            user_id: 7,
+            stars: 0,
+            demons: 0,
+            creator_points: 0,
+            primary_color: Color::Known(0, 0, 0),
+            secondary_color: Color::Known(0, 0, 0),
+            secret_coins: 0,
+            account_id: 71,
+            user_coins: 0,
+            index_18: String::new(),
+            index_19: String::new(),
+            youtube_url: None,
+            cube_index: 0,
+            ship_index: 0,
+            ball_index: 0,
+            ufo_index: 0,
+            wave_index: 0,
+            robot_index: 0,
+            has_glow: false,
+            index_29: String::new(),
+            global_rank: None,
+            index_31: String::new(),
+            spider_index: 0,
+            twitter_url: None,
+            twitch_url: None,
+            diamonds: 0,
+            death_effect_index: 0,
+            mod_level: ModLevel::None,
+            index_50: String::new(),
+        };
+
+        cache.store(&user, &UserRequest::new(71)).unwrap();
+
+        // The creator carries the account ID, so upgrading it to a user is a lookup by UserKey
+        match cache.lookup(&CreatorKey(7)).unwrap() {
+            CacheEntry::Cached(creator, _) =>
+                assert_eq!(creator, Creator {
+                    user_id: 7,
+                    name: "Alphalaneous".to_string(),
+                    account_id: Some(71),
+                }),
+            other => panic!("expected creator to be cached, got {:?}", other),
+        }
+
+        match cache.lookup(&UserKey(71)).unwrap() {
+            CacheEntry::Cached(cached, _) => assert_eq!(cached, user),
+            other => panic!("expected user to be cached, got {:?}", other),
+        }
+    }
+}
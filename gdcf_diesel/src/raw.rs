@@ -0,0 +1,61 @@
+use crate::{
+    codec::{decode_text, encode_text},
+    Cache, Error,
+};
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+use log::trace;
+
+table! {
+    raw_response(request_kind, request_key) {
+        request_kind -> Text,
+        request_key -> Int8,
+        body -> Binary,
+    }
+}
+
+impl Cache {
+    /// Stores the given raw response body under the given key, replacing any previously stored one
+    ///
+    /// `kind` distinguishes the different request types, whose database keys might collide. The body
+    /// is passed through the cache's `BlobCodec`. Does nothing unless retaining raw responses has been
+    /// enabled.
+    pub(crate) fn store_raw_response(&self, kind: &str, key: i64, body: &str) -> Result<(), Error> {
+        if !self.retain_raw_responses {
+            return Ok(())
+        }
+
+        trace!("Storing raw response for {} with key {}", kind, key);
+
+        let connection = self.pool.get()?;
+
+        diesel::delete(
+            raw_response::table
+                .filter(raw_response::request_kind.eq(kind))
+                .filter(raw_response::request_key.eq(key)),
+        )
+        .execute(&connection)?;
+
+        diesel::insert_into(raw_response::table)
+            .values((
+                raw_response::request_kind.eq(kind),
+                raw_response::request_key.eq(key),
+                raw_response::body.eq(&*encode_text(&*self.blob_codec, body)),
+            ))
+            .execute(&connection)?;
+
+        Ok(())
+    }
+
+    pub(crate) fn lookup_raw_response(&self, kind: &str, key: i64) -> Result<Option<String>, Error> {
+        let connection = self.pool.get()?;
+
+        let body: Option<Vec<u8>> = raw_response::table
+            .filter(raw_response::request_kind.eq(kind))
+            .filter(raw_response::request_key.eq(key))
+            .select(raw_response::body)
+            .get_result(&connection)
+            .optional()?;
+
+        Ok(body.map(|body| decode_text(&*self.blob_codec, body)).transpose()?)
+    }
+}
@@ -21,7 +21,7 @@ diesel_stuff! {
     newgrounds_song (song_id, NewgroundsSong) {
         (song_id, song_id, u64),
         (song_name, name, String),
-        (index_3, index_3,  u64),
+        (index_3, artist_id, u64),
         (song_artist, artist, String),
         (filesize, filesize, f64),
         (index_6, index_6, Option<String>),
@@ -33,5 +33,20 @@ diesel_stuff! {
 
 meta_table!(song_meta, song_id);
 
+stored_as_is!(NewgroundsSong);
+
 store_simply!(NewgroundsSongKey, newgrounds_song, song_meta, song_id);
 lookup_simply!(NewgroundsSongKey, newgrounds_song, song_meta, song_id);
+
+// Songs retrieved via `SongRequest`s are stored in the same tables as the ones retrieved as
+// secondary objects. The macros need their own module, since they generate module-level items.
+mod request {
+    use super::{newgrounds_song, song_meta};
+    #[cfg(feature = "pg")]
+    use crate::wrap::Wrapped;
+    use diesel::ExpressionMethods;
+    use gdcf::api::request::SongRequest;
+
+    store_simply!(SongRequest, newgrounds_song, song_meta, song_id);
+    lookup_simply!(SongRequest, newgrounds_song, song_meta, song_id);
+}
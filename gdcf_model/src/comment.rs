@@ -3,7 +3,7 @@ use crate::{
     GameMode,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct ProfileComment {
     /// The actual content of the [`ProfileComment`] made.
     ///
@@ -32,7 +32,7 @@ pub struct ProfileComment {
     pub time_since_post: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct LevelComment<User = ()> {
     /// Information about the user that made this [`LevelComment`]. Is generally a [`CommentUser`]
     /// object
@@ -83,11 +83,11 @@ pub struct LevelComment<User = ()> {
     /// This value is provided at index `10`
     pub progress: Option<u8>,
 
-    /// Whether the player that made this [`LevelComment`] is an elder mod
+    /// The moderator badge displayed next to this [`LevelComment`]
     ///
     /// ## GD Internals
     /// This value is provided at index `11`
-    pub is_elder_mod: bool,
+    pub mod_level: ModLevel,
 
     /// If this [`LevelComment`]'s text is displayed in a special color (blue for robtop, green for
     /// elder mods), the RGB code of that color will be stored here
@@ -99,7 +99,20 @@ pub struct LevelComment<User = ()> {
     pub special_color: Option<Color>,
 }
 
-#[derive(Debug)]
+impl<User> LevelComment<User> {
+    /// Whether this [`LevelComment`] was made by a (normal or elder) moderator, as indicated by the
+    /// badge displayed next to it
+    pub fn is_moderator(&self) -> bool {
+        self.mod_level != ModLevel::None
+    }
+
+    /// Whether this [`LevelComment`] was made by an elder moderator
+    pub fn is_elder_mod(&self) -> bool {
+        self.mod_level == ModLevel::Elder
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct CommentUser {
     /// This [`CommentUser`]'s name
     ///
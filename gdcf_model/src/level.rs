@@ -1,9 +1,16 @@
 //! Module containing all models related to Geometry Dash levels
 
 pub mod data;
+pub mod validate;
 
-use crate::{song::MainSong, GameVersion};
-use std::fmt::{Display, Error, Formatter};
+use crate::{level::data::LevelData, song::MainSong, GameVersion};
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt::{Display, Error, Formatter},
+    hash::{Hash, Hasher},
+    time::Duration,
+};
 
 #[cfg(feature = "serde_support")]
 use serde::Deserializer;
@@ -197,6 +204,13 @@ pub enum Password {
 
     /// The level requires the specified password to copy
     PasswordCopy(String),
+
+    /// The level has a password set, but it doesn't decrypt to anything sensible (either to
+    /// nothing at all, or to something that isn't a number). Contains the raw, still encrypted
+    /// value as provided by the servers.
+    ///
+    /// Whether such levels can be copied at all is unclear.
+    Invalid(String),
 }
 
 /// Struct representing partial levels. These are returned to
@@ -231,8 +245,14 @@ pub enum Password {
 /// The following indices aren't used by the Geometry Dash servers: `11`, `16`,
 /// `17`, `20`, `21`, `22`, `23`, `24`, `26`, `31`, `32`, `33`, `34`, `40`,
 /// `41`, `44`
+///
+/// ## Equality:
+/// Equality (and hashing) of [`PartialLevel`]s is identity-based, meaning two [`PartialLevel`]s
+/// are equal if they have the same [`PartialLevel::level_id`], regardless of any other field. This
+/// allows deduplicating levels via a [`HashSet`](std::collections::HashSet). Use
+/// [`PartialLevel::same_level`] to check whether two levels are the same version of a level.
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Clone)]
 pub struct PartialLevel<Song, User> {
     /// The [`Level`]'s unique level id
     ///
@@ -301,6 +321,18 @@ pub struct PartialLevel<Song, User> {
     /// This value is provided at index `13`
     pub gd_version: GameVersion,
 
+    /// The binary version of the client the level was uploaded/last updated with, or [`None`] if
+    /// it is unknown
+    ///
+    /// Unlike the [`gd_version`](PartialLevel::gd_version), which only distinguishes major updates,
+    /// this identifies the exact client build, which allows telling apart levels built in
+    /// different 2.2 betas.
+    ///
+    /// ## GD Internals:
+    /// This value is provided at index `50`, although only for levels uploaded in version 2.2 or
+    /// later. It uses the same encoding as the binary version sent with requests.
+    pub binary_version: Option<GameVersion>,
+
     /// The amount of likes this [`PartialLevel`] has received
     ///
     /// ## GD Internals:
@@ -333,11 +365,11 @@ pub struct PartialLevel<Song, User> {
     /// This value is provided at index `30`
     pub copy_of: Option<u64>,
 
-    // TODO: figure this value out
+    /// Value indicating whether this [`PartialLevel`] is a two player level
     ///
     /// ## GD Internals:
-    /// This value is provided at index `31`
-    pub index_31: Option<String>,
+    /// This value is provided at index `31`, as an integer
+    pub two_player: bool,
 
     /// The id of the newgrounds song this [`PartialLevel`] uses, or [`None`]
     /// if it useds a main song.
@@ -368,11 +400,11 @@ pub struct PartialLevel<Song, User> {
     /// were requested
     pub stars_requested: Option<u8>,
 
-    // TODO: figure this value out
+    /// Value indicating whether this [`PartialLevel`] has a low detail mode
     ///
     /// ## GD Internals:
-    /// This value is provided at index `40`
-    pub index_40: Option<String>,
+    /// This value is provided at index `40`, as an integer
+    pub low_detail_available: bool,
 
     /// Value indicating whether this [`PartialLevel`] is epic
     ///
@@ -395,19 +427,35 @@ pub struct PartialLevel<Song, User> {
     /// in version 2.1 or later. For all older levels this is always `None`
     pub object_amount: Option<u32>,
 
-    /// According to the GDPS source this is always `1`, although that is
-    /// evidently wrong
+    /// The time the creator spent in the editor working on this [`PartialLevel`] itself, or
+    /// [`None`] if the level was uploaded before update 2.2 started tracking it
     ///
     /// ## GD Internals:
-    /// This value is provided at index `46` and seems to be an integer
-    pub index_46: Option<String>,
+    /// This value is provided at index `46`, in seconds
+    pub editor_time: Option<Duration>,
 
-    /// According to the GDPS source, this is always `2`, although that is
-    /// evidently wrong
+    /// The time spent in the editor working on the levels this [`PartialLevel`] was copied from,
+    /// or [`None`] if the level isn't a copy or was uploaded before update 2.2
     ///
     /// ## GD Internals:
-    /// This value is provided at index `47` and seems to be an integer
-    pub index_47: Option<String>,
+    /// This value is provided at index `47`, in seconds
+    pub editor_time_copies: Option<Duration>,
+
+    /// The ids of all newgrounds songs used by this [`PartialLevel`], empty if the level doesn't use
+    /// any songs besides the one in [`PartialLevel::custom_song`]
+    ///
+    /// ## GD Internals:
+    /// This value is provided at index `52` as a comma separated list, although only for levels
+    /// uploaded in version 2.2 or later
+    pub song_ids: Vec<u64>,
+
+    /// The ids of all sound effects used by this [`PartialLevel`], empty if the level doesn't use
+    /// any
+    ///
+    /// ## GD Internals:
+    /// This value is provided at index `53` as a comma separated list, although only for levels
+    /// uploaded in version 2.2 or later
+    pub sfx_ids: Vec<u64>,
 }
 
 impl<Song, User> PartialLevel<Song, User> {
@@ -425,6 +473,88 @@ impl<Song, User> PartialLevel<Song, User> {
             _ => false,
         }
     }
+
+    /// Whether this [`PartialLevel`] is part of the hall of fame
+    ///
+    /// The servers don't provide this information directly. The hall of fame consists of exactly
+    /// the epic levels, so this simply checks [`PartialLevel::is_epic`]. The featured weight is
+    /// deliberately not taken into account: Hall of fame levels have ordinary featured weights, and
+    /// plenty of levels outside of it have weights just as high.
+    pub fn is_hall_of_fame(&self) -> bool {
+        self.is_epic
+    }
+
+    /// Checks whether `self` and `other` describe the same version of the same level
+    ///
+    /// Unlike the [`PartialEq`] implementation, which only compares level ids, this compares all
+    /// fields identifying a version of a level (its id, name, creator and version). It ignores
+    /// volatile fields like the download or like count, making it suitable for detecting whether a
+    /// level actually changed.
+    pub fn same_level(&self, other: &Self) -> bool
+    where
+        User: PartialEq,
+    {
+        self.level_id == other.level_id && self.version == other.version && self.name == other.name && self.creator == other.creator
+    }
+
+    /// Compares the download counts of two levels
+    pub fn by_downloads<S, U>(&self, other: &PartialLevel<S, U>) -> Ordering {
+        self.downloads.cmp(&other.downloads)
+    }
+
+    /// Compares the like counts of two levels
+    ///
+    /// Note that the like count can be negative for disliked levels
+    pub fn by_likes<S, U>(&self, other: &PartialLevel<S, U>) -> Ordering {
+        self.likes.cmp(&other.likes)
+    }
+
+    /// Compares the star rewards of two levels. Unrated levels have a star reward of `0` and thus
+    /// compare lowest
+    pub fn by_stars<S, U>(&self, other: &PartialLevel<S, U>) -> Ordering {
+        self.stars.cmp(&other.stars)
+    }
+
+    /// Compares how recently two levels were uploaded
+    ///
+    /// Level IDs are assigned sequentially, so this simply compares them
+    pub fn by_recency<S, U>(&self, other: &PartialLevel<S, U>) -> Ordering {
+        self.level_id.cmp(&other.level_id)
+    }
+
+    /// Compares two levels by the given [`SortKey`]
+    pub fn compare_by<S, U>(&self, other: &PartialLevel<S, U>, key: SortKey) -> Ordering {
+        match key {
+            SortKey::Downloads => self.by_downloads(other),
+            SortKey::Likes => self.by_likes(other),
+            SortKey::Stars => self.by_stars(other),
+            SortKey::Recency => self.by_recency(other),
+        }
+    }
+}
+
+/// Enum describing the fields levels are commonly sorted by
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum SortKey {
+    /// Sort by download count
+    Downloads,
+
+    /// Sort by like count
+    Likes,
+
+    /// Sort by star reward
+    Stars,
+
+    /// Sort by upload date
+    Recency,
+}
+
+/// Sorts the given levels by the given [`SortKey`], in descending order (meaning the most
+/// downloaded/liked/rewarding/recent level comes first)
+///
+/// The sort is stable, so levels that compare equal keep their relative order.
+pub fn sort_levels<Song, User>(levels: &mut [PartialLevel<Song, User>], key: SortKey) {
+    levels.sort_by(|a, b| b.compare_by(a, key))
 }
 
 // TODO: Consider having only one type parameter that is used as the type for `base`
@@ -439,7 +569,10 @@ impl<Song, User> PartialLevel<Song, User> {
 /// The following indices aren't used by the Geometry Dash servers: `11`, `16`,
 /// `17`, `20`, `21`, `22`, `23`, `24`, `26`, `31`, `32`, `33`, `34`, `40`,
 /// `41`, `44`
-#[derive(Debug, Eq, PartialEq, Clone)]
+///
+/// ## Equality:
+/// Like for [`PartialLevel`]s, equality and hashing of [`Level`]s is based on their level id only
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde_support", derive(Serialize))]
 pub struct Level<Song, User> {
     /// The [`PartialLevel`] this [`Level`] instance supplements
@@ -456,7 +589,7 @@ pub struct Level<Song, User> {
     /// `DEFLATE` compressed
     #[cfg_attr(feature = "serialize_level_data", serde(serialize_with = "base64_encode"))]
     #[cfg_attr(all(feature = "serde_support", not(feature = "serialize_level_data")), serde(skip_serializing))]
-    pub level_data: Vec<u8>,
+    pub level_data: LevelData,
 
     /// The level's password
     ///
@@ -485,6 +618,190 @@ pub struct Level<Song, User> {
     pub index_36: String,
 }
 
+/// Struct representing a level as stored in a player's local save file
+///
+/// ## GD Internals:
+/// Local levels are stored in the `CCLocalLevels.dat` save file, as key-value pairs whose keys
+/// differ from the indices the servers use. Besides the fields below, the save file contains various
+/// editor-only keys (like `kCEK`, which is always `4` for levels). These are ignored when parsing.
+#[derive(Debug, Eq, PartialEq, Clone)]
+#[cfg_attr(feature = "serde_support", derive(Serialize))]
+pub struct LocalLevel {
+    /// The id of the uploaded version of this [`LocalLevel`], or [`None`] if it has never been
+    /// uploaded
+    ///
+    /// ## GD Internals:
+    /// This value is stored under key `k1`, and a value of `0` means that the level was never
+    /// uploaded
+    pub level_id: Option<u64>,
+
+    /// The [`LocalLevel`]'s name
+    ///
+    /// ## GD Internals:
+    /// This value is stored under key `k2`
+    pub name: String,
+
+    /// The [`LocalLevel`]'s description
+    ///
+    /// ## GD Internals:
+    /// This value is stored under key `k3`, and is urlsafe base64 encoded
+    pub description: Option<String>,
+
+    /// The raw level data, in the same format as [`Level::level_data`]
+    ///
+    /// ## GD Internals:
+    /// This value is stored under key `k4`, and is urlsafe base64 encoded and `DEFLATE` compressed
+    #[cfg_attr(feature = "serialize_level_data", serde(serialize_with = "base64_encode"))]
+    #[cfg_attr(all(feature = "serde_support", not(feature = "serialize_level_data")), serde(skip_serializing))]
+    pub level_data: LevelData,
+
+    /// The name of the player that created this [`LocalLevel`]
+    ///
+    /// ## GD Internals:
+    /// This value is stored under key `k5`
+    pub creator_name: String,
+
+    /// The [`LocalLevel`]'s version
+    ///
+    /// ## GD Internals:
+    /// This value is stored under key `k16`
+    pub version: u32,
+
+    /// The amount of attempts made on this [`LocalLevel`]
+    ///
+    /// ## GD Internals:
+    /// This value is stored under key `k18`
+    pub attempts: u32,
+
+    /// The editor folder this [`LocalLevel`] has been placed in, or [`None`] if it hasn't been
+    /// placed in one
+    ///
+    /// ## GD Internals:
+    /// This value is stored under key `k84`, and a value of `0` means no folder
+    pub folder: Option<u32>,
+}
+
+/// Enum describing how a [`LocalLevel`] relates to the version of it uploaded to the servers
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub enum SyncStatus {
+    /// The local level has never been uploaded
+    NotUploaded,
+
+    /// The local level was uploaded under a different level id than the one it was compared to
+    Unrelated,
+
+    /// The level data of both copies is identical
+    InSync,
+
+    /// The local copy has changes that haven't been uploaded yet
+    LocalNewer,
+
+    /// The uploaded copy has changes that the local copy doesn't have
+    ServerNewer,
+
+    /// Both copies have the same version, but different level data, meaning both have been
+    /// modified independently
+    Diverged,
+}
+
+impl<Song, User> Level<Song, User> {
+    /// Gives a very rough estimate of how many attempts an average player needs to beat this
+    /// [`Level`], based on its difficulty and length
+    ///
+    /// This is a heuristic meant for "how grindy is this" displays, nothing more. Returns `None` if
+    /// the level's difficulty or length is unknown (this includes N/A levels).
+    pub fn estimated_attempts_to_complete(&self) -> Option<u32> {
+        let per_tier = match self.base.difficulty {
+            LevelRating::Auto => 1,
+            LevelRating::Easy => 3,
+            LevelRating::Normal => 8,
+            LevelRating::Hard => 20,
+            LevelRating::Harder => 50,
+            LevelRating::Insane => 120,
+            LevelRating::Demon(DemonRating::Easy) => 400,
+            LevelRating::Demon(DemonRating::Medium) => 1_000,
+            LevelRating::Demon(DemonRating::Hard) => 3_000,
+            LevelRating::Demon(DemonRating::Insane) => 8_000,
+            LevelRating::Demon(DemonRating::Extreme) => 25_000,
+            LevelRating::NotAvailable | LevelRating::Unknown(_) | LevelRating::Demon(DemonRating::Unknown(_)) => return None,
+        };
+
+        // Longer levels mean more places to die, but attempts don't scale linearly since the
+        // earlier parts get practiced more often
+        let length_factor = match self.base.length {
+            LevelLength::Tiny => 0.5,
+            LevelLength::Short => 0.75,
+            LevelLength::Medium => 1.0,
+            LevelLength::Long => 1.5,
+            LevelLength::ExtraLong => 2.5,
+            LevelLength::Unknown(_) => return None,
+        };
+
+        Some((f64::from(per_tier) * length_factor).ceil() as u32)
+    }
+}
+
+impl LocalLevel {
+    /// Compares this [`LocalLevel`] to the given [`Level`] downloaded from the servers
+    ///
+    /// The object content is compared after decompressing both copies' level data, so differences in
+    /// compression alone don't count as modifications. If the content differs, the copy with the
+    /// higher version is considered newer. [`Level::time_since_update`] is a rounded,
+    /// human-readable string ("3 weeks") and can't be compared to anything stored locally, so it
+    /// isn't taken into account.
+    pub fn sync_status<Song, User>(&self, server: &Level<Song, User>) -> SyncStatus {
+        match self.level_id {
+            None => return SyncStatus::NotUploaded,
+            Some(level_id) if level_id != server.base.level_id => return SyncStatus::Unrelated,
+            _ => (),
+        }
+
+        let same_content = match (self.decompress_data(), server.decompress_data()) {
+            (Ok(local), Ok(remote)) => local == remote,
+            _ => self.level_data == server.level_data,
+        };
+
+        if same_content {
+            return SyncStatus::InSync
+        }
+
+        match self.version.cmp(&server.base.version) {
+            Ordering::Greater => SyncStatus::LocalNewer,
+            Ordering::Less => SyncStatus::ServerNewer,
+            Ordering::Equal => SyncStatus::Diverged,
+        }
+    }
+}
+
+impl<Song, User> PartialEq for PartialLevel<Song, User> {
+    fn eq(&self, other: &Self) -> bool {
+        self.level_id == other.level_id
+    }
+}
+
+impl<Song, User> Eq for PartialLevel<Song, User> {}
+
+impl<Song, User> Hash for PartialLevel<Song, User> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.level_id.hash(state)
+    }
+}
+
+impl<Song, User> PartialEq for Level<Song, User> {
+    fn eq(&self, other: &Self) -> bool {
+        self.base == other.base
+    }
+}
+
+impl<Song, User> Eq for Level<Song, User> {}
+
+impl<Song, User> Hash for Level<Song, User> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.base.hash(state)
+    }
+}
+
 impl<Song, User> Display for PartialLevel<Song, User> {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
         write!(f, "PartialLevel({}, {})", self.level_id, self.name)
@@ -497,6 +814,12 @@ impl<Song, User> Display for Level<Song, User> {
     }
 }
 
+impl Display for LocalLevel {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "LocalLevel({})", self.name)
+    }
+}
+
 #[cfg(feature = "serde_support")]
 fn deserialize_main_song<'de, D>(deserializer: D) -> Result<Option<&'static MainSong>, D::Error>
 where
@@ -513,7 +836,7 @@ use base64::{encode_config, URL_SAFE};
 use serde::Serializer;
 
 #[cfg(feature = "serialize_level_data")]
-fn base64_encode<S>(level_data: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+fn base64_encode<S>(level_data: &LevelData, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
@@ -530,6 +853,53 @@ impl From<i32> for Featured {
     }
 }
 
+/// Error returned when converting an integer that doesn't correspond to any known demon difficulty
+/// into a [`DemonRating`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct UnknownDemonRating(pub i32);
+
+impl Display for UnknownDemonRating {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "Unknown demon rating {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownDemonRating {}
+
+/// Converts the demon difficulty integers used by the servers (for example at index `43` of level
+/// responses) into a [`DemonRating`]
+///
+/// ## GD Internals:
+/// The values are `3` for easy, `4` for medium, `0` for hard, `5` for insane and `6` for extreme
+/// demons. Note that these differ from both the legacy ratings and the values used in requests.
+impl TryFrom<i32> for DemonRating {
+    type Error = UnknownDemonRating;
+
+    fn try_from(value: i32) -> Result<Self, UnknownDemonRating> {
+        match value {
+            3 => Ok(DemonRating::Easy),
+            4 => Ok(DemonRating::Medium),
+            0 => Ok(DemonRating::Hard),
+            5 => Ok(DemonRating::Insane),
+            6 => Ok(DemonRating::Extreme),
+            value => Err(UnknownDemonRating(value)),
+        }
+    }
+}
+
+impl From<DemonRating> for i32 {
+    fn from(rating: DemonRating) -> Self {
+        match rating {
+            DemonRating::Easy => 3,
+            DemonRating::Medium => 4,
+            DemonRating::Hard => 0,
+            DemonRating::Insane => 5,
+            DemonRating::Extreme => 6,
+            DemonRating::Unknown(value) => value,
+        }
+    }
+}
+
 impl Into<i32> for Featured {
     fn into(self) -> i32 {
         match self {
@@ -624,4 +994,160 @@ impl From<String> for LevelLength {
             _ => LevelLength::Unknown(-1),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sort_levels, DemonRating, Featured, Level, LevelLength, LevelRating, PartialLevel, Password, SortKey, UnknownDemonRating};
+    use crate::GameVersion;
+    use std::{collections::HashSet, convert::TryFrom};
+
+    fn level(downloads: u32) -> PartialLevel<Option<u64>, u64> {
+        PartialLevel {
+            level_id: 10_565_740,
+            name: "Bloodbath".to_string(),
+            description: None,
+            version: 3,
+            creator: 503_085,
+            difficulty: LevelRating::NotAvailable,
+            downloads,
+            main_song: None,
+            gd_version: GameVersion::Unknown,
+            binary_version: None,
+            likes: 0,
+            length: LevelLength::Long,
+            stars: 0,
+            featured: Featured::NotFeatured,
+            copy_of: None,
+            two_player: false,
+            custom_song: Some(467_339),
+            coin_amount: 0,
+            coins_verified: false,
+            stars_requested: None,
+            low_detail_available: false,
+            is_epic: false,
+            index_43: String::new(),
+            object_amount: None,
+            editor_time: None,
+            editor_time_copies: None,
+            song_ids: Vec::new(),
+            sfx_ids: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn hash_set_dedups_by_level_id() {
+        let mut levels = HashSet::new();
+
+        levels.insert(level(100));
+        levels.insert(level(200));
+
+        assert_eq!(levels.len(), 1);
+    }
+
+    fn ranked_level(level_id: u64, downloads: u32, likes: i32, stars: u8) -> PartialLevel<Option<u64>, u64> {
+        let mut ranked = level(downloads);
+
+        ranked.level_id = level_id;
+        ranked.likes = likes;
+        ranked.stars = stars;
+        ranked
+    }
+
+    #[test]
+    fn sort_levels_by_each_key() {
+        let mut levels = vec![
+            ranked_level(1, 500, -20, 0),
+            ranked_level(2, 9000, 300, 10),
+            ranked_level(3, 70, 15, 2),
+            ranked_level(4, 500, 0, 0),
+        ];
+        let ids = |levels: &[PartialLevel<Option<u64>, u64>]| levels.iter().map(|level| level.level_id).collect::<Vec<_>>();
+
+        sort_levels(&mut levels, SortKey::Downloads);
+        assert_eq!(ids(&levels), vec![2, 1, 4, 3]);
+
+        sort_levels(&mut levels, SortKey::Likes);
+        assert_eq!(ids(&levels), vec![2, 3, 4, 1]);
+
+        // Unrated levels compare lowest and keep their relative order
+        sort_levels(&mut levels, SortKey::Stars);
+        assert_eq!(ids(&levels), vec![2, 3, 4, 1]);
+
+        sort_levels(&mut levels, SortKey::Recency);
+        assert_eq!(ids(&levels), vec![4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn same_level_ignores_volatile_fields() {
+        let cached = level(100);
+        let mut fetched = level(200);
+        fetched.likes = 42;
+
+        assert!(cached.same_level(&fetched));
+
+        fetched.version += 1;
+
+        assert!(!cached.same_level(&fetched));
+    }
+
+    fn full_level(difficulty: LevelRating, length: LevelLength) -> Level<Option<u64>, u64> {
+        let mut base = level(0);
+
+        base.difficulty = difficulty;
+        base.length = length;
+
+        Level {
+            base,
+            level_data: Default::default(),
+            password: Password::NoCopy,
+            time_since_upload: String::new(),
+            time_since_update: String::new(),
+            index_36: String::new(),
+        }
+    }
+
+    #[test]
+    fn extreme_demons_take_more_attempts_than_easy_levels() {
+        let easy = full_level(LevelRating::Easy, LevelLength::Short).estimated_attempts_to_complete().unwrap();
+        let extreme = full_level(LevelRating::Demon(DemonRating::Extreme), LevelLength::ExtraLong)
+            .estimated_attempts_to_complete()
+            .unwrap();
+
+        assert!(easy < extreme, "easy level estimated at {} attempts, extreme demon at {}", easy, extreme);
+        assert!(easy > 0);
+    }
+
+    #[test]
+    fn attempts_unknown_without_rating_or_length() {
+        assert_eq!(full_level(LevelRating::NotAvailable, LevelLength::Short).estimated_attempts_to_complete(), None);
+        assert_eq!(
+            full_level(LevelRating::Demon(DemonRating::Unknown(-1)), LevelLength::Long).estimated_attempts_to_complete(),
+            None
+        );
+        assert_eq!(full_level(LevelRating::Easy, LevelLength::Unknown(9)).estimated_attempts_to_complete(), None);
+    }
+
+    #[test]
+    fn demon_rating_round_trips() {
+        let ratings = [
+            (DemonRating::Easy, 3),
+            (DemonRating::Medium, 4),
+            (DemonRating::Hard, 0),
+            (DemonRating::Insane, 5),
+            (DemonRating::Extreme, 6),
+        ];
+
+        for &(rating, value) in &ratings {
+            assert_eq!(i32::from(rating), value);
+            assert_eq!(DemonRating::try_from(value), Ok(rating));
+        }
+    }
+
+    #[test]
+    fn unknown_demon_rating_is_rejected() {
+        assert_eq!(DemonRating::try_from(7), Err(UnknownDemonRating(7)));
+        assert_eq!(DemonRating::try_from(-1), Err(UnknownDemonRating(-1)));
+        assert_eq!(i32::from(DemonRating::Unknown(7)), 7);
+    }
+}
@@ -1,3 +1,5 @@
+pub mod color;
+pub mod difficulty;
 pub mod ids;
 pub mod portal;
 pub mod text;
@@ -5,40 +7,175 @@ pub mod trigger;
 
 use crate::level::{
     data::{
+        color::ColorChannel,
         portal::{PortalData, Speed},
         text::TextData,
         trigger::ColorTriggerData,
     },
-    Level,
+    Level, LocalLevel, PartialLevel,
 };
 use flate2::read::GzDecoder;
-use std::{io::Read, time::Duration};
+use std::{
+    collections::HashSet,
+    io::{BufRead, BufReader, ErrorKind, Read},
+    ops::Deref,
+    rc::Rc,
+    time::Duration,
+};
 
-#[derive(Debug, PartialEq, Clone, Default, Copy)]
+/// Struct representing the metadata section (the "header") at the start of a level string
+///
+/// ## GD Internals:
+/// The header consists of `kA`/`kS` keys describing the level's initial state. It doesn't contain
+/// the level's song, which is only part of the responses to level requests (index `12` for main
+/// songs, index `35` for custom songs). Consequently, the song of a level cannot be cross-checked
+/// against its data.
+#[derive(Debug, PartialEq, Clone, Default)]
 pub struct LevelMetadata {
     pub starting_speed: Speed,
-    pub song_offset: f64,
+
+    /// The point in the song at which playback starts when the level is played
+    ///
+    /// Zero for levels that don't set an offset
+    pub song_offset: Duration,
+
+    /// Whether the song fades in at the start of the level
     pub fade_in: bool,
+
+    /// Whether the song fades out at the end of the level
     pub fade_out: bool,
+
+    /// Whether the level starts in dual mode, with a second icon mirroring the first
+    ///
+    /// `false` for levels that don't set this
     pub dual_start: bool,
+
+    /// Whether the two icons of dual mode are controlled separately, as in two player mode
     pub two_player_controls: bool,
+
+    /// Whether the level starts with upside down gravity
+    ///
+    /// Levels that don't set this start with normal gravity
     pub start_gravity_inverted: bool,
+
+    /// The song guidelines placed in the editor, in the order they are stored in
+    ///
+    /// See [`LevelMetadata::song_guidelines_duration`] for the closest thing to a "song length" the
+    /// level header contains.
+    pub song_guidelines: Vec<Guideline>,
+
+    /// The color channels defined in the level's header
+    pub color_channels: Vec<ColorChannel>,
     // ... other fields in the metadata section ...
 }
 
+impl LevelMetadata {
+    /// The timestamp of the last song guideline placed in the editor, or [`None`] if the level
+    /// has no guidelines
+    ///
+    /// Creators usually place guidelines up until the point the song (or the level) ends.
+    pub fn song_guidelines_duration(&self) -> Option<Duration> {
+        self.song_guidelines
+            .iter()
+            .map(|guideline| guideline.time)
+            .fold(None, |last: Option<f64>, time| Some(last.map_or(time, |last| last.max(time))))
+            .map(|secs| Duration::from_millis((secs * 1000.0) as u64))
+    }
+}
+
+/// A song guideline placed in the level editor
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Guideline {
+    /// The point in the song this guideline is placed at, in seconds
+    pub time: f64,
+
+    /// The raw color value of this guideline, as stored by the editor
+    pub color: f32,
+}
+
+/// Struct representing a single object in a level
+///
+/// The `Data` parameter is the type the object's [`ObjectData`] is stored as. It is either stored
+/// inline, or shared with other objects via an [`ObjectDataPalette`].
 #[derive(Debug, PartialEq, Clone)]
-pub struct LevelObject {
+pub struct LevelObject<Data = ObjectData> {
     pub id: u16,
     pub x: f32,
     pub y: f32,
     pub flipped_x: bool,
     pub flipped_y: bool,
     pub rotation: f32,
+
+    /// The ids of the groups this object is part of, sorted and without duplicates
+    ///
+    /// Triggers (like move or rotate triggers) target objects via these groups.
+    pub groups: Vec<u16>,
+
+    /// Whether this object is hidden when playing in low detail mode
+    ///
+    /// Only meaningful if the level has [`low_detail_available`](crate::level::PartialLevel::low_detail_available) set.
+    pub high_detail: bool,
     // ... other fields they all have ...
-    pub metadata: ObjectData,
+    pub metadata: Data,
+}
+
+impl LevelObject {
+    /// Moves this object's [`ObjectData`] into the given palette, sharing it with all other objects
+    /// whose data was interned into the same palette and is equal
+    pub fn intern(self, palette: &mut ObjectDataPalette) -> LevelObject<Rc<ObjectData>> {
+        LevelObject {
+            id: self.id,
+            x: self.x,
+            y: self.y,
+            flipped_x: self.flipped_x,
+            flipped_y: self.flipped_y,
+            rotation: self.rotation,
+            groups: self.groups,
+            high_detail: self.high_detail,
+            metadata: palette.intern(self.metadata),
+        }
+    }
+}
+
+/// Collection of distinct [`ObjectData`] values shared between level objects
+///
+/// Large levels often consist of tens of thousands of objects with identical data (for example
+/// triggers with the same configuration). Interning their data stores it only once.
+#[derive(Debug, Default)]
+pub struct ObjectDataPalette {
+    entries: HashSet<Rc<ObjectData>>,
+}
+
+impl ObjectDataPalette {
+    pub fn new() -> ObjectDataPalette {
+        ObjectDataPalette::default()
+    }
+
+    /// Gets the shared instance of the given [`ObjectData`], adding it to the palette if it isn't
+    /// part of it yet
+    pub fn intern(&mut self, data: ObjectData) -> Rc<ObjectData> {
+        if let Some(shared) = self.entries.get(&data) {
+            return Rc::clone(shared)
+        }
+
+        let shared = Rc::new(data);
+
+        self.entries.insert(Rc::clone(&shared));
+
+        shared
+    }
+
+    /// The amount of distinct [`ObjectData`] values in this palette
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ObjectData {
     None,
     Portal(PortalData),
@@ -50,16 +187,207 @@ pub enum ObjectData {
 pub struct Stats {
     pub duration: Duration,
     pub object_count: u64,
+
+    /// The amount of distinct group ids objects in the level are part of
+    pub distinct_groups: u64,
+}
+
+impl Stats {
+    /// Gives a very rough estimate of how difficult a level with these stats is, on a scale from
+    /// `0.0` to `10.0`
+    ///
+    /// This is a heuristic based solely on the object density (objects per second of playtime) and
+    /// the duration of the level. It is in no way authoritative and will happily misjudge
+    /// decoration-heavy levels. The estimate grows with the density for levels of the same length,
+    /// and with the length for levels of the same density.
+    pub fn difficulty_estimate(&self) -> f32 {
+        let seconds = self.duration.as_millis() as f32 / 1000.0;
+
+        if seconds <= 0.0 {
+            return 0.0
+        }
+
+        let density = self.object_count as f32 / seconds;
+
+        // Both components saturate, density being the far more important one. Around 100 objects
+        // per second puts a level into the upper half of the scale
+        let density_score = 7.5 * (1.0 - (-density / 100.0).exp());
+        let duration_score = 2.5 * (1.0 - (-seconds / 120.0).exp());
+
+        (density_score + duration_score).min(10.0).max(0.0)
+    }
+}
+
+/// The user coins advertised for a level compared to the coin objects actually placed in it
+#[derive(Debug, PartialEq, Clone)]
+pub struct CoinVerification {
+    /// The amount of coins the level claims to have, see [`PartialLevel::coin_amount`]
+    pub advertised: u8,
+
+    /// The amount of user coin objects found in the level's data
+    pub found: usize,
+
+    /// The `(x, y)` positions of all user coin objects in the level's data, ordered by their x
+    /// position
+    pub positions: Vec<(f32, f32)>,
+}
+
+impl CoinVerification {
+    pub fn new<S, U>(level: &PartialLevel<S, U>, source: impl LevelInformationSource) -> CoinVerification {
+        let positions = source.user_coins();
+
+        CoinVerification {
+            advertised: level.coin_amount,
+            found: positions.len(),
+            positions,
+        }
+    }
+
+    /// Whether the level contains exactly as many coin objects as it advertises
+    pub fn is_consistent(&self) -> bool {
+        self.found == self.advertised as usize
+    }
+}
+
+/// The maximum size of decompressed level data [`Level::decompress_data`] accepts, in bytes
+///
+/// The largest legitimate levels are a few dozen megabytes when decompressed.
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: usize = 256 * 1024 * 1024;
+
+/// The raw level data of a [`Level`] or [`LocalLevel`], still `DEFLATE` compressed
+///
+/// This derefs to the underlying bytes, so it can be fed to any decoder directly.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct LevelData(pub Vec<u8>);
+
+impl LevelData {
+    /// Decompresses the level data, failing if it exceeds [`DEFAULT_MAX_DECOMPRESSED_SIZE`]
+    pub fn decompress(&self) -> std::io::Result<String> {
+        decompress_limited(&self.0, DEFAULT_MAX_DECOMPRESSED_SIZE)
+    }
+
+    /// Decompresses the level data, failing with [`ErrorKind::InvalidData`] if the decompressed
+    /// data exceeds `max_size` bytes
+    pub fn decompress_limited(&self, max_size: usize) -> std::io::Result<String> {
+        decompress_limited(&self.0, max_size)
+    }
+
+    /// Returns a reader decompressing the level data on the fly, failing with
+    /// [`ErrorKind::InvalidData`] once more than [`DEFAULT_MAX_DECOMPRESSED_SIZE`] bytes have been
+    /// decompressed
+    ///
+    /// Unlike [`LevelData::decompress`], this never holds the entire decompressed data in memory.
+    pub fn decompressing_reader(&self) -> impl BufRead + '_ {
+        self.decompressing_reader_limited(DEFAULT_MAX_DECOMPRESSED_SIZE)
+    }
+
+    /// Returns a reader decompressing the level data on the fly, failing with
+    /// [`ErrorKind::InvalidData`] once more than `max_size` bytes have been decompressed
+    pub fn decompressing_reader_limited(&self, max_size: usize) -> impl BufRead + '_ {
+        BufReader::new(LimitedReader {
+            inner: GzDecoder::new(&self.0[..]),
+            remaining: max_size as u64,
+            max_size,
+        })
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl Deref for LevelData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for LevelData {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<Vec<u8>> for LevelData {
+    fn from(data: Vec<u8>) -> Self {
+        LevelData(data)
+    }
+}
+
+impl From<LevelData> for Vec<u8> {
+    fn from(data: LevelData) -> Self {
+        data.0
+    }
 }
 
 impl<S, U> Level<S, U> {
+    /// Decompresses the level data, failing if it exceeds [`DEFAULT_MAX_DECOMPRESSED_SIZE`]
     pub fn decompress_data(&self) -> std::io::Result<String> {
-        let mut s = String::new();
-        let mut d = GzDecoder::new(&self.level_data[..]);
+        self.level_data.decompress()
+    }
 
-        d.read_to_string(&mut s)?;
+    /// Decompresses the level data, failing with [`ErrorKind::InvalidData`] if the decompressed
+    /// data exceeds `max_size` bytes
+    ///
+    /// Decompression stops as soon as the limit is exceeded, so maliciously crafted level data
+    /// cannot inflate to arbitrary sizes.
+    pub fn decompress_data_limited(&self, max_size: usize) -> std::io::Result<String> {
+        self.level_data.decompress_limited(max_size)
+    }
+}
 
-        Ok(s)
+impl LocalLevel {
+    /// Decompresses the level data, failing if it exceeds [`DEFAULT_MAX_DECOMPRESSED_SIZE`]
+    pub fn decompress_data(&self) -> std::io::Result<String> {
+        self.level_data.decompress()
+    }
+}
+
+fn decompress_limited(level_data: &[u8], max_size: usize) -> std::io::Result<String> {
+    let mut s = String::new();
+    let mut d = GzDecoder::new(level_data).take(max_size as u64 + 1);
+
+    d.read_to_string(&mut s)?;
+
+    if s.len() > max_size {
+        return Err(std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!("decompressed level data exceeds {} bytes", max_size),
+        ))
+    }
+
+    Ok(s)
+}
+
+/// Reader failing with [`ErrorKind::InvalidData`] once its inner reader produced more than
+/// `max_size` bytes
+///
+/// Unlike [`Read::take`], which silently ends the data at the limit, this makes oversize data
+/// distinguishable from data that just happens to end there.
+struct LimitedReader<R> {
+    inner: R,
+    remaining: u64,
+    max_size: usize,
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        // Read one byte more than allowed so we notice if the limit is exceeded
+        let len = (buf.len() as u64).min(self.remaining + 1) as usize;
+        let read = self.inner.read(&mut buf[..len])?;
+
+        if read as u64 > self.remaining {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("decompressed level data exceeds {} bytes", self.max_size),
+            ))
+        }
+
+        self.remaining -= read as u64;
+
+        Ok(read)
     }
 }
 
@@ -68,5 +396,123 @@ pub trait LevelInformationSource {
 
     fn stats(self) -> Stats;
 
+    /// Computes the x position at which the gameplay of this level ends
+    ///
+    /// If the level contains an end trigger, its position is returned. Otherwise, this is the
+    /// furthest x position of any object, except for a small number of objects placed far to the
+    /// right of the rest of the level (which are usually decoration or hidden messages).
+    fn finish_x(self) -> f32;
+
+    /// Counts the objects that remain visible in low detail mode, meaning all objects that aren't
+    /// [`high_detail`](LevelObject::high_detail)
+    fn low_detail_object_count(self) -> u64;
+
+    /// Collects the `(x, y)` positions of all user coin objects in this level, ordered by their x
+    /// position
+    fn user_coins(self) -> Vec<(f32, f32)>;
+
     fn metadata(&self) -> LevelMetadata;
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LevelData, Stats};
+    use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+    use std::{
+        io::{BufRead, ErrorKind, Read, Write},
+        time::Duration,
+    };
+
+    fn compress(data: &[u8]) -> LevelData {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        LevelData(encoder.finish().unwrap())
+    }
+
+    fn decode_with<R: Read>(mut reader: R) -> String {
+        let mut decoded = String::new();
+        reader.read_to_string(&mut decoded).unwrap();
+        decoded
+    }
+
+    #[test]
+    fn level_data_can_be_fed_to_any_decoder() {
+        let level_data = compress(b"kA4,0;1,1,2,15,3,15;");
+
+        assert_eq!(decode_with(GzDecoder::new(&level_data[..])), "kA4,0;1,1,2,15,3,15;");
+        assert_eq!(decode_with(GzDecoder::new(level_data.as_ref())), level_data.decompress().unwrap());
+        assert_eq!(level_data.clone().into_inner(), level_data.to_vec());
+    }
+
+    #[test]
+    fn decompress_limited_rejects_oversize_data() {
+        // A megabyte of semicolons compresses to about a kilobyte
+        let level_data = compress(&[b';'; 1024 * 1024]);
+
+        assert!(level_data.len() < 4096);
+        assert_eq!(level_data.decompress_limited(1024).unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(level_data.decompress_limited(1024 * 1024 - 1).unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(level_data.decompress_limited(1024 * 1024).unwrap().len(), 1024 * 1024);
+    }
+
+    #[test]
+    fn decompressing_reader_limited_rejects_oversize_data() {
+        let level_data = compress(&[b';'; 1024 * 1024]);
+        let mut decompressed = Vec::new();
+
+        let err = level_data.decompressing_reader_limited(1024).read_to_end(&mut decompressed).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(decompressed.len() <= 1024);
+
+        decompressed.clear();
+
+        level_data
+            .decompressing_reader_limited(1024 * 1024)
+            .read_to_end(&mut decompressed)
+            .unwrap();
+
+        assert_eq!(decompressed.len(), 1024 * 1024);
+    }
+
+    #[test]
+    fn decompressing_reader_limited_rejects_huge_chunks() {
+        // A single chunk without any separators must not be buffered beyond the limit either
+        let level_data = compress(&[b'a'; 1024 * 1024]);
+        let mut chunks = level_data.decompressing_reader_limited(1024).split(b';');
+
+        assert_eq!(chunks.next().unwrap().unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    fn stats(object_count: u64, seconds: u64) -> Stats {
+        Stats {
+            duration: Duration::from_secs(seconds),
+            object_count,
+            distinct_groups: 0,
+        }
+    }
+
+    #[test]
+    fn denser_levels_are_estimated_harder() {
+        let sparse = stats(1_000, 60).difficulty_estimate();
+        let dense = stats(20_000, 60).difficulty_estimate();
+
+        assert!(sparse < dense, "sparse level estimated at {}, dense one at {}", sparse, dense);
+        assert!(0.0 <= sparse && dense <= 10.0);
+    }
+
+    #[test]
+    fn longer_levels_are_estimated_harder() {
+        let short = stats(3_000, 30).difficulty_estimate();
+        let long = stats(30_000, 300).difficulty_estimate();
+
+        assert!(short < long, "short level estimated at {}, long one at {}", short, long);
+    }
+
+    #[test]
+    fn difficulty_estimate_stays_in_range() {
+        assert_eq!(stats(0, 0).difficulty_estimate(), 0.0);
+        assert_eq!(stats(1_000, 0).difficulty_estimate(), 0.0);
+        assert!(stats(u64::max_value(), 3_600).difficulty_estimate() <= 10.0);
+    }
+}
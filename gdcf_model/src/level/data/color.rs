@@ -0,0 +1,40 @@
+#[cfg(feature = "serde_support")]
+use serde_derive::{Deserialize, Serialize};
+
+/// Struct representing an HSV modification applied to a color
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct HsvData {
+    /// The hue shift, in degrees
+    pub hue: i16,
+
+    /// The saturation factor, or the saturation offset if [`HsvData::saturation_additive`] is set
+    pub saturation: f32,
+
+    /// The brightness factor, or the brightness offset if [`HsvData::value_additive`] is set
+    pub value: f32,
+
+    pub saturation_additive: bool,
+    pub value_additive: bool,
+}
+
+/// Struct representing one of the color channels defined in a level's header
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct ColorChannel {
+    pub id: u16,
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub blending_enabled: bool,
+    pub opacity: f32,
+
+    /// The id of the color channel this channel copies its color from, if any
+    pub copy_ref: Option<i32>,
+
+    /// The HSV modification applied to the copied color
+    ///
+    /// This is separate from the HSV modifications objects can apply to their colors, and is only
+    /// meaningful if [`ColorChannel::copy_ref`] is set
+    pub copy_hsv: Option<HsvData>,
+}
@@ -0,0 +1,48 @@
+//! Module containing a heuristic for estimating how difficult a level is to play
+//!
+//! Everything in here is an estimate computed purely from the level data. It has nothing to do with
+//! the difficulty rating RobTop (or the level's creator) assigned to the level, and is meant for
+//! things like suggesting a rating for unrated levels. The heuristic is expected to change between
+//! versions.
+
+use crate::level::data::{portal::PortalType, LevelObject, Stats};
+
+/// Ids of objects the player directly interacts with, meaning jump orbs, jump pads and dash orbs
+const INTERACTIVE_OBJECTS: [u16; 12] = [35, 36, 67, 84, 140, 141, 1022, 1330, 1332, 1333, 1704, 1751];
+
+/// Estimates how difficult a level is to play, on a scale from `0.0` to `10.0`
+///
+/// This combines the estimate based on the object density from [`Stats::difficulty_estimate`] with
+/// the frequency of speed changes and of orbs and pads the player has to interact with. `objects`
+/// are the objects from which `stats` have been computed.
+///
+/// Note that this is only an estimate, see the [module level documentation](self).
+pub fn difficulty_score(stats: &Stats, objects: &[LevelObject]) -> f32 {
+    let seconds = stats.duration.as_millis() as f32 / 1000.0;
+
+    if seconds <= 0.0 {
+        return 0.0
+    }
+
+    let mut speed_changes = 0;
+    let mut interactive_objects = 0;
+
+    // Objects aren't ordered by their position, so we cannot tell whether a speed portal actually
+    // changes the speed. We simply assume that they do.
+    for object in objects {
+        if let PortalType::Speed(_) = PortalType::from_id(object.id) {
+            speed_changes += 1;
+        } else if INTERACTIVE_OBJECTS.contains(&object.id) {
+            interactive_objects += 1;
+        }
+    }
+
+    let speed_changes_per_minute = speed_changes as f32 * 60.0 / seconds;
+    let interactive_per_second = interactive_objects as f32 / seconds;
+
+    let density_score = 0.6 * stats.difficulty_estimate();
+    let speed_score = 2.0 * (1.0 - (-speed_changes_per_minute / 4.0).exp());
+    let interaction_score = 2.0 * (1.0 - (-interactive_per_second / 2.0).exp());
+
+    (density_score + speed_score + interaction_score).min(10.0).max(0.0)
+}
@@ -4,8 +4,115 @@ pub const MEDIUM_PORTAL: u16 = 202;
 pub const FAST_PORTAL: u16 = 203;
 pub const VERY_FAST_PORTAL: u16 = 1334;
 
+pub const END_TRIGGER: u16 = 3600;
+
+pub const USER_COIN: u16 = 1329;
+
 pub const S_SLOW_PORTAL: &str = "200";
 pub const S_NORMAL_PORTAL: &str = "201";
 pub const S_MEDIUM_PORTAL: &str = "202";
 pub const S_FAST_PORTAL: &str = "203";
 pub const S_VERY_FAST_PORTAL: &str = "1334";
+
+/// Human readable names of well-known objects, sorted by id
+///
+/// This only covers gameplay objects and triggers. Decoration (which makes up the vast majority of
+/// object ids) isn't included.
+static OBJECT_NAMES: &[(u16, &str)] = &[
+    (1, "default block"),
+    (8, "spike"),
+    (10, "blue gravity portal"),
+    (11, "yellow gravity portal"),
+    (12, "cube portal"),
+    (13, "ship portal"),
+    (29, "background color trigger"),
+    (30, "ground color trigger"),
+    (31, "start position"),
+    (35, "yellow jump pad"),
+    (36, "yellow jump ring"),
+    (45, "orange mirror portal"),
+    (46, "blue mirror portal"),
+    (47, "ball portal"),
+    (67, "blue jump pad"),
+    (84, "blue jump ring"),
+    (99, "normal size portal"),
+    (101, "mini size portal"),
+    (111, "ufo portal"),
+    (140, "pink jump pad"),
+    (141, "pink jump ring"),
+    (142, "secret coin"),
+    (SLOW_PORTAL, "slow speed portal"),
+    (NORMAL_PORTAL, "normal speed portal"),
+    (MEDIUM_PORTAL, "medium speed portal"),
+    (FAST_PORTAL, "fast speed portal"),
+    (286, "dual portal"),
+    (287, "dual off portal"),
+    (660, "wave portal"),
+    (745, "robot portal"),
+    (747, "teleport portal"),
+    (899, "color trigger"),
+    (901, "move trigger"),
+    (914, "text"),
+    (1006, "pulse trigger"),
+    (1007, "alpha trigger"),
+    (1022, "green jump ring"),
+    (1049, "toggle trigger"),
+    (1268, "spawn trigger"),
+    (USER_COIN, "user coin"),
+    (1330, "black jump ring"),
+    (1331, "spider portal"),
+    (1332, "red jump pad"),
+    (1333, "red jump ring"),
+    (VERY_FAST_PORTAL, "very fast speed portal"),
+    (1346, "rotate trigger"),
+    (1347, "follow trigger"),
+    (1520, "shake trigger"),
+    (1585, "animate trigger"),
+    (1595, "touch trigger"),
+    (1611, "count trigger"),
+    (1616, "stop trigger"),
+    (1811, "instant count trigger"),
+    (1812, "on death trigger"),
+    (1814, "follow player y trigger"),
+    (1815, "collision trigger"),
+    (1816, "collision block"),
+    (1817, "pickup trigger"),
+    (END_TRIGGER, "end trigger"),
+];
+
+/// Looks up the human readable name of the object with the given id
+///
+/// Returns [`None`] for objects not in the (incomplete) table of well-known objects, which
+/// includes all decoration.
+pub fn object_name(id: u16) -> Option<&'static str> {
+    OBJECT_NAMES
+        .binary_search_by_key(&id, |&(id, _)| id)
+        .ok()
+        .map(|idx| OBJECT_NAMES[idx].1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{object_name, END_TRIGGER, OBJECT_NAMES, USER_COIN};
+
+    #[test]
+    fn well_known_objects_are_named() {
+        assert_eq!(object_name(1), Some("default block"));
+        assert_eq!(object_name(36), Some("yellow jump ring"));
+        assert_eq!(object_name(84), Some("blue jump ring"));
+        assert_eq!(object_name(USER_COIN), Some("user coin"));
+        assert_eq!(object_name(END_TRIGGER), Some("end trigger"));
+    }
+
+    #[test]
+    fn unknown_objects_have_no_name() {
+        assert_eq!(object_name(0), None);
+        assert_eq!(object_name(u16::max_value()), None);
+    }
+
+    #[test]
+    fn object_names_are_sorted_by_id() {
+        // Required for the binary search in `object_name`
+        assert!(OBJECT_NAMES.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+}
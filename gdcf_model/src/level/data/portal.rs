@@ -1,6 +1,6 @@
 use crate::level::data::ids;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Speed {
     Slow,
     Normal,
@@ -29,7 +29,7 @@ impl Into<f32> for Speed {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PortalType {
     Nonsense,
     Speed(Speed),
@@ -60,31 +60,35 @@ impl PortalType {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct PortalData {
     pub checked: bool,
     pub portal_type: PortalType,
 }
 
+/// Computes how many seconds it takes the player to reach the given x position
+///
+/// `portals` are the positions of the speed portals in the level, sorted by x position. The player
+/// starts at `x = 0`, so positions to the left of that take zero seconds, and portals to the left
+/// of that are never passed.
 pub fn get_seconds_from_x_pos(pos: f32, start_speed: Speed, portals: &[(f32, Speed)]) -> f32 {
-    let mut speed: f32 = start_speed.into();
-
-    if portals.is_empty() {
-        return pos / speed
+    if pos <= 0.0 {
+        return 0.0
     }
 
+    let mut speed: f32 = start_speed.into();
     let mut last_obj_pos = 0.0;
     let mut total_time = 0.0;
 
-    for (x, portal_speed) in portals {
-        // distance between last portal and this one
-        let current_segment = x - last_obj_pos;
-
-        // break if we're past the position we want to calculate the position to
-        if pos <= current_segment {
+    for (x, portal_speed) in portals.iter().skip_while(|(x, _)| *x < 0.0) {
+        // portals at or past the position we want to calculate the time to don't matter
+        if pos <= *x {
             break
         }
 
+        // distance between last portal and this one
+        let current_segment = x - last_obj_pos;
+
         // Calculate time spent in this segment and add to total time
         total_time += current_segment / speed;
 
@@ -96,3 +100,53 @@ pub fn get_seconds_from_x_pos(pos: f32, start_speed: Speed, portals: &[(f32, Spe
     // add the time spent between end and last portal to total time and return
     (pos - last_obj_pos) / speed + total_time
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{get_seconds_from_x_pos, Speed};
+
+    fn assert_close(actual: f32, expected: f32) {
+        assert!((actual - expected).abs() <= expected.abs() * 1e-5, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn seconds_without_portals() {
+        assert_close(get_seconds_from_x_pos(3115.8, Speed::Normal, &[]), 10.0);
+        assert_close(get_seconds_from_x_pos(1.0e7, Speed::VeryFast, &[]), 1.0e7 / 576.0);
+    }
+
+    #[test]
+    fn seconds_across_portals() {
+        let portals = [(100.0, Speed::Fast), (1000.0, Speed::Slow)];
+
+        assert_close(get_seconds_from_x_pos(50.0, Speed::Normal, &portals), 50.0 / 311.58);
+        assert_close(get_seconds_from_x_pos(500.0, Speed::Normal, &portals), 100.0 / 311.58 + 400.0 / 468.0);
+        assert_close(
+            get_seconds_from_x_pos(1.0e6, Speed::Normal, &portals),
+            100.0 / 311.58 + 900.0 / 468.0 + 999_000.0 / 251.16,
+        );
+    }
+
+    #[test]
+    fn seconds_increase_with_position() {
+        let portals = [(100.0, Speed::Fast), (1000.0, Speed::Slow), (5000.0, Speed::VeryFast)];
+        let positions = [1.0, 99.0, 100.0, 101.0, 999.0, 1000.0, 4999.0, 5001.0, 1.0e5, 1.0e7, 3.0e38];
+
+        let seconds: Vec<f32> = positions.iter().map(|&x| get_seconds_from_x_pos(x, Speed::Normal, &portals)).collect();
+
+        assert!(seconds.windows(2).all(|pair| pair[0] <= pair[1]), "durations aren't monotonic: {:?}", seconds);
+        assert!(seconds.iter().all(|s| s.is_finite()));
+    }
+
+    #[test]
+    fn negative_positions_and_portals() {
+        let portals = [(-500.0, Speed::VeryFast), (-1.0, Speed::Slow), (200.0, Speed::Fast)];
+
+        assert_eq!(get_seconds_from_x_pos(-1.0e6, Speed::Normal, &portals), 0.0);
+        assert_eq!(get_seconds_from_x_pos(0.0, Speed::Normal, &portals), 0.0);
+
+        // Portals left of the start are never passed
+        assert_close(get_seconds_from_x_pos(100.0, Speed::Normal, &portals), 100.0 / 311.58);
+        assert_close(get_seconds_from_x_pos(400.0, Speed::Normal, &portals), 200.0 / 311.58 + 200.0 / 468.0);
+    }
+}
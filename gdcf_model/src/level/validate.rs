@@ -0,0 +1,136 @@
+//! Module containing sanity checks for level data
+//!
+//! RobTop's servers happily return levels whose metadata contradicts itself (or the actual level
+//! data). The checks in here only look at already parsed data and never modify anything.
+
+use crate::level::{data::Stats, Featured, Level, LevelLength, LevelRating};
+use std::{
+    fmt::{Display, Formatter},
+    time::Duration,
+};
+
+/// An inconsistency found while validating a [`Level`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// The name of the field the issue was found in
+    pub field: &'static str,
+
+    /// Human readable description of the issue
+    pub issue: String,
+}
+
+impl Warning {
+    fn new(field: &'static str, issue: impl Into<String>) -> Self {
+        Warning { field, issue: issue.into() }
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.issue)
+    }
+}
+
+/// Calculates the [`LevelLength`] the game would assign to a level of the given duration
+fn length_for_duration(duration: Duration) -> LevelLength {
+    match duration.as_secs() {
+        0..=9 => LevelLength::Tiny,
+        10..=29 => LevelLength::Short,
+        30..=59 => LevelLength::Medium,
+        60..=119 => LevelLength::Long,
+        _ => LevelLength::ExtraLong,
+    }
+}
+
+fn length_index(length: LevelLength) -> Option<i32> {
+    match length {
+        LevelLength::Unknown(_) => None,
+        LevelLength::Tiny => Some(0),
+        LevelLength::Short => Some(1),
+        LevelLength::Medium => Some(2),
+        LevelLength::Long => Some(3),
+        LevelLength::ExtraLong => Some(4),
+    }
+}
+
+impl<Song, User> Level<Option<Song>, User> {
+    /// Checks the metadata of this level for inconsistencies
+    ///
+    /// This only checks the fields of the level against each other. Use
+    /// [`Level::validate_with_stats`] to additionally check them against the actual level data.
+    pub fn validate(&self) -> Vec<Warning> {
+        let base = &self.base;
+        let mut warnings = Vec::new();
+
+        if base.object_amount == Some(0) && !self.level_data.is_empty() {
+            warnings.push(Warning::new("object_amount", "object amount is zero, but the level data is not empty"))
+        }
+
+        if base.custom_song.is_some() && base.main_song.is_some() {
+            warnings.push(Warning::new("custom_song", "both a custom song and a main song are set"))
+        }
+
+        if base.coin_amount > 3 {
+            warnings.push(Warning::new(
+                "coin_amount",
+                format!("levels can have at most 3 coins, but {} are advertised", base.coin_amount),
+            ))
+        }
+
+        if base.coins_verified && base.coin_amount == 0 {
+            warnings.push(Warning::new("coins_verified", "coins are marked as verified, but the level has no coins"))
+        }
+
+        if base.stars > 0 && base.difficulty == LevelRating::NotAvailable {
+            warnings.push(Warning::new("stars", "level is rated, but its difficulty is N/A"))
+        }
+
+        if base.is_epic && base.featured == Featured::NotFeatured {
+            warnings.push(Warning::new("is_epic", "level is epic, but not featured"))
+        }
+
+        if let LevelLength::Unknown(value) = base.length {
+            warnings.push(Warning::new("length", format!("unknown level length {}", value)))
+        }
+
+        warnings
+    }
+
+    /// Checks the metadata of this level for inconsistencies, both internally (see
+    /// [`Level::validate`]) and against the given [`Stats`] computed from the level data
+    pub fn validate_with_stats(&self, stats: &Stats) -> Vec<Warning> {
+        let mut warnings = self.validate();
+
+        if let Some(object_amount) = self.base.object_amount {
+            if object_amount != 0 && u64::from(object_amount) != stats.object_count {
+                warnings.push(Warning::new(
+                    "object_amount",
+                    format!(
+                        "advertised object amount {} does not match the {} objects in the level data",
+                        object_amount, stats.object_count
+                    ),
+                ))
+            }
+        }
+
+        // The duration in the stats is only an estimate, so we only complain about lengths that are
+        // off by more than one category
+        let computed = length_for_duration(stats.duration);
+
+        if let (Some(advertised_index), Some(computed_index)) = (length_index(self.base.length), length_index(computed)) {
+            if (advertised_index - computed_index).abs() > 1 {
+                warnings.push(Warning::new(
+                    "length",
+                    format!(
+                        "advertised length {:?} does not match the computed duration of {}s ({:?})",
+                        self.base.length,
+                        stats.duration.as_secs(),
+                        computed
+                    ),
+                ))
+            }
+        }
+
+        warnings
+    }
+}
@@ -1,6 +1,9 @@
 //! Module containing all models releated to Songs
 
-use std::fmt::{Display, Error, Formatter};
+use std::{
+    fmt::{Display, Error, Formatter},
+    time::Duration,
+};
 
 #[cfg(feature = "serde_support")]
 use serde_derive::{Deserialize, Serialize};
@@ -42,10 +45,15 @@ pub struct NewgroundsSong {
     /// The name of this [`NewgroundsSong`]
     ///
     /// ## GD Internals:
-    /// This value is provided at index `2`
+    /// This value is provided at index `2`. Unlike the [`link`](NewgroundsSong::link), it isn't
+    /// encoded in any way, so a `+` in it is a literal plus sign
     pub name: String,
 
-    pub index_3: u64,
+    /// The newgrounds id of the artist of this [`NewgroundsSong`]
+    ///
+    /// ## GD Internals:
+    /// This value is provided at index `3`
+    pub artist_id: u64,
 
     /// The artist of this [`NewgroundsSong`]
     ///
@@ -56,7 +64,8 @@ pub struct NewgroundsSong {
     /// The filesize of this [`NewgroundsSong`], in megabytes
     ///
     /// ## GD Internals:
-    /// This value is provided at index `5`
+    /// This value is provided at index `5`. Depending on the server's locale, it sometimes uses a
+    /// comma as decimal separator
     pub filesize: f64,
 
     pub index_6: Option<String>,
@@ -69,10 +78,67 @@ pub struct NewgroundsSong {
     /// The direct `audio.ngfiles.com` download link for this [`NewgroundsSong`]
     ///
     /// ## GD Internals:
-    /// This value is provided at index `10`, and is percent encoded.
+    /// This value is provided at index `10`, and is form encoded (percent encoded, with spaces
+    /// encoded as `+`).
     pub link: String,
 }
 
+/// Struct representing the newgrounds artist of a [`NewgroundsSong`]
+///
+/// The Geometry Dash servers only provide an artist's newgrounds id and name as part of the songs
+/// they made. There is no endpoint to retrieve anything else about artists (their other songs,
+/// whether they are whitelisted, ...).
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct Artist {
+    /// The newgrounds id of this [`Artist`]
+    pub artist_id: u64,
+
+    /// The name of this [`Artist`]
+    pub name: String,
+}
+
+impl Artist {
+    /// The url of this [`Artist`]'s newgrounds page
+    ///
+    /// Newgrounds uses the lowercase username as subdomain, with spaces being removed
+    pub fn page_url(&self) -> String {
+        format!("https://{}.newgrounds.com", self.name.replace(' ', "").to_lowercase())
+    }
+}
+
+/// The bitrate newgrounds audio files are assumed to be encoded with, in kilobits per second
+///
+/// Newgrounds re-encodes uploaded audio to 128kbps mp3s in most cases, though some older or
+/// differently encoded submissions deviate from this.
+pub const ASSUMED_SONG_BITRATE: u32 = 128;
+
+impl NewgroundsSong {
+    /// Gets the [`Artist`] of this [`NewgroundsSong`]
+    pub fn artist_info(&self) -> Artist {
+        Artist {
+            artist_id: self.artist_id,
+            name: self.artist.clone(),
+        }
+    }
+
+    /// Estimates the playback duration of this [`NewgroundsSong`] from its
+    /// [`filesize`](NewgroundsSong::filesize), or returns [`None`] if the filesize is unknown (zero)
+    ///
+    /// ## GD Internals:
+    /// The servers don't provide the duration of songs. The estimate assumes the file is encoded
+    /// with a constant bitrate of [`ASSUMED_SONG_BITRATE`].
+    pub fn estimated_duration(&self) -> Option<Duration> {
+        if self.filesize <= 0.0 {
+            return None
+        }
+
+        let bits = self.filesize * 1024.0 * 1024.0 * 8.0;
+
+        Some(Duration::from_secs_f64(bits / f64::from(ASSUMED_SONG_BITRATE * 1000)))
+    }
+}
+
 impl MainSong {
     const fn new(main_song_id: u8, name: &'static str, artist: &'static str) -> MainSong {
         MainSong {
@@ -118,6 +184,38 @@ pub const UNKNOWN: MainSong = MainSong::new(
     "Please either update to the newest version, or bug stadust about adding the new songs",
 );
 
+/// The song a level uses, which is either one of the [`MainSong`]s or a custom [`NewgroundsSong`]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde_support", derive(Serialize))]
+pub enum Song {
+    Main(&'static MainSong),
+    Custom(NewgroundsSong),
+}
+
+impl Song {
+    /// The name of this [`Song`]
+    pub fn name(&self) -> &str {
+        match self {
+            Song::Main(song) => song.name,
+            Song::Custom(song) => &song.name,
+        }
+    }
+
+    /// The name of the artist of this [`Song`]
+    pub fn artist(&self) -> &str {
+        match self {
+            Song::Main(song) => song.artist,
+            Song::Custom(song) => &song.artist,
+        }
+    }
+}
+
+impl Display for Song {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        write!(f, "{} by {}", self.name(), self.artist())
+    }
+}
+
 impl Display for NewgroundsSong {
     fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
         write!(f, "NewgroundsSong({}, {} by {})", self.song_id, self.name, self.artist)
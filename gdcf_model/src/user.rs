@@ -62,6 +62,9 @@ pub struct Creator {
     pub name: String,
 
     /// The [`Creator`]'s unique account ID
+    ///
+    /// [`None`] for creators without a registered account. The servers report these with an
+    /// account ID of `0` (or none at all).
     pub account_id: Option<u64>,
 }
 
@@ -79,6 +82,14 @@ impl Creator {
             account_id: None,
         }
     }
+
+    /// Whether this [`Creator`] has a registered account
+    ///
+    /// Only registered creators have a profile, so only those can be upgraded to a full
+    /// [`User`]
+    pub fn is_registered(&self) -> bool {
+        self.account_id.is_some()
+    }
 }
 
 /// Struct representing a Geometry Dash User
@@ -23,7 +23,7 @@ parser! {
         is_flagged_spam(index = 7),
         time_since_post(index = 9),
         progress(index = 10, default),
-        is_elder_mod(index = 11, parse = TwoBool, optional),
+        mod_level(index = 11, default),
         special_color(index = 12, parse = RGBColor, optional_non_default),
     }
 }
@@ -41,3 +41,54 @@ parser! {
 }
 
 fn dummy() {}
+
+#[cfg(test)]
+mod tests {
+    use crate::Parse;
+    use gdcf_model::{
+        comment::{CommentUser, LevelComment},
+        user::{Color, ModLevel},
+    };
+
+    const COMMENT: &str = "2~R0cgbWFuIHRoaXMgbGV2ZWwgaXMgaW5zYW5l~3~5509312~4~42~7~0~10~87~9~2 months~6~34523233~11~2~12~75,255,75";
+    const USER: &str = "1~Riot~9~4~10~15~11~12~14~0~15~2~16~1311773";
+
+    #[test]
+    fn level_comment_populates_all_fields() {
+        let comment = LevelComment::parse_str(COMMENT, '~').unwrap();
+
+        assert_eq!(comment.content, Some("GG man this level is insane".to_string()));
+        assert_eq!(comment.user_id, "5509312");
+        assert_eq!(comment.likes, 42);
+        assert_eq!(comment.comment_id, "34523233");
+        assert!(!comment.is_flagged_spam);
+        assert_eq!(comment.time_since_post, "2 months");
+        assert_eq!(comment.progress, Some(87));
+        assert_eq!(comment.mod_level, ModLevel::Elder);
+        assert_eq!(comment.special_color, Some(Color::Known(75, 255, 75)));
+        assert!(comment.is_moderator());
+        assert!(comment.is_elder_mod());
+    }
+
+    #[test]
+    fn level_comment_without_optional_fields() {
+        let comment = LevelComment::parse_str("2~~3~16~4~-3~7~0~9~1 day~6~1", '~').unwrap();
+
+        assert_eq!(comment.content, Some(String::new()));
+        assert_eq!(comment.likes, -3);
+        assert_eq!(comment.progress, None);
+        assert_eq!(comment.mod_level, ModLevel::None);
+        assert_eq!(comment.special_color, None);
+        assert!(!comment.is_moderator());
+    }
+
+    #[test]
+    fn comment_user() {
+        let user = CommentUser::parse_str(USER, '~').unwrap();
+
+        assert_eq!(user.name, "Riot");
+        assert_eq!(user.icon_index, 4);
+        assert!(user.has_glow);
+        assert_eq!(user.account_id, Some(1_311_773));
+    }
+}
@@ -1,11 +1,21 @@
-use crate::util::{self, b64_decode_string, xor_decrypt};
+use crate::{
+    util::{self, b64_decode_string, xor_decrypt, SelfZipExt},
+    Parse,
+};
 use gdcf_model::{
-    level::{data::portal::Speed, DemonRating, Featured, LevelLength, LevelRating, Password},
+    level::{
+        data::{
+            color::{ColorChannel, HsvData},
+            portal::Speed,
+            Guideline, LevelData,
+        },
+        DemonRating, Featured, LevelLength, LevelRating, Password,
+    },
     user::{Color, ModLevel},
     GameMode, GameVersion,
 };
 use percent_encoding::{percent_decode, percent_encode, SIMPLE_ENCODE_SET};
-use std::{num::ParseIntError, str::FromStr};
+use std::{num::ParseIntError, str::FromStr, time::Duration};
 
 /// Trait for converting objects of type `Self` into RobTop's data format of type `T` (most commonly
 /// `T = String`)
@@ -32,6 +42,11 @@ pub trait RobtopInto<Conv, T> {
         self.robtop_into()
     }
 
+    /// Whether this value can be left out when unparsing an optional field
+    ///
+    /// Returns `true` if the servers would send this value as an empty string (or not at all), so
+    /// that parsing the unparsed data yields the same value again. Defaults to `false`, meaning the
+    /// value is always emitted.
     fn can_omit(&self) -> bool {
         false
     }
@@ -358,19 +373,23 @@ impl RobtopFrom<Password, &str> for Password {
     ///
     /// # Errors
     /// If the given string isn't `"0"` and also isn't valid URL-safe base64, a
-    /// [`DecodeError`] is returned
+    /// [`DecodeError`] is returned. Values that are valid base64 but don't decrypt to a password
+    /// are parsed into [`Password::Invalid`]
     fn robtop_from(encrypted: &str) -> Result<Password, String> {
         match encrypted {
             "0" => Ok(Password::NoCopy),
             pass => {
                 let decoded = b64_decode_string(pass).map_err(|e| e.to_string())?;
-                let mut decrypted = xor_decrypt(&decoded, "26364");
+                let decrypted = xor_decrypt(&decoded, "26364");
+
+                // The first character is a marker we don't care about, everything after it is the
+                // password itself
+                let password = decrypted.get(1..);
 
-                if decrypted.len() == 1 {
-                    Ok(Password::FreeCopy)
-                } else {
-                    decrypted.remove(0);
-                    Ok(Password::PasswordCopy(decrypted))
+                match password {
+                    Some("") => Ok(Password::FreeCopy),
+                    Some(password) if password.bytes().all(|b| b.is_ascii_digit()) => Ok(Password::PasswordCopy(password.to_string())),
+                    _ => Ok(Password::Invalid(pass.to_string())),
                 }
             },
         }
@@ -383,6 +402,7 @@ impl RobtopInto<Password, String> for Password {
             Password::NoCopy => return "0".to_string(),
             Password::FreeCopy => xor_decrypt("1", "26364"),
             Password::PasswordCopy(pw) => xor_decrypt(&format!("0{}", pw), "26364"),
+            Password::Invalid(raw) => return raw,
         };
 
         base64::encode_config(&encrypted, base64::URL_SAFE)
@@ -430,7 +450,7 @@ impl RobtopInto<ModLevel, String> for ModLevel {
 impl RobtopFrom<ModLevel, &str> for ModLevel {
     fn robtop_from(t: &str) -> Result<ModLevel, String> {
         Ok(match t {
-            "0" => ModLevel::None,
+            "0" | "" => ModLevel::None,
             "1" => ModLevel::Normal,
             "2" => ModLevel::Elder,
             t => ModLevel::Unknown(u8::robtop_from(t)?),
@@ -554,6 +574,18 @@ impl RobtopInto<Base64BytesConverter, String> for Vec<u8> {
     }
 }
 
+impl RobtopFrom<LevelData, &str> for Base64BytesConverter {
+    fn robtop_from(s: &str) -> Result<LevelData, String> {
+        Base64BytesConverter::robtop_from(s).map(LevelData)
+    }
+}
+
+impl RobtopInto<Base64BytesConverter, String> for LevelData {
+    fn robtop_into(self) -> String {
+        self.into_inner().robtop_into()
+    }
+}
+
 pub struct Base64Converter;
 
 impl RobtopFromInfallible<Option<String>, &str> for Base64Converter {
@@ -569,6 +601,10 @@ impl RobtopInto<Base64Converter, String> for Option<String> {
             None => String::new(),
         }
     }
+
+    fn can_omit(&self) -> bool {
+        self.is_none()
+    }
 }
 
 impl RobtopFrom<String, &str> for Base64Converter {
@@ -583,6 +619,283 @@ impl RobtopInto<Base64Converter, String> for String {
     }
 }
 
+/// Converter for the song guidelines in a level's header
+///
+/// The guideline string has the form `time~color~time~color~...~`, with the times given in seconds.
+pub struct GuidelinesConverter;
+
+impl RobtopFrom<Vec<Guideline>, &str> for GuidelinesConverter {
+    fn robtop_from(s: &str) -> Result<Vec<Guideline>, String> {
+        let values = s.split('~').filter(|value| !value.is_empty()).collect::<Vec<_>>();
+
+        values
+            .chunks(2)
+            .map(|guideline| {
+                let (time, color) = match guideline {
+                    [time, color] => (f64::robtop_from(time)?, f32::robtop_from(color)?),
+                    _ => return Err(format!("Guideline at {} has no color", guideline[0])),
+                };
+
+                if time < 0.0 {
+                    return Err(format!("Negative guideline timestamp {}", time))
+                }
+
+                Ok(Guideline { time, color })
+            })
+            .collect()
+    }
+}
+
+impl RobtopInto<GuidelinesConverter, String> for Vec<Guideline> {
+    fn robtop_into(self) -> String {
+        self.into_iter()
+            .map(|guideline| format!("{}~{}~", guideline.time, guideline.color))
+            .collect()
+    }
+
+    fn can_omit(&self) -> bool {
+        self.is_empty()
+    }
+}
+
+/// Converter for decimal numbers that might use a comma as their decimal separator
+///
+/// Some values (like the filesize of newgrounds songs) are formatted using the locale of the
+/// server, which sometimes results in values like `1,23` instead of `1.23`.
+pub struct LocaleDecimalConverter;
+
+impl RobtopFrom<f64, &str> for LocaleDecimalConverter {
+    fn robtop_from(s: &str) -> Result<f64, String> {
+        s.replace(',', ".").parse().map_err(|err: std::num::ParseFloatError| err.to_string())
+    }
+}
+
+impl RobtopInto<LocaleDecimalConverter, String> for f64 {
+    fn robtop_into(self) -> String {
+        self.to_string()
+    }
+}
+
+/// Converter for durations given in whole seconds
+///
+/// Robtop uses `0` for durations that weren't tracked, which is converted to [`None`].
+pub struct SecondsConverter;
+
+impl RobtopFrom<Option<Duration>, &str> for SecondsConverter {
+    fn robtop_from(s: &str) -> Result<Option<Duration>, String> {
+        match u64::robtop_from(s)? {
+            0 => Ok(None),
+            secs => Ok(Some(Duration::from_secs(secs))),
+        }
+    }
+}
+
+impl RobtopInto<SecondsConverter, String> for Option<Duration> {
+    fn robtop_into(self) -> String {
+        self.map(|duration| duration.as_secs()).unwrap_or(0).to_string()
+    }
+}
+
+/// Converter for game versions that might not be known, in which case they are given as `0`
+pub struct OptionalVersionConverter;
+
+impl RobtopFrom<Option<GameVersion>, &str> for OptionalVersionConverter {
+    fn robtop_from(s: &str) -> Result<Option<GameVersion>, String> {
+        match u8::robtop_from(s)? {
+            0 => Ok(None),
+            version => Ok(Some(GameVersion::from(version))),
+        }
+    }
+}
+
+impl RobtopInto<OptionalVersionConverter, String> for Option<GameVersion> {
+    fn robtop_into(self) -> String {
+        self.map(RobtopInto::<GameVersion, String>::robtop_into).unwrap_or_else(|| "0".to_string())
+    }
+
+    fn can_omit(&self) -> bool {
+        self.is_none()
+    }
+}
+
+/// Converter for durations given in (possibly fractional) seconds
+pub struct FractionalSecondsConverter;
+
+impl RobtopFrom<Duration, &str> for FractionalSecondsConverter {
+    fn robtop_from(s: &str) -> Result<Duration, String> {
+        let secs = f64::robtop_from(s)?;
+
+        if !secs.is_finite() || secs < 0.0 {
+            return Err(format!("Invalid duration of {} seconds", secs))
+        }
+
+        Ok(Duration::from_millis((secs * 1000.0) as u64))
+    }
+}
+
+impl RobtopInto<FractionalSecondsConverter, String> for Duration {
+    fn robtop_into(self) -> String {
+        (self.as_millis() as f64 / 1000.0).to_string()
+    }
+}
+
+/// Converter for object coordinates
+///
+/// Coordinates can be negative (for example for decoration below the ground) and very large in
+/// long levels, but they are always finite. Accepting `NaN` would break every computation that
+/// orders objects by their position.
+pub struct CoordinateConverter;
+
+impl RobtopFrom<f32, &str> for CoordinateConverter {
+    fn robtop_from(s: &str) -> Result<f32, String> {
+        let coordinate = f32::robtop_from(s)?;
+
+        if !coordinate.is_finite() {
+            return Err(format!("Invalid coordinate {}", coordinate))
+        }
+
+        Ok(coordinate)
+    }
+}
+
+impl RobtopInto<CoordinateConverter, String> for f32 {
+    fn robtop_into(self) -> String {
+        self.to_string()
+    }
+}
+
+/// Converter for HSV modifications
+///
+/// HSV modifications have the form `hue a saturation a value a saturation_additive a value_additive`.
+/// An empty string means that no modification is applied.
+pub struct HsvConverter;
+
+impl RobtopFrom<Option<HsvData>, &str> for HsvConverter {
+    fn robtop_from(s: &str) -> Result<Option<HsvData>, String> {
+        if s.is_empty() {
+            return Ok(None)
+        }
+
+        let parts: Vec<&str> = s.split('a').collect();
+
+        if parts.len() != 5 {
+            return Err(format!("HSV modification '{}' doesn't have exactly 5 components", s))
+        }
+
+        Ok(Some(HsvData {
+            hue: i16::robtop_from(parts[0])?,
+            saturation: f32::robtop_from(parts[1])?,
+            value: f32::robtop_from(parts[2])?,
+            saturation_additive: bool::robtop_from(parts[3])?,
+            value_additive: bool::robtop_from(parts[4])?,
+        }))
+    }
+}
+
+impl RobtopInto<HsvConverter, String> for Option<HsvData> {
+    fn robtop_into(self) -> String {
+        match self {
+            Some(hsv) =>
+                format!(
+                    "{}a{}a{}a{}a{}",
+                    hsv.hue,
+                    hsv.saturation,
+                    hsv.value,
+                    RobtopInto::<bool, String>::robtop_into(hsv.saturation_additive),
+                    RobtopInto::<bool, String>::robtop_into(hsv.value_additive)
+                ),
+            None => String::new(),
+        }
+    }
+}
+
+/// Converter for the color channels defined in a level's header
+///
+/// The channels are separated by `|`, and each channel is a `_`-separated list of key-value pairs.
+pub struct ColorChannelsConverter;
+
+impl RobtopFrom<Vec<ColorChannel>, &str> for ColorChannelsConverter {
+    fn robtop_from(s: &str) -> Result<Vec<ColorChannel>, String> {
+        s.split('|')
+            .filter(|channel| !channel.is_empty())
+            // Channels contain plenty of keys we don't care about, so we silently ignore those
+            .map(|channel| ColorChannel::parse(channel.split('_').self_zip(), |_, _| Ok(())).map_err(|err| err.to_string()))
+            .collect()
+    }
+}
+
+impl RobtopInto<ColorChannelsConverter, String> for Vec<ColorChannel> {
+    fn robtop_into(self) -> String {
+        self.into_iter()
+            .map(|channel| {
+                channel
+                    .unparse()
+                    .into_iter()
+                    .map(|(key, value)| format!("{}_{}", key, value))
+                    .collect::<Vec<_>>()
+                    .join("_")
+            })
+            .collect::<Vec<_>>()
+            .join("|")
+    }
+
+    fn can_omit(&self) -> bool {
+        self.is_empty()
+    }
+}
+
+/// Converter for the list of group ids an object is part of
+///
+/// The list has the form `id.id.id...`. Parsing sorts the ids and removes duplicates.
+pub struct GroupsConverter;
+
+impl RobtopFrom<Vec<u16>, &str> for GroupsConverter {
+    fn robtop_from(s: &str) -> Result<Vec<u16>, String> {
+        let mut groups = s
+            .split('.')
+            .filter(|group| !group.is_empty())
+            .map(|group| group.parse().map_err(|err: ParseIntError| format!("Invalid group id '{}': {}", group, err)))
+            .collect::<Result<Vec<u16>, _>>()?;
+
+        groups.sort_unstable();
+        groups.dedup();
+
+        Ok(groups)
+    }
+}
+
+impl RobtopInto<GroupsConverter, String> for Vec<u16> {
+    fn robtop_into(self) -> String {
+        self.iter().map(ToString::to_string).collect::<Vec<_>>().join(".")
+    }
+
+    fn can_omit(&self) -> bool {
+        self.is_empty()
+    }
+}
+
+/// Converter for comma separated lists of ids, like the songs and sound effects a level uses
+pub struct IdListConverter;
+
+impl RobtopFrom<Vec<u64>, &str> for IdListConverter {
+    fn robtop_from(s: &str) -> Result<Vec<u64>, String> {
+        s.split(',')
+            .filter(|id| !id.is_empty())
+            .map(|id| id.parse().map_err(|err: ParseIntError| format!("Invalid id '{}': {}", id, err)))
+            .collect()
+    }
+}
+
+impl RobtopInto<IdListConverter, String> for Vec<u64> {
+    fn robtop_into(self) -> String {
+        self.iter().map(ToString::to_string).collect::<Vec<_>>().join(",")
+    }
+
+    fn can_omit(&self) -> bool {
+        self.is_empty()
+    }
+}
+
 pub struct UrlConverter;
 
 impl RobtopFrom<String, &str> for UrlConverter {
@@ -599,6 +912,28 @@ impl RobtopInto<UrlConverter, String> for String {
     }
 }
 
+/// Converter for values that are form encoded, meaning spaces are encoded as `+` instead of `%20`
+///
+/// Only use this for fields known to be form encoded, since it turns every literal `+` into a
+/// space. Fields using `%20` should use [`UrlConverter`] instead.
+pub struct FormUrlConverter;
+
+impl RobtopFrom<String, &str> for FormUrlConverter {
+    fn robtop_from(s: &str) -> Result<String, String> {
+        // Replace before decoding, otherwise encoded plus signs (`%2B`) would become spaces too
+        UrlConverter::robtop_from(&s.replace('+', " "))
+    }
+}
+
+impl RobtopInto<FormUrlConverter, String> for String {
+    fn robtop_into(self) -> String {
+        percent_encode(self.as_bytes(), SIMPLE_ENCODE_SET)
+            .to_string()
+            .replace('+', "%2B")
+            .replace(' ', "+")
+    }
+}
+
 pub struct YoutubeConverter;
 
 pub struct TwitterConverter;
@@ -651,4 +986,36 @@ impl RobtopFromInfallible<Option<String>, &str> for TwitchConverter {
             Some(format!("https://www.twitch.tv/{}", value))
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::{RobtopFrom, RobtopInto};
+    use gdcf_model::level::Password;
+
+    #[test]
+    fn well_formed_passwords() {
+        assert_eq!(Password::robtop_from("0"), Ok(Password::NoCopy));
+        assert_eq!(Password::robtop_from("Aw=="), Ok(Password::FreeCopy));
+        assert_eq!(Password::robtop_from("AgcBBQAHAA=="), Ok(Password::PasswordCopy("123456".to_string())));
+    }
+
+    #[test]
+    fn password_not_decrypting_to_a_number_is_invalid() {
+        // "0abc" and "0" followed by a stray null byte
+        for &encrypted in &["AldRVQ==", "AjY="] {
+            assert_eq!(Password::robtop_from(encrypted), Ok(Password::Invalid(encrypted.to_string())));
+        }
+    }
+
+    #[test]
+    fn invalid_password_keeps_its_raw_value() {
+        let password: String = Password::Invalid("AldRVQ==".to_string()).robtop_into();
+
+        assert_eq!(password, "AldRVQ==");
+    }
+
+    #[test]
+    fn password_that_is_not_base64_is_rejected() {
+        assert!(Password::robtop_from("not base64!").is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! Module containing helpers for inspecting captured traffic of the Geometry Dash client
+//!
+//! These are debugging aids for figuring out what the client sends to endpoints GDCF doesn't
+//! support yet. Request bodies are decoded using the same primitives the parsers use.
+
+use crate::{
+    convert::{Base64BytesConverter, RobtopFrom},
+    util,
+};
+use gdcf_model::level::data::LevelData;
+use percent_encoding::percent_decode;
+use std::borrow::Cow;
+
+/// The key the client XORs account passwords with before base64 encoding them into a `gjp`
+const GJP_KEY: &str = "37526";
+
+/// A single field of a decoded request body
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodedField<'a> {
+    /// The field isn't known to be encoded. Contains the value with percent-encoding undone
+    Plain(Cow<'a, str>),
+
+    /// The field is known to be encoded and was decoded successfully. Contains the decoded value
+    ///
+    /// For `gjp`, this is the account password. For level data, this is the decompressed level
+    /// string.
+    Decoded(String),
+
+    /// The field is known to be encoded in a way that cannot be reversed (like the hashed
+    /// `gjp2`). Contains the raw value
+    Irreversible(Cow<'a, str>),
+
+    /// The field is known to be encoded, but decoding it failed. Contains the raw value and the
+    /// reason decoding failed
+    Malformed(Cow<'a, str>, String),
+}
+
+/// Decodes the given captured request body
+///
+/// The body is expected to be `application/x-www-form-urlencoded`, which is what the client sends.
+/// Fields are returned in the order they appear in the body. The following fields are decoded:
+/// + `gjp`: Decrypted back to the account password
+/// + `gjp2`: Salted SHA-1 hash of the password, which cannot be reversed
+/// + `levelString`: Base64 decoded and decompressed
+/// + `levelDesc`, `comment`: Base64 decoded
+pub fn decode_request_body(body: &str) -> Vec<(Cow<'_, str>, DecodedField<'_>)> {
+    body.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let mut split = pair.splitn(2, '=');
+            let name = form_decode(split.next().unwrap_or_default());
+            let value = form_decode(split.next().unwrap_or_default());
+
+            let value = decode_field(&name, value);
+
+            (name, value)
+        })
+        .collect()
+}
+
+/// Undoes the form encoding of a name or value, which encodes spaces as `+` and everything else
+/// that isn't alphanumeric using percent-encoding
+fn form_decode(encoded: &str) -> Cow<'_, str> {
+    if encoded.contains('+') {
+        Cow::Owned(percent_decode(encoded.replace('+', " ").as_bytes()).decode_utf8_lossy().into_owned())
+    } else {
+        percent_decode(encoded.as_bytes()).decode_utf8_lossy()
+    }
+}
+
+/// Decodes the given value of a field with the given name
+///
+/// See [`decode_request_body`] for the fields that are decoded.
+pub fn decode_field<'a>(name: &str, value: Cow<'a, str>) -> DecodedField<'a> {
+    let decoded = match name {
+        "gjp" => decode_gjp(&value),
+        "gjp2" => return DecodedField::Irreversible(value),
+        "levelString" => decode_level_string(&value),
+        "levelDesc" | "comment" => util::b64_decode_string(&value).map_err(|err| err.to_string()),
+        _ => return DecodedField::Plain(value),
+    };
+
+    match decoded {
+        Ok(decoded) => DecodedField::Decoded(decoded),
+        Err(err) => DecodedField::Malformed(value, err),
+    }
+}
+
+/// Decrypts the given `gjp` back to the account password it was generated from
+pub fn decode_gjp(gjp: &str) -> Result<String, String> {
+    util::b64_decode_string(gjp)
+        .map(|decoded| util::xor_decrypt(&decoded, GJP_KEY))
+        .map_err(|err| err.to_string())
+}
+
+fn decode_level_string(level_string: &str) -> Result<String, String> {
+    let data: LevelData = Base64BytesConverter::robtop_from(level_string)?;
+
+    data.decompress().map_err(|err| err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_request_body, DecodedField};
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    fn decode_one(body: &str) -> DecodedField<'_> {
+        let mut fields = decode_request_body(body);
+
+        assert_eq!(fields.len(), 1);
+
+        fields.remove(0).1
+    }
+
+    #[test]
+    fn gjp_is_decrypted() {
+        let fields = decode_request_body("gameVersion=21&gjp=W0JbRlNBBQ%3D%3D&gjp2=0123456789abcdef");
+
+        assert_eq!(fields[0], ("gameVersion".into(), DecodedField::Plain("21".into())));
+        assert_eq!(fields[1], ("gjp".into(), DecodedField::Decoded("hunter2".to_string())));
+        assert_eq!(fields[2], ("gjp2".into(), DecodedField::Irreversible("0123456789abcdef".into())));
+    }
+
+    #[test]
+    fn level_string_is_decompressed() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"kA4,0;1,1,2,15,3,15;").unwrap();
+
+        let encoded = base64::encode_config(&encoder.finish().unwrap(), base64::URL_SAFE);
+
+        assert_eq!(
+            decode_one(&format!("levelString={}", encoded)),
+            DecodedField::Decoded("kA4,0;1,1,2,15,3,15;".to_string())
+        );
+    }
+
+    #[test]
+    fn descriptions_and_comments_are_base64_decoded() {
+        for name in &["levelDesc", "comment"] {
+            assert_eq!(
+                decode_one(&format!("{}=Q2hlY2sgb3V0IG15IGxldmVsIQ%3D%3D", name)),
+                DecodedField::Decoded("Check out my level!".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn malformed_values_are_reported() {
+        for body in &["gjp=%40%40%40", "levelString=not+base64", "levelDesc=%25%25"] {
+            match decode_one(body) {
+                DecodedField::Malformed(..) => (),
+                field => panic!("{} decoded to {:?}", body, field),
+            }
+        }
+
+        // Valid base64, but not compressed level data
+        match decode_one("levelString=Q2hlY2sgb3V0IG15IGxldmVsIQ%3D%3D") {
+            DecodedField::Malformed(raw, _) => assert_eq!(raw, "Q2hlY2sgb3V0IG15IGxldmVsIQ=="),
+            field => panic!("level string decoded to {:?}", field),
+        }
+    }
+
+    #[test]
+    fn plus_decodes_to_space() {
+        assert_eq!(decode_one("levelName=My+first+level"), DecodedField::Plain("My first level".into()));
+        assert_eq!(decode_one("levelName=1%2B1"), DecodedField::Plain("1+1".into()));
+    }
+}
@@ -2,6 +2,21 @@
 pub enum ValueError<'a> {
     NoValue(&'a str),
     Parse(&'a str, &'a str, String),
+
+    /// The input didn't alternate between indices and values. The contained value is the position
+    /// of the first token where the alternation broke.
+    Alternation(usize),
+
+    /// The level data contained more objects than the given limit
+    TooManyObjects(usize),
+
+    /// The contained index appeared more than once in the input, which is rejected under
+    /// [`DuplicateIndices::Reject`](crate::DuplicateIndices::Reject)
+    DuplicateIndex(&'a str),
+
+    /// The input contained no data at all, meaning it was either empty or consisted solely of
+    /// delimiters
+    EmptyResponse,
 }
 
 impl std::error::Error for ValueError<'_> {}
@@ -11,6 +26,10 @@ impl std::fmt::Display for ValueError<'_> {
         match self {
             ValueError::NoValue(idx) => write!(f, "No value provided at index {}", idx),
             ValueError::Parse(idx, value, cause) => write!(f, "The value '{}' at index {} could not be parsed: {}", value, idx, cause),
+            ValueError::Alternation(position) => write!(f, "Index/value alternation broken at token {}", position),
+            ValueError::TooManyObjects(limit) => write!(f, "The level contains more than {} objects", limit),
+            ValueError::DuplicateIndex(idx) => write!(f, "Index {} appears more than once", idx),
+            ValueError::EmptyResponse => write!(f, "The input contains no data"),
         }
     }
 }
@@ -1,32 +1,108 @@
 use crate::{
-    convert::{Base64BytesConverter, Base64Converter, RobtopFrom, RobtopInto},
+    convert::{Base64BytesConverter, Base64Converter, IdListConverter, OptionalVersionConverter, RobtopFrom, RobtopInto, SecondsConverter},
     error::ValueError,
     Parse,
 };
 use gdcf_model::{
-    level::{DemonRating, Level, LevelRating, PartialLevel},
+    level::{DemonRating, Level, LevelRating, LocalLevel, PartialLevel},
     song::{MainSong, MAIN_SONGS, UNKNOWN},
+    GameVersion,
 };
+use std::convert::TryFrom;
 
 pub mod data;
 pub mod object;
 
-pub fn process_difficulty(rating: &str, is_auto: bool, is_demon: bool) -> LevelRating {
+/// The level format a parser expects its input to be in
+///
+/// Levels uploaded before update 2.0 lack many of the fields newer levels have (coins, the demon
+/// rating at index `43`, the object count, ...) and encode some of the remaining ones differently.
+/// The parsers in this module are written for the modern format, so checking a parsed level against
+/// the profile its source is expected to produce detects levels that were likely misread.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParseProfile {
+    /// The format of levels uploaded in 1.x versions of the game
+    Legacy,
+
+    /// The format of levels uploaded in 2.0 or later
+    Modern,
+}
+
+impl ParseProfile {
+    /// Gets the profile a level uploaded in the given game version is in
+    ///
+    /// Levels uploaded before the game started tracking versions ([`GameVersion::Unknown`]) are
+    /// always legacy levels.
+    pub fn for_version(version: GameVersion) -> ParseProfile {
+        match version {
+            GameVersion::Version { major, .. } if major >= 2 => ParseProfile::Modern,
+            _ => ParseProfile::Legacy,
+        }
+    }
+
+    /// Checks whether a level uploaded in the given game version is in this profile's format
+    pub fn matches(self, version: GameVersion) -> bool {
+        ParseProfile::for_version(version) == self
+    }
+
+    /// Checks whether the given level is in this profile's format, logging a warning if not
+    pub fn check<Song, User>(self, level: &PartialLevel<Song, User>) -> bool {
+        let matches = self.matches(level.gd_version);
+
+        if !matches {
+            warn!(
+                "Level {} was uploaded in version {}, which doesn't match the {:?} parse profile. Some of its fields might have been \
+                 misread",
+                level.level_id, level.gd_version, self
+            );
+        }
+
+        matches
+    }
+}
+
+/// Determines a level's rating from the rating-related indices of a level response
+///
+/// The demon sub-rating is reported in two places: Legacy responses encode it in the rating at index
+/// `9` (`10` to `50`), while newer responses provide it at index `43`. Depending on the endpoint,
+/// either of them may be missing or zeroed out, so the legacy index is used if it contains a known
+/// demon rating, and index `43` otherwise.
+pub fn process_difficulty(rating: &str, is_auto: bool, is_demon: bool, demon_rating: &impl AsRef<str>) -> LevelRating {
     if is_demon {
-        LevelRating::Demon(DemonRating::robtop_from(rating).unwrap()) // FIXME: make custom
-                                                                      // functions return result
+        let legacy = match DemonRating::robtop_from(rating) {
+            Ok(DemonRating::Unknown(_)) | Err(_) => None,
+            Ok(demon_rating) => Some(demon_rating),
+        };
+
+        LevelRating::Demon(
+            legacy
+                .or_else(|| demon_rating_from_index_43(demon_rating.as_ref()))
+                .unwrap_or(DemonRating::Unknown(-1)),
+        )
     } else if is_auto {
         LevelRating::Auto
     } else {
-        LevelRating::robtop_from(rating).unwrap()
+        LevelRating::robtop_from(rating).unwrap() // FIXME: make custom functions return result
     }
 }
 
+/// Converts the value of index `43` of a level response into a [`DemonRating`]
+///
+/// See the [`TryFrom<i32>`](TryFrom) implementation of [`DemonRating`] for the possible values
+fn demon_rating_from_index_43(value: &str) -> Option<DemonRating> {
+    value.parse().ok().and_then(|value: i32| DemonRating::try_from(value).ok())
+}
+
+/// Resolves the main song of a level, which is [`None`] if the level uses a custom song
+///
+/// Responses often contain both a main song id (index `12`) and a custom song id (index `35`), in
+/// which case the custom song takes precedence and the main song id is meaningless. There is no
+/// explicit "uses custom song" flag in responses (the `kA13` key in level headers is the song
+/// offset), so a non-zero custom song id is what decides this.
 pub fn process_song(main_song: usize, custom_song: &Option<u64>) -> Option<&'static MainSong> {
-    if custom_song.is_none() {
-        Some(MAIN_SONGS.get(main_song).unwrap_or(&UNKNOWN))
-    } else {
-        None
+    match custom_song {
+        Some(_) => None,
+        None => Some(MAIN_SONGS.get(main_song).unwrap_or(&UNKNOWN)),
     }
 }
 
@@ -37,26 +113,29 @@ parser! {
         description(index = 3, parse_infallible = Base64Converter, default),
         version(index = 5),
         creator(index = 6),
-        difficulty(custom = process_difficulty[rating, is_auto, is_demon]),
+        difficulty(custom = process_difficulty[rating, is_auto, is_demon, &index_43]),
         downloads(index = 10),
         main_song(custom = process_song[main_song_id, &custom_song]),
         gd_version(index = 13),
+        binary_version(index = 50, parse = OptionalVersionConverter, default),
         likes(index = 14),
         length(index = 15),
         stars(index = 18),
         featured(index = 19),
         copy_of(index = 30),
-        index_31(index = 31),
+        two_player(index = 31, default),
         custom_song(index = 35),
         coin_amount(index = 37),
         coins_verified(index = 38),
         stars_requested(index = 39),
-        index_40(index = 40, optional),
+        low_detail_available(index = 40, default),
         is_epic(index = 42),
         index_43(index = 43),
         object_amount(index = 45),
-        index_46(index = 46, default),
-        index_47(index = 47, default),
+        editor_time(index = 46, parse = SecondsConverter, default),
+        editor_time_copies(index = 47, parse = SecondsConverter, default),
+        song_ids(index = 52, parse = IdListConverter, default),
+        sfx_ids(index = 53, parse = IdListConverter, default),
     },
     main_song_id(index = 12, extract = extract_main_song_id[main_song], default),
     rating(index = 9, extract = extract_rating[difficulty]),
@@ -84,8 +163,14 @@ fn extract_is_auto(rating: LevelRating) -> String {
     RobtopInto::<bool, String>::robtop_into(rating == LevelRating::Auto)
 }
 
+// Index 8 is actually the denominator of the difficulty rating, which is 0 for N/A levels and 10 for
+// all others
 fn extract_is_na(rating: LevelRating) -> String {
-    RobtopInto::<bool, String>::robtop_into(rating == LevelRating::NotAvailable)
+    match rating {
+        LevelRating::NotAvailable => "0",
+        _ => "10",
+    }
+    .to_string()
 }
 
 parser! {
@@ -97,4 +182,234 @@ parser! {
         time_since_update(index = 29),
         index_36(index = 36, default),
     }
-}
\ No newline at end of file
+}
+
+// The local save file contains plenty of editor-only keys we don't care about. Since the parser
+// passes unknown keys to the closure given to `Parse::parse`, wrap this in `UnparseSafe` to capture
+// them.
+parser! {
+    LocalLevel => {
+        level_id(index = k1, default),
+        name(index = k2),
+        description(index = k3, parse_infallible = Base64Converter, default),
+        level_data(index = k4, parse = Base64BytesConverter),
+        creator_name(index = k5, default),
+        version(index = k16, default),
+        attempts(index = k18, default),
+        folder(index = k84, default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{process_difficulty, ParseProfile};
+    use crate::{error::ValueError, DuplicateIndices, Parse, UnparseSafe};
+    use gdcf_model::{
+        level::{DemonRating, Level, LevelRating, LocalLevel, PartialLevel, Password},
+        GameVersion,
+    };
+
+    const PARTIAL_LEVEL: &str = "1:10565740:2:Bloodbath:3:V2hhdHMgdGhpcyBleHRyZW1lIGRlbW9uPw==:5:3:6:503085:8:10:9:50:10:\
+                                 31279016:12:0:13:21:14:1549098:15:3:17:1:18:10:19:10330:25::30:7679228:31:0:35:467339:37:0:38:0:\
+                                 39:10:42:0:43:0:45:24746:52:10003479,10002843:53:7,29";
+
+    #[test]
+    fn partial_level_round_trips_through_response_string() {
+        let level = PartialLevel::<Option<u64>, u64>::parse_str(PARTIAL_LEVEL, ':').unwrap();
+
+        assert_eq!(level.song_ids, vec![10_003_479, 10_002_843]);
+        assert_eq!(level.sfx_ids, vec![7, 29]);
+
+        let response = level.to_response_string(':');
+        let reparsed = PartialLevel::<Option<u64>, u64>::parse_str(&response, ':').unwrap();
+
+        assert_eq!(format!("{:?}", level), format!("{:?}", reparsed));
+    }
+
+    #[test]
+    fn level_with_invalid_password() {
+        let response = format!(
+            "{}:4:H4sIAAAAAAAAC0u0MjS1MjQ2MjEyNjI2MjA2ADEAHyDqVxQAAAA=:27:AldRVQ==:28:5 years:29:3 years",
+            PARTIAL_LEVEL
+        );
+
+        let level = Level::<Option<u64>, u64>::parse_str(&response, ':').unwrap();
+
+        assert_eq!(level.password, Password::Invalid("AldRVQ==".to_string()));
+
+        let reparsed = Level::<Option<u64>, u64>::parse_str(&level.to_response_string(':'), ':').unwrap();
+
+        assert_eq!(reparsed.password, level.password);
+    }
+
+    #[test]
+    fn level_round_trips_through_response_string() {
+        let response = format!(
+            "{}:4:H4sIAAAAAAAAC0u0MjS1MjQ2MjEyNjI2MjA2ADEAHyDqVxQAAAA=:27:0:28:5 years:29:3 years",
+            PARTIAL_LEVEL
+        );
+
+        let level = Level::<Option<u64>, u64>::parse_str(&response, ':').unwrap();
+        let response = level.to_response_string(':');
+        let reparsed = Level::<Option<u64>, u64>::parse_str(&response, ':').unwrap();
+
+        assert_eq!(format!("{:?}", level), format!("{:?}", reparsed));
+    }
+
+    #[test]
+    fn modern_level_matches_modern_profile() {
+        let level = PartialLevel::<Option<u64>, u64>::parse_str(PARTIAL_LEVEL, ':').unwrap();
+
+        assert_eq!(level.gd_version, GameVersion::Version { major: 2, minor: 1 });
+        assert_eq!(ParseProfile::for_version(level.gd_version), ParseProfile::Modern);
+        assert!(ParseProfile::Modern.check(&level));
+        assert!(!ParseProfile::Legacy.check(&level));
+    }
+
+    #[test]
+    fn legacy_level_doesnt_match_modern_profile() {
+        let response = PARTIAL_LEVEL.replace(":13:21:", ":13:18:");
+        let level = PartialLevel::<Option<u64>, u64>::parse_str(&response, ':').unwrap();
+
+        assert_eq!(level.gd_version, GameVersion::Version { major: 1, minor: 8 });
+        assert_eq!(ParseProfile::for_version(level.gd_version), ParseProfile::Legacy);
+        assert!(!ParseProfile::Modern.check(&level));
+        assert!(ParseProfile::Legacy.check(&level));
+
+        // Levels from before versions were tracked are legacy levels as well
+        let response = PARTIAL_LEVEL.replace(":13:21:", ":13:10:");
+        let level = PartialLevel::<Option<u64>, u64>::parse_str(&response, ':').unwrap();
+
+        assert!(!ParseProfile::Modern.check(&level));
+    }
+
+    #[test]
+    fn duplicate_index_with_malformed_earlier_value_fails() {
+        let input = format!("38:garbage:{}", PARTIAL_LEVEL);
+
+        match PartialLevel::<Option<u64>, u64>::parse_str_with(&input, ':', DuplicateIndices::LastWins) {
+            Err(ValueError::Parse("38", "garbage", _)) => (),
+            result => panic!("unexpected parse result {:?}", result),
+        }
+    }
+
+    #[test]
+    fn collecting_falls_back_to_defaults_for_broken_values() {
+        let input = format!("{}:40:garbage", PARTIAL_LEVEL.replace(":31:0:", ":31:garbage:"));
+        let (level, errors) = PartialLevel::<Option<u64>, u64>::parse_str_collecting(&input, ':');
+
+        let level = level.unwrap();
+
+        assert!(!level.two_player);
+        assert!(!level.low_detail_available);
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().all(|error| matches!(error, ValueError::Parse("31", "garbage", _) | ValueError::Parse("40", "garbage", _))));
+    }
+
+    #[test]
+    fn collecting_reports_every_broken_value() {
+        let input = PARTIAL_LEVEL.replace(":31:0:", ":31:garbage:").replace(":38:0:", ":38:garbage:");
+        let (level, errors) = PartialLevel::<Option<u64>, u64>::parse_str_collecting(&input, ':');
+
+        // `coins_verified` has no default value
+        assert!(level.is_none());
+        assert_eq!(errors.len(), 2);
+        assert!(errors.iter().all(|error| matches!(error, ValueError::Parse("31", "garbage", _) | ValueError::Parse("38", "garbage", _))));
+    }
+
+    #[test]
+    fn binary_version_is_parsed_separately_from_gd_version() {
+        let input = format!("{}:50:42", PARTIAL_LEVEL.replace(":13:21:", ":13:22:"));
+        let level = PartialLevel::<Option<u64>, u64>::parse_str(&input, ':').unwrap();
+
+        assert_eq!(level.gd_version, GameVersion::Version { major: 2, minor: 2 });
+        assert_eq!(level.binary_version, Some(GameVersion::Version { major: 4, minor: 2 }));
+
+        let reparsed = PartialLevel::<Option<u64>, u64>::parse_str(&level.to_response_string(':'), ':').unwrap();
+
+        assert_eq!(reparsed.binary_version, level.binary_version);
+    }
+
+    #[test]
+    fn binary_version_defaults_to_none() {
+        let level = PartialLevel::<Option<u64>, u64>::parse_str(PARTIAL_LEVEL, ':').unwrap();
+
+        assert_eq!(level.binary_version, None);
+    }
+
+    #[test]
+    fn demon_rating_from_legacy_index() {
+        assert_eq!(process_difficulty("30", false, true, &""), LevelRating::Demon(DemonRating::Hard));
+        assert_eq!(process_difficulty("50", false, true, &""), LevelRating::Demon(DemonRating::Extreme));
+    }
+
+    #[test]
+    fn demon_rating_from_index_43() {
+        assert_eq!(process_difficulty("0", false, true, &"3"), LevelRating::Demon(DemonRating::Easy));
+        assert_eq!(process_difficulty("0", false, true, &"0"), LevelRating::Demon(DemonRating::Hard));
+        assert_eq!(process_difficulty("", false, true, &"6"), LevelRating::Demon(DemonRating::Extreme));
+    }
+
+    #[test]
+    fn demon_rating_prefers_legacy_index() {
+        assert_eq!(process_difficulty("20", false, true, &"5"), LevelRating::Demon(DemonRating::Medium));
+        assert_eq!(process_difficulty("0", false, true, &""), LevelRating::Demon(DemonRating::Unknown(-1)));
+    }
+
+    #[test]
+    fn demon_rating_survives_zeroed_legacy_index() {
+        let level = PartialLevel::<Option<u64>, u64>::parse_str(PARTIAL_LEVEL, ':').unwrap();
+
+        assert_eq!(level.difficulty, LevelRating::Demon(DemonRating::Extreme));
+
+        let response = PARTIAL_LEVEL.replace(":9:50:", ":9:0:").replace(":43:0:", ":43:5:");
+        let level = PartialLevel::<Option<u64>, u64>::parse_str(&response, ':').unwrap();
+
+        assert_eq!(level.difficulty, LevelRating::Demon(DemonRating::Insane));
+    }
+
+    #[test]
+    fn custom_song_takes_precedence_over_main_song() {
+        let response = PARTIAL_LEVEL.replace(":12:0:", ":12:5:");
+        let level = PartialLevel::<Option<u64>, u64>::parse_str(&response, ':').unwrap();
+
+        assert_eq!(level.custom_song, Some(467_339));
+        assert_eq!(level.main_song, None);
+    }
+
+    #[test]
+    fn main_song_used_without_custom_song() {
+        let response = PARTIAL_LEVEL.replace(":12:0:", ":12:5:").replace(":35:467339:", ":35:0:");
+        let level = PartialLevel::<Option<u64>, u64>::parse_str(&response, ':').unwrap();
+
+        assert_eq!(level.custom_song, None);
+        assert_eq!(level.main_song.map(|song| song.main_song_id), Some(5));
+    }
+
+    const LOCAL_LEVEL: &str = "kCEK:4:k1:0:k2:My Level:k3:QSBsZXZlbA==:k4:H4sIAAAAAAAAC0u0MjS1MjQ2MjEyNjI2MjA2ADEAHyDqVxQAAAA=:k5:Player:\
+                               k13:1:k16:2:k21:2:k18:57:k84:3:kI1:120.5:kI6:0,0,1,0";
+
+    #[test]
+    fn local_level_ignores_editor_only_keys() {
+        let level = LocalLevel::parse_str(LOCAL_LEVEL, ':').unwrap();
+
+        assert_eq!(level.level_id, None);
+        assert_eq!(level.name, "My Level");
+        assert_eq!(level.description, Some("A level".to_string()));
+        assert!(!level.level_data.is_empty());
+        assert_eq!(level.creator_name, "Player");
+        assert_eq!(level.version, 2);
+        assert_eq!(level.attempts, 57);
+        assert_eq!(level.folder, Some(3));
+    }
+
+    #[test]
+    fn local_level_captures_editor_only_keys() {
+        let level = UnparseSafe::<LocalLevel>::parse_str(LOCAL_LEVEL, ':').unwrap();
+
+        assert_eq!(level.parsed.name, "My Level");
+        assert_eq!(level.unparsed.get("kCEK"), Some(&"4"));
+        assert_eq!(level.unparsed.get("kI6"), Some(&"0,0,1,0"));
+        assert_eq!(level.unparsed.len(), 5);
+    }
+}
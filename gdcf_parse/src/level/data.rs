@@ -1,11 +1,26 @@
-use crate::{error::ValueError, Parse};
-use gdcf_model::level::data::{
-    portal::{self, PortalData, PortalType},
-    LevelInformationSource, LevelMetadata, LevelObject, ObjectData, Stats,
+use crate::{
+    convert::{ColorChannelsConverter, CoordinateConverter, FractionalSecondsConverter, GroupsConverter, GuidelinesConverter, HsvConverter},
+    error::ValueError,
+    Parse,
+};
+use gdcf_model::level::{
+    data::{
+        color::ColorChannel,
+        ids,
+        portal::{self, PortalData, PortalType},
+        CoinVerification, LevelInformationSource, LevelMetadata, LevelObject, ObjectData, ObjectDataPalette, Stats,
+        DEFAULT_MAX_DECOMPRESSED_SIZE,
+    },
+    Level,
 };
 #[cfg(feature = "parallel")]
 use rayon::{iter::ParallelIterator, str::ParallelString};
-use std::time::Duration;
+use std::{
+    collections::HashSet,
+    io::{self, BufRead, ErrorKind},
+    rc::Rc,
+    time::Duration,
+};
 
 pub struct IterSource<I>(LevelMetadata, I)
 where
@@ -16,6 +31,55 @@ pub struct ParIterSource<I>(LevelMetadata, I)
 where
     I: ParallelIterator<Item = LevelObject>;
 
+/// Objects further than this to the right of the previous object are considered to not be part of
+/// the gameplay if they are at the very end of the level. This is about 33 blocks.
+const FINISH_GAP_THRESHOLD: f32 = 1000.0;
+
+/// Computes the end of a level's gameplay from the (unordered) x positions of all its objects
+///
+/// See [`LevelInformationSource::finish_x`]. `NaN` positions are ignored.
+fn finish_x(mut xs: Vec<f32>, end_trigger: Option<f32>) -> f32 {
+    if let Some(x) = end_trigger {
+        return x
+    }
+
+    xs.retain(|x| !x.is_nan());
+    xs.sort_unstable_by(f32::total_cmp);
+
+    // We never discard more than 5% of the objects as "decoration", otherwise we'd cut off actual
+    // gameplay in sparse levels
+    let max_discarded = xs.len() / 20;
+    let mut end = xs.len();
+
+    for idx in (xs.len().saturating_sub(max_discarded).max(1)..xs.len()).rev() {
+        if xs[idx] - xs[idx - 1] > FINISH_GAP_THRESHOLD {
+            end = idx;
+        }
+    }
+
+    match end {
+        0 => 0.0,
+        _ => xs[end - 1],
+    }
+}
+
+/// Like [`parse_lazy`], but fails with [`ValueError::TooManyObjects`] if the level contains more
+/// than `max_objects` objects
+///
+/// The objects are counted before any of them is parsed, and counting stops as soon as the limit
+/// is exceeded, so this is cheap even for huge levels.
+pub fn parse_lazy_limited<'a>(
+    level_string: &'a str,
+    max_objects: usize,
+) -> Result<IterSource<impl Iterator<Item = LevelObject> + 'a>, ValueError<'a>> {
+    // Every object is terminated by a semicolon, as is the metadata section
+    if level_string.bytes().filter(|&byte| byte == b';').nth(max_objects + 1).is_some() {
+        return Err(ValueError::TooManyObjects(max_objects))
+    }
+
+    parse_lazy(level_string)
+}
+
 pub fn parse_lazy<'a>(level_string: &'a str) -> Result<IterSource<impl Iterator<Item = LevelObject> + 'a>, ValueError<'a>> {
     let mut iter = level_string.split(';');
 
@@ -33,6 +97,96 @@ pub fn parse_lazy<'a>(level_string: &'a str) -> Result<IterSource<impl Iterator<
     Ok(IterSource(metadata, iter))
 }
 
+/// Like [`parse_lazy`], but decompresses the data of the given level while iterating over its
+/// objects
+///
+/// Neither the decompressed level string nor the objects are ever held in memory in their entirety,
+/// so this allows computing [`Stats`] of huge levels with constant memory usage. Only the metadata
+/// section is decompressed eagerly, failing with [`ErrorKind::InvalidData`] if it cannot be parsed.
+///
+/// Objects that fail to parse are skipped, just like with [`parse_lazy`]. If the level data turns
+/// out to be corrupted while iterating, or decompresses to more than
+/// [`DEFAULT_MAX_DECOMPRESSED_SIZE`] bytes, the iterator ends early.
+pub fn parse_objects_streaming<Song, User>(
+    level: &Level<Song, User>,
+) -> io::Result<IterSource<impl Iterator<Item = LevelObject> + '_>> {
+    parse_objects_streaming_limited(level, DEFAULT_MAX_DECOMPRESSED_SIZE)
+}
+
+/// Like [`parse_objects_streaming`], but stops decompressing after `max_size` bytes
+///
+/// If the limit is already exceeded while reading the metadata section, this fails with
+/// [`ErrorKind::InvalidData`]. Otherwise, the iterator ends early once it is reached.
+pub fn parse_objects_streaming_limited<Song, User>(
+    level: &Level<Song, User>,
+    max_size: usize,
+) -> io::Result<IterSource<impl Iterator<Item = LevelObject> + '_>> {
+    let mut chunks = level.level_data.decompressing_reader_limited(max_size).split(b';');
+
+    let metadata = match chunks.next() {
+        None => return Err(io::Error::new(ErrorKind::InvalidData, ValueError::NoValue("metadata").to_string())),
+        Some(chunk) => {
+            let chunk = String::from_utf8(chunk?).map_err(|err| io::Error::new(ErrorKind::InvalidData, err))?;
+
+            LevelMetadata::parse_str(&chunk, ',').map_err(|err| io::Error::new(ErrorKind::InvalidData, err.to_string()))?
+        },
+    };
+
+    let iter = chunks
+        .scan((), |_, chunk| {
+            chunk
+                .map_err(|err| error!("Failed to decompress level data, stopping iteration - {}", err))
+                .ok()
+        })
+        .filter(|chunk| !chunk.is_empty())
+        .filter_map(|chunk| {
+            let obj = String::from_utf8_lossy(&chunk);
+
+            LevelObject::parse_str(&obj, ',')
+                .map_err(|err| error!("Ignoring error during parsing of object {} - {}", obj, err))
+                .ok()
+        });
+
+    Ok(IterSource(metadata, iter))
+}
+
+/// Extension trait for iterating over the objects of a [`Level`] while decompressing its level data
+pub trait ParseObjectsStreaming {
+    /// Decompresses the level data and lazily parses its objects, see [`parse_objects_streaming`]
+    fn parse_objects_streaming(&self) -> io::Result<IterSource<impl Iterator<Item = LevelObject> + '_>>;
+}
+
+impl<Song, User> ParseObjectsStreaming for Level<Song, User> {
+    fn parse_objects_streaming(&self) -> io::Result<IterSource<impl Iterator<Item = LevelObject> + '_>> {
+        parse_objects_streaming(self)
+    }
+}
+
+/// Extension trait for verifying the user coins of a [`Level`] against its level data
+pub trait VerifyCoins {
+    /// Compares the user coins this level advertises with the coin objects found in its data
+    ///
+    /// Returns [`None`] if the level data cannot be decompressed or its metadata cannot be parsed.
+    /// Objects that fail to parse are skipped, just like with [`parse_lazy`], so a malformed coin
+    /// object shows up as a missing coin.
+    fn verify_coins(&self) -> Option<CoinVerification>;
+}
+
+impl<Song, User> VerifyCoins for Level<Song, User> {
+    fn verify_coins(&self) -> Option<CoinVerification> {
+        let level_string = self
+            .decompress_data()
+            .map_err(|err| warn!("Failed to decompress data of level {}: {}", self.base.level_id, err))
+            .ok()?;
+
+        let source = parse_lazy(&level_string)
+            .map_err(|err| warn!("Failed to parse data of level {}: {}", self.base.level_id, err))
+            .ok()?;
+
+        Some(CoinVerification::new(&self.base, source))
+    }
+}
+
 #[cfg(feature = "parallel")]
 pub fn parse_lazy_parallel<'a>(
     level_string: &'a str,
@@ -53,6 +207,22 @@ pub fn parse_lazy_parallel<'a>(
     Ok(ParIterSource(metadata, iter))
 }
 
+impl<I> IterSource<I>
+where
+    I: Iterator<Item = LevelObject>,
+{
+    /// Collects all objects, interning their [`ObjectData`] into a palette
+    ///
+    /// Uses far less memory than [`LevelInformationSource::collect`] for levels consisting of many
+    /// objects with identical data.
+    pub fn collect_interned(self) -> (Vec<LevelObject<Rc<ObjectData>>>, ObjectDataPalette) {
+        let mut palette = ObjectDataPalette::new();
+        let objects = self.1.map(|object| object.intern(&mut palette)).collect();
+
+        (objects, palette)
+    }
+}
+
 impl<I> LevelInformationSource for IterSource<I>
 where
     I: Iterator<Item = LevelObject>,
@@ -67,10 +237,13 @@ where
         let mut object_count = 0;
         let mut portals = Vec::new();
         let mut furthest_x = 0.0;
+        let mut groups = HashSet::new();
 
         for object in iter {
             object_count += 1;
 
+            groups.extend(object.groups.iter().cloned());
+
             if let ObjectData::Portal(PortalData {
                 checked: true,
                 portal_type: PortalType::Speed(speed),
@@ -82,11 +255,42 @@ where
             furthest_x = f32::max(furthest_x, object.x);
         }
 
-        portals.sort_by(|(x1, _), (x2, _)| x1.partial_cmp(x2).unwrap());
+        portals.sort_by(|(x1, _), (x2, _)| x1.total_cmp(x2));
 
         let duration = Duration::from_secs(portal::get_seconds_from_x_pos(furthest_x, metadata.starting_speed, &portals) as u64);
 
-        Stats { object_count, duration }
+        Stats {
+            object_count,
+            duration,
+            distinct_groups: groups.len() as u64,
+        }
+    }
+
+    fn finish_x(self) -> f32 {
+        let mut end_trigger = None;
+
+        let xs = self
+            .1
+            .inspect(|object| {
+                if object.id == ids::END_TRIGGER {
+                    end_trigger = Some(object.x)
+                }
+            })
+            .map(|object| object.x)
+            .collect();
+
+        finish_x(xs, end_trigger)
+    }
+
+    fn low_detail_object_count(self) -> u64 {
+        self.1.filter(|object| !object.high_detail).count() as u64
+    }
+
+    fn user_coins(self) -> Vec<(f32, f32)> {
+        let mut coins: Vec<_> = self.1.filter(|object| object.id == ids::USER_COIN).map(|object| (object.x, object.y)).collect();
+
+        coins.sort_by(|(x1, _), (x2, _)| x1.total_cmp(x2));
+        coins
     }
 
     fn metadata(&self) -> LevelMetadata {
@@ -106,10 +310,10 @@ where
     fn stats(self) -> Stats {
         let ParIterSource(metadata, iter) = self;
 
-        let (mut portals, object_count, max_x) = iter
+        let (mut portals, object_count, max_x, groups) = iter
             .fold(
-                || (Vec::new(), 0, 0.0),
-                |(mut portals, obj_count, max_x), object| {
+                || (Vec::new(), 0, 0.0, HashSet::new()),
+                |(mut portals, obj_count, max_x, mut groups), object| {
                     if let ObjectData::Portal(PortalData {
                         checked: true,
                         portal_type: PortalType::Speed(speed),
@@ -118,24 +322,64 @@ where
                         portals.push((object.x, speed))
                     }
 
-                    (portals, obj_count + 1, f32::max(max_x, object.x))
+                    groups.extend(object.groups.iter().cloned());
+
+                    (portals, obj_count + 1, f32::max(max_x, object.x), groups)
                 },
             )
             .reduce(
-                || (Vec::with_capacity(32), 0, 0.0),
-                |(mut v1, c1, x1), (v2, c2, x2)| {
+                || (Vec::with_capacity(32), 0, 0.0, HashSet::new()),
+                |(mut v1, c1, x1, mut g1), (v2, c2, x2, g2)| {
                     v1.extend(v2);
-                    (v1, c1 + c2, f32::max(x1, x2))
+                    g1.extend(g2);
+                    (v1, c1 + c2, f32::max(x1, x2), g1)
                 },
             );
 
         // The parallel parsing fucked up the order already anyway, so we wont have to bother using a stable
         // sort
-        portals.sort_unstable_by(|(x1, _), (x2, _)| x1.partial_cmp(x2).unwrap());
+        portals.sort_unstable_by(|(x1, _), (x2, _)| x1.total_cmp(x2));
 
         let duration = Duration::from_secs(portal::get_seconds_from_x_pos(max_x, metadata.starting_speed, &portals) as u64);
 
-        Stats { object_count, duration }
+        Stats {
+            object_count,
+            duration,
+            distinct_groups: groups.len() as u64,
+        }
+    }
+
+    fn finish_x(self) -> f32 {
+        let (xs, end_trigger) = self
+            .1
+            .map(|object| (object.x, if object.id == ids::END_TRIGGER { Some(object.x) } else { None }))
+            .fold(
+                || (Vec::new(), None),
+                |(mut xs, end_trigger), (x, trigger)| {
+                    xs.push(x);
+                    (xs, end_trigger.or(trigger))
+                },
+            )
+            .reduce(
+                || (Vec::new(), None),
+                |(mut xs1, t1), (xs2, t2)| {
+                    xs1.extend(xs2);
+                    (xs1, t1.or(t2))
+                },
+            );
+
+        finish_x(xs, end_trigger)
+    }
+
+    fn low_detail_object_count(self) -> u64 {
+        self.1.filter(|object| !object.high_detail).count() as u64
+    }
+
+    fn user_coins(self) -> Vec<(f32, f32)> {
+        let mut coins: Vec<_> = self.1.filter(|object| object.id == ids::USER_COIN).map(|object| (object.x, object.y)).collect();
+
+        coins.sort_unstable_by(|(x1, _), (x2, _)| x1.total_cmp(x2));
+        coins
     }
 
     fn metadata(&self) -> LevelMetadata {
@@ -146,11 +390,13 @@ where
 parser! {
     LevelObject => {
         id(index = 1),
-        x(index = 2),
-        y(index = 3),
+        x(index = 2, parse = CoordinateConverter),
+        y(index = 3, parse = CoordinateConverter),
         flipped_y(index = 4, optional),
         flipped_x(index = 5, optional),
         rotation(index = 6, optional),
+        groups(index = 57, parse = GroupsConverter, default),
+        high_detail(index = 103, optional),
         // ... all the other fields ...
         metadata(delegate),
     }
@@ -159,21 +405,391 @@ parser! {
 parser! {
     LevelMetadata => {
         starting_speed(index = kA4),
-        song_offset(index = kA13),
-        fade_in(index = kA15),
-        fade_out(index = kA16),
-        // song guidelines: kA16
-        // background texture index: kA6
+        song_offset(index = kA13, parse = FractionalSecondsConverter, default),
+        fade_in(index = kA15, default),
+        fade_out(index = kA16, default),
+        song_guidelines(index = kA14, parse = GuidelinesConverter, default),
+        // background texture index (not the song, which the header doesn't contain): kA6
         // ground texture index: kA7
         // ground line index: kA17
         // font: kA18
         // color page (???): kS39
         // starting game mode: kA2
         // starting size: kA3
-        dual_start(index = kA8),
+        dual_start(index = kA8, default),
         // level/start pos (???): kA9
-        two_player_controls(index = kA10),
-        start_gravity_inverted(index = kA11, optional),
+        two_player_controls(index = kA10, default),
+        start_gravity_inverted(index = kA11, default),
+        color_channels(index = kS38, parse = ColorChannelsConverter, default),
         // ... all the other fields ...
     }
 }
+
+parser! {
+    ColorChannel => {
+        id(index = 6),
+        r(index = 1),
+        g(index = 2),
+        b(index = 3),
+        blending_enabled(index = 5, default),
+        opacity(index = 7, default_with = default_opacity),
+        copy_ref(index = 9, default),
+        copy_hsv(index = 10, parse = HsvConverter, default),
+    }
+}
+
+// Channels that don't specify an opacity are fully opaque
+fn default_opacity() -> f32 {
+    1.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        finish_x, parse_lazy, parse_lazy_limited, parse_objects_streaming_limited, LevelInformationSource, ParseObjectsStreaming, VerifyCoins,
+    };
+    use crate::{
+        convert::{ColorChannelsConverter, RobtopFrom, RobtopInto},
+        error::ValueError,
+        Parse,
+    };
+    use flate2::{write::GzEncoder, Compression};
+    use gdcf_model::level::{
+        data::{
+            color::{ColorChannel, HsvData},
+            LevelData, LevelMetadata, LevelObject,
+        },
+        Level,
+    };
+    use std::{
+        io::{ErrorKind, Write},
+        rc::Rc,
+        time::Duration,
+    };
+
+    const LEVEL: &str = "1:10565740:2:Bloodbath:5:3:6:503085:8:10:9:50:10:31279016:12:0:13:21:14:1549098:15:3:17:1:18:10:19:10330:\
+                         25::30:7679228:31:0:35:467339:37:0:38:0:39:10:42:0:43:0:45:24746:4::27:0:28:5 years:29:3 years";
+
+    fn level_with_data(level_string: &str) -> Level<Option<u64>, u64> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(level_string.as_bytes()).unwrap();
+
+        let mut level = Level::parse_str(LEVEL, ':').unwrap();
+        level.level_data = LevelData(encoder.finish().unwrap());
+        level
+    }
+
+    fn level_string(objects: usize) -> String {
+        let mut level_string = String::from("kA4,0;");
+
+        for _ in 0..objects {
+            level_string.push_str("1,1,2,15,3,15;");
+        }
+
+        level_string
+    }
+
+    #[test]
+    fn parse_lazy_limited_accepts_levels_at_the_limit() {
+        let level_string = level_string(100);
+        let source = parse_lazy_limited(&level_string, 100).unwrap();
+
+        assert_eq!(source.collect().len(), 100);
+    }
+
+    #[test]
+    fn parse_lazy_limited_rejects_absurd_object_counts() {
+        match parse_lazy_limited(&level_string(101), 100) {
+            Err(ValueError::TooManyObjects(100)) => (),
+            _ => panic!("level with 101 objects was accepted with a limit of 100"),
+        }
+
+        match parse_lazy_limited(&";".repeat(10_000_000), 100) {
+            Err(ValueError::TooManyObjects(100)) => (),
+            _ => panic!("level with 10 million objects was accepted with a limit of 100"),
+        }
+    }
+
+    #[test]
+    fn parse_objects_streaming_limited_stops_at_the_limit() {
+        let level_string = level_string(10_000);
+        let level = level_with_data(&level_string);
+
+        let source = parse_objects_streaming_limited(&level, level_string.len()).unwrap();
+        assert_eq!(source.collect().len(), 10_000);
+
+        // The iterator ends early once the limit is exceeded
+        let source = parse_objects_streaming_limited(&level, level_string.len() / 2).unwrap();
+        assert!(source.collect().len() < 5_000);
+    }
+
+    #[test]
+    fn stats_of_large_level_are_computed_while_streaming() {
+        let level = level_with_data(&level_string(1_000_000));
+
+        let stats = level.parse_objects_streaming().unwrap().stats();
+
+        assert_eq!(stats.object_count, 1_000_000);
+    }
+
+    #[test]
+    fn parse_objects_streaming_limited_rejects_oversize_metadata() {
+        // A single huge chunk must not be buffered in its entirety
+        let level = level_with_data(&"kA4,0,".repeat(1024 * 1024));
+
+        let result = parse_objects_streaming_limited(&level, 1024).map(|_| ());
+
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn color_channels_default_to_opaque() {
+        let channels =
+            ColorChannelsConverter::robtop_from("1_40_2_125_3_255_11_255_12_255_13_255_4_-1_6_1000|1_0_2_0_3_0_6_1001_7_0.5").unwrap();
+
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0].id, 1000);
+        assert_eq!((channels[0].r, channels[0].g, channels[0].b), (40, 125, 255));
+        assert_eq!(channels[0].opacity, 1.0);
+        assert_eq!(channels[1].id, 1001);
+        assert_eq!(channels[1].opacity, 0.5);
+    }
+
+    #[test]
+    fn color_channels_parse_copy_color_hsv() {
+        let channels = ColorChannelsConverter::robtop_from("1_255_2_255_3_255_6_3_9_1000_10_-30a0.5a1a1a0").unwrap();
+
+        assert_eq!(channels[0].copy_ref, Some(1000));
+        assert_eq!(
+            channels[0].copy_hsv,
+            Some(HsvData {
+                hue: -30,
+                saturation: 0.5,
+                value: 1.0,
+                saturation_additive: true,
+                value_additive: false,
+            })
+        );
+
+        let channels = ColorChannelsConverter::robtop_from("1_255_2_255_3_255_6_3").unwrap();
+
+        assert_eq!(channels[0].copy_ref, None);
+        assert_eq!(channels[0].copy_hsv, None);
+    }
+
+    #[test]
+    fn color_channels_round_trip() {
+        let channels = vec![
+            ColorChannel {
+                id: 1000,
+                r: 40,
+                g: 125,
+                b: 255,
+                blending_enabled: true,
+                opacity: 1.0,
+                copy_ref: None,
+                copy_hsv: None,
+            },
+            ColorChannel {
+                id: 3,
+                r: 0,
+                g: 0,
+                b: 0,
+                blending_enabled: false,
+                opacity: 0.0,
+                copy_ref: Some(1000),
+                copy_hsv: Some(HsvData {
+                    hue: 90,
+                    saturation: 1.0,
+                    value: -0.25,
+                    saturation_additive: false,
+                    value_additive: true,
+                }),
+            },
+        ];
+
+        let unparsed = RobtopInto::<ColorChannelsConverter, String>::robtop_into(channels.clone());
+
+        assert_eq!(ColorChannelsConverter::robtop_from(&unparsed).unwrap(), channels);
+    }
+
+    #[test]
+    fn level_metadata_parses_song_guidelines_duration() {
+        let metadata = LevelMetadata::parse_str("kA4,1,kA14,0.5~0.8~12.25~0.9~3~0~", ',').unwrap();
+
+        assert_eq!(metadata.song_guidelines.len(), 3);
+        assert_eq!(metadata.song_guidelines_duration(), Some(Duration::from_millis(12_250)));
+    }
+
+    #[test]
+    fn level_metadata_without_song_guidelines() {
+        assert_eq!(LevelMetadata::parse_str("kA4,1", ',').unwrap().song_guidelines_duration(), None);
+        assert_eq!(LevelMetadata::parse_str("kA4,1,kA14,", ',').unwrap().song_guidelines_duration(), None);
+    }
+
+    #[test]
+    fn song_guidelines_round_trip() {
+        let metadata = LevelMetadata::parse_str("kA4,1,kA14,0.5~0.8~12.25~0.9~3~0~", ',').unwrap();
+
+        assert_eq!(metadata.clone().unparse().get("kA14").map(String::as_str), Some("0.5~0.8~12.25~0.9~3~0~"));
+        assert_eq!(LevelMetadata::parse_str(&metadata.to_response_string(','), ',').unwrap(), metadata);
+    }
+
+    #[test]
+    fn malformed_song_guidelines_are_rejected() {
+        assert!(LevelMetadata::parse_str("kA4,1,kA14,0.5~0.8~12.25~", ',').is_err());
+        assert!(LevelMetadata::parse_str("kA4,1,kA14,-2~0.8~", ',').is_err());
+    }
+
+    #[test]
+    fn level_metadata_parses_color_channels() {
+        let metadata = LevelMetadata::parse_str("kA4,1,kS38,1_255_2_0_3_0_6_1004|1_0_2_255_3_0_6_1_7_0.25", ',').unwrap();
+
+        assert_eq!(metadata.color_channels.len(), 2);
+        assert_eq!(metadata.color_channels[0].id, 1004);
+        assert_eq!(metadata.color_channels[0].opacity, 1.0);
+        assert_eq!(metadata.color_channels[1].opacity, 0.25);
+    }
+
+    #[test]
+    fn low_detail_object_count_skips_high_detail_objects() {
+        let level_string = "kA4,0;1,1,2,15,3,15;1,1,2,45,3,15,103,1;1,1,2,75,3,15,103,0;1,914,2,105,3,15,103,1;1,1,2,135,3,15;";
+
+        assert_eq!(parse_lazy(level_string).unwrap().low_detail_object_count(), 3);
+        assert_eq!(parse_lazy(level_string).unwrap().collect().iter().filter(|object| object.high_detail).count(), 2);
+    }
+
+    #[test]
+    fn verify_coins_reports_missing_coin_objects() {
+        let mut level = level_with_data("kA4,0;1,1329,2,45,3,15;1,1,2,15,3,15;1,1329,2,30,3,75;");
+        level.base.coin_amount = 3;
+
+        let verification = level.verify_coins().unwrap();
+
+        assert_eq!(verification.advertised, 3);
+        assert_eq!(verification.found, 2);
+        assert_eq!(verification.positions, vec![(30.0, 75.0), (45.0, 15.0)]);
+        assert!(!verification.is_consistent());
+    }
+
+    #[test]
+    fn verify_coins_accepts_matching_coin_objects() {
+        let mut level = level_with_data("kA4,0;1,1329,2,45,3,15;1,1329,2,30,3,75;");
+        level.base.coin_amount = 2;
+
+        assert!(level.verify_coins().unwrap().is_consistent());
+    }
+
+    #[test]
+    fn finish_x_ignores_decoration_far_past_the_end() {
+        let mut level_string = String::from("kA4,0;");
+
+        for idx in 0..40 {
+            level_string.push_str(&format!("1,1,2,{},3,15;", 15 + 30 * idx));
+        }
+
+        // A hidden message way to the right of the actual level
+        level_string.push_str("1,914,2,50000,3,15;");
+
+        assert_eq!(parse_lazy(&level_string).unwrap().finish_x(), 1185.0);
+    }
+
+    #[test]
+    fn finish_x_prefers_end_trigger() {
+        let level_string = "kA4,0;1,1,2,15,3,15;1,3600,2,645,3,15;1,1,2,1245,3,15;";
+
+        assert_eq!(parse_lazy(level_string).unwrap().finish_x(), 645.0);
+    }
+
+    #[test]
+    fn finish_x_ignores_nan_positions() {
+        assert_eq!(finish_x(vec![15.0, std::f32::NAN, 45.0], None), 45.0);
+        assert_eq!(finish_x(vec![std::f32::NAN], None), 0.0);
+    }
+
+    #[test]
+    fn user_coins_are_ordered_by_x_position() {
+        let level_string = "kA4,0;1,1329,2,645,3,15;1,1,2,15,3,15;1,1329,2,45,3,75;1,1329,2,-15,3,105;";
+
+        assert_eq!(parse_lazy(level_string).unwrap().user_coins(), vec![(-15.0, 105.0), (45.0, 75.0), (645.0, 15.0)]);
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_user_coins_are_ordered_by_x_position() {
+        use super::ParIterSource;
+        use gdcf_model::level::data::LevelObject;
+        use rayon::iter::IntoParallelIterator;
+
+        let objects: Vec<_> = ["1,1329,2,645,3,15", "1,1,2,15,3,15", "1,1329,2,45,3,75", "1,1329,2,-15,3,105"]
+            .iter()
+            .map(|object| LevelObject::parse_str(object, ',').unwrap())
+            .collect();
+        let source = ParIterSource(LevelMetadata::default(), objects.into_par_iter());
+
+        assert_eq!(source.user_coins(), vec![(-15.0, 105.0), (45.0, 75.0), (645.0, 15.0)]);
+    }
+
+    #[test]
+    fn identical_objects_share_interned_data() {
+        let mut level_string = level_string(1000);
+
+        for _ in 0..500 {
+            level_string.push_str("1,203,2,30,3,15,13,1;");
+        }
+
+        let (objects, palette) = parse_lazy(&level_string).unwrap().collect_interned();
+
+        assert_eq!(objects.len(), 1500);
+        assert_eq!(palette.len(), 2);
+
+        let block_data = &objects[0].metadata;
+        let portal_data = &objects[1000].metadata;
+
+        assert!(!Rc::ptr_eq(block_data, portal_data));
+        assert!(objects[..1000].iter().all(|object| Rc::ptr_eq(&object.metadata, block_data)));
+        assert!(objects[1000..].iter().all(|object| Rc::ptr_eq(&object.metadata, portal_data)));
+
+        // every object, plus the palette itself
+        assert_eq!(Rc::strong_count(block_data), 1001);
+        assert_eq!(Rc::strong_count(portal_data), 501);
+    }
+
+    #[test]
+    fn stats_count_distinct_groups() {
+        let grouped = "kA4,0;1,1,2,15,3,15,57,1.2;1,1,2,45,3,15,57,2.3;1,1,2,75,3,15,57,3.3.1;1,1,2,105,3,15;1,1,2,135,3,15,57,10;";
+
+        let stats = parse_lazy(grouped).unwrap().stats();
+
+        assert_eq!(stats.object_count, 5);
+        assert_eq!(stats.distinct_groups, 4);
+        assert_eq!(parse_lazy(&level_string(10)).unwrap().stats().distinct_groups, 0);
+    }
+
+    #[test]
+    fn objects_with_extreme_coordinates() {
+        let object = LevelObject::parse_str("1,1,2,-1e-3,3,-3000", ',').unwrap();
+
+        assert_eq!((object.x, object.y), (-0.001, -3000.0));
+
+        let object = LevelObject::parse_str("1,1,2,3.0e38,3,15", ',').unwrap();
+
+        assert_eq!(object.x, 3.0e38);
+
+        for &coordinates in &["2,NaN,3,15", "2,15,3,inf", "2,-inf,3,15", "2,4e38,3,15"] {
+            assert!(LevelObject::parse_str(&format!("1,1,{}", coordinates), ',').is_err(), "{} was accepted", coordinates);
+        }
+    }
+
+    #[test]
+    fn stats_of_level_far_beyond_the_start() {
+        let level_string = "kA4,0;1,1,2,-500,3,-90;1,203,2,300,3,15,13,1;1,1,2,1000000,3,-3000;";
+
+        let stats = parse_lazy(level_string).unwrap().stats();
+        let expected = 300.0 / 311.58 + (1_000_000.0 - 300.0) / 468.0;
+
+        assert_eq!(stats.object_count, 3);
+        assert_eq!(stats.duration, Duration::from_secs(expected as u64));
+        assert_eq!(parse_lazy(level_string).unwrap().finish_x(), 1_000_000.0);
+    }
+}
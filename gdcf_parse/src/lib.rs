@@ -6,7 +6,7 @@
 //trace_macros!(true);
 
 use crate::{error::ValueError, util::SelfZipExt};
-use std::collections::HashMap;
+use std::{cmp::Ordering, collections::HashMap};
 
 #[macro_use]
 extern crate log;
@@ -16,6 +16,8 @@ pub mod util;
 pub mod macros;
 pub mod comment;
 pub mod convert;
+#[cfg(feature = "debug")]
+pub mod debug;
 pub mod error;
 pub mod level;
 pub mod song;
@@ -27,6 +29,20 @@ const INDICES: [&str; 50] = [
     "47", "48", "49", "50",
 ];
 
+/// How indices appearing more than once in a response are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateIndices {
+    /// The last value given for an index is used. This is what [`Parse::parse_str`] does.
+    ///
+    /// Note that earlier values are still parsed, they are only discarded afterwards. This means
+    /// that a malformed earlier value can still fail parsing, even if the last value is well-formed.
+    #[default]
+    LastWins,
+
+    /// Parsing fails with [`ValueError::DuplicateIndex`]
+    Reject,
+}
+
 pub trait Parse<'a>: Sized {
     fn parse<I, F>(iter: I, f: F) -> Result<Self, ValueError<'a>>
     where
@@ -34,7 +50,45 @@ pub trait Parse<'a>: Sized {
         F: FnMut(&'a str, &'a str) -> Result<(), ValueError<'a>>;
     fn unparse(self) -> HashMap<&'a str, String>;
 
+    /// Like [`Parse::parse`], but doesn't stop at the first error
+    ///
+    /// Instead, all values are processed and every error encountered is returned, together with a
+    /// best-effort object. This is meant for debugging malformed responses, where it is useful to see
+    /// all broken fields at once. Fields that have a default value fall back to it if their value is
+    /// broken, so the object is only [`None`] if a required value is missing or broken. The default
+    /// implementation simply wraps the result of [`Parse::parse`].
+    fn parse_collecting<I, F>(iter: I, f: F) -> (Option<Self>, Vec<ValueError<'a>>)
+    where
+        I: Iterator<Item = (&'a str, &'a str)> + Clone,
+        F: FnMut(&'a str, &'a str) -> Result<(), ValueError<'a>>,
+    {
+        match Self::parse(iter, f) {
+            Ok(parsed) => (Some(parsed), Vec::new()),
+            Err(err) => (None, vec![err]),
+        }
+    }
+
+    fn parse_str_collecting(input: &'a str, delimiter: char) -> (Option<Self>, Vec<ValueError<'a>>) {
+        if let Err(err) = util::check_empty(input.split(delimiter)) {
+            return (None, vec![err])
+        }
+
+        Self::parse_collecting(input.split(delimiter).self_zip(), |i, v| {
+            Ok(warn!(
+                "Unused value '{}' at index '{}'. This can and will lead to incorrect .unparse() results",
+                v, i
+            ))
+        })
+    }
+
+    /// Parses the given `index, value, index, value, ...` sequence
+    ///
+    /// If the sequence contains no data, that is, if it is empty or all its tokens are empty (which
+    /// is what splitting an empty string, or a string consisting only of delimiters, produces), this
+    /// fails with [`ValueError::EmptyResponse`].
     fn parse_iter(iter: impl Iterator<Item = &'a str> + Clone) -> Result<Self, ValueError<'a>> {
+        util::check_empty(iter.clone())?;
+
         Self::parse(iter.self_zip(), |i, v| {
             Ok(warn!(
                 "Unused value '{}' at index '{}'. This can and will lead to incorrect .unparse() results",
@@ -43,7 +97,11 @@ pub trait Parse<'a>: Sized {
         })
     }
 
+    /// Like [`Parse::parse_iter`], but for sequences consisting only of values, whose indices are
+    /// implied by their position
     fn parse_unindexed_iter(iter: impl Iterator<Item = &'a str> + Clone) -> Result<Self, ValueError<'a>> {
+        util::check_empty(iter.clone())?;
+
         // well this is a stupid solution
         Self::parse(INDICES.iter().cloned().zip(iter), |i, v| {
             Ok(warn!(
@@ -53,10 +111,39 @@ pub trait Parse<'a>: Sized {
         })
     }
 
+    /// Parses the given `index<delimiter>value<delimiter>...` string
+    ///
+    /// If an index appears more than once, the last value wins (but every value has to parse, see
+    /// [`DuplicateIndices::LastWins`]). Use [`Parse::parse_str_with`] to reject such input instead.
+    /// Empty input, as well as input consisting only of delimiters, fails
+    /// with [`ValueError::EmptyResponse`].
     fn parse_str(input: &'a str, delimiter: char) -> Result<Self, ValueError> {
         Self::parse_iter(input.split(delimiter))
     }
 
+    /// Like [`Parse::parse_str`], but handles indices appearing more than once according to the
+    /// given [`DuplicateIndices`] policy
+    fn parse_str_with(input: &'a str, delimiter: char, duplicates: DuplicateIndices) -> Result<Self, ValueError> {
+        if duplicates == DuplicateIndices::Reject {
+            util::check_duplicates(input.split(delimiter))?;
+        }
+
+        Self::parse_str(input, delimiter)
+    }
+
+    /// Like [`Parse::parse_str`], but first checks that the input properly alternates between
+    /// indices and values
+    ///
+    /// A stray delimiter inside the input shifts every following index, which `parse_str` would
+    /// silently misinterpret. Instead, this returns [`ValueError::Alternation`] containing the
+    /// position of the offending token if an index is empty or the last index has no value.
+    fn parse_str_strict(input: &'a str, delimiter: char) -> Result<Self, ValueError> {
+        util::check_empty(input.split(delimiter))?;
+        util::check_alternation(input.split(delimiter))?;
+
+        Self::parse_str(input, delimiter)
+    }
+
     fn parse_str2(input: &'a str, delimiter: &'a str) -> Result<Self, ValueError<'a>> {
         Self::parse_iter(input.split(delimiter))
     }
@@ -68,6 +155,36 @@ pub trait Parse<'a>: Sized {
     fn parse_unindexed_str2(input: &'a str, delimiter: &'a str) -> Result<Self, ValueError<'a>> {
         Self::parse_unindexed_iter(input.split(delimiter))
     }
+
+    /// Converts this object back into the `index<delimiter>value<delimiter>...` format it was
+    /// parsed from, making this the inverse of [`Parse::parse_str`]
+    ///
+    /// Values are re-encoded the way the servers provide them (e.g. base64 for descriptions), and
+    /// indices whose value can be omitted are left out. The indices are sorted numerically, so the
+    /// result doesn't necessarily have the exact same order as the original response.
+    ///
+    /// Since [`Parse::unparse`] consumes the object, this unparses a clone of it.
+    fn to_response_string(&self, delimiter: char) -> String
+    where
+        Self: Clone,
+    {
+        let mut values: Vec<_> = self.clone().unparse().into_iter().collect();
+
+        values.sort_by(|(idx1, _), (idx2, _)| {
+            match (idx1.parse::<u32>(), idx2.parse::<u32>()) {
+                (Ok(idx1), Ok(idx2)) => idx1.cmp(&idx2),
+                (Ok(_), Err(_)) => Ordering::Less,
+                (Err(_), Ok(_)) => Ordering::Greater,
+                (Err(_), Err(_)) => idx1.cmp(idx2),
+            }
+        });
+
+        values
+            .into_iter()
+            .flat_map(|(index, value)| vec![index.to_string(), value])
+            .collect::<Vec<_>>()
+            .join(&delimiter.to_string())
+    }
 }
 
 pub struct UnparseSafe<'a, P: Parse<'a>> {
@@ -97,3 +214,60 @@ impl<'a, P: Parse<'a>> Parse<'a> for UnparseSafe<'a, P> {
         inner_unparsed
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::ValueError, DuplicateIndices, Parse};
+    use gdcf_model::user::Creator;
+
+    #[test]
+    fn duplicate_index_last_value_wins() {
+        let creator = Creator::parse_str_with("1:5:2:Riot:1:6", ':', DuplicateIndices::LastWins).unwrap();
+
+        assert_eq!(creator.user_id, 6);
+        assert_eq!(Creator::parse_str("1:5:2:Riot:1:6", ':').unwrap(), creator);
+    }
+
+    #[test]
+    fn duplicate_index_rejected() {
+        match Creator::parse_str_with("1:5:2:Riot:1:6", ':', DuplicateIndices::Reject) {
+            Err(ValueError::DuplicateIndex("1")) => (),
+            result => panic!("unexpected parse result {:?}", result),
+        }
+
+        assert_eq!(Creator::parse_str_with("1:5:2:Riot", ':', DuplicateIndices::Reject).unwrap().user_id, 5);
+    }
+
+    #[test]
+    fn strict_parsing_allows_duplicate_indices() {
+        assert_eq!(Creator::parse_str_strict("1:5:2:Riot:1:6", ':').unwrap().user_id, 6);
+    }
+
+    #[test]
+    fn empty_input_is_rejected() {
+        for &input in &["", ":", ":::"] {
+            match Creator::parse_str(input, ':') {
+                Err(ValueError::EmptyResponse) => (),
+                result => panic!("unexpected parse result {:?} for input {:?}", result, input),
+            }
+
+            match Creator::parse_str_strict(input, ':') {
+                Err(ValueError::EmptyResponse) => (),
+                result => panic!("unexpected strict parse result {:?} for input {:?}", result, input),
+            }
+
+            match Creator::parse_unindexed_str(input, ':') {
+                Err(ValueError::EmptyResponse) => (),
+                result => panic!("unexpected unindexed parse result {:?} for input {:?}", result, input),
+            }
+        }
+
+        let (creator, errors) = Creator::parse_str_collecting("", ':');
+
+        assert!(creator.is_none());
+        match errors.as_slice() {
+            [ValueError::EmptyResponse] => (),
+            errors => panic!("unexpected errors {:?}", errors),
+        }
+    }
+}
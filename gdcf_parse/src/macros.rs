@@ -80,8 +80,10 @@ macro_rules! __match_arm_expr {
 }
 
 macro_rules! __into_expr {
+    // Optional values are only emitted if they cannot be omitted. Omitted values are parsed back to
+    // their default
     (@ $map: expr, $value: expr, index = $idx: expr, parse = $external: ident, optional $(, $($__:tt)*)?) => {{
-        if RobtopInto::<$external, _>::can_omit(&$value) {
+        if !RobtopInto::<$external, _>::can_omit(&$value) {
             $map.insert(stringify!($idx), RobtopInto::<$external, _>::robtop_into($value));
         }
     }};
@@ -99,9 +101,17 @@ macro_rules! __into_expr {
         $map.insert(stringify!($idx), RobtopInto::<$external, _>::robtop_into($value))
     }};
 
-    // Custom parser that cannot fail
+    // Custom parser that cannot fail. These are always used with default values, so we can omit them
     (@ $map: expr, $value: expr, index = $idx: expr, parse_infallible = $external: ident $(, $($__:tt)*)?) => {{
-        __into_expr!(@ $map, $value, index = $idx, parse = $external)
+        __into_expr!(@ $map, $value, index = $idx, parse = $external, optional)
+    }};
+
+    // Values with a custom default are only emitted if they differ from it, since omitted values are
+    // parsed back to that default
+    (@ $map: expr, $value: expr, index = $idx: expr, default_with = $default: path) => {{
+        if $value != $default() {
+            $map.insert(stringify!($idx), crate::util::unparse($value));
+        }
     }};
 
     // Built-in parsing
@@ -165,6 +175,10 @@ macro_rules! __unwrap {
         $field_name.unwrap_or_else($default_func)
     };
 
+    ($field_name: ident($(^)?index = $idx: expr, default_with = $default_func: path)) => {
+        $field_name.unwrap_or_else($default_func)
+    };
+
     ($field_name: ident($(^)?index = $idx: expr, parse_infallible = $_: ty $(, $($crap:tt)*)?)) => {
         __unwrap!($field_name(index = $idx $(, $($crap)*)?))
     };
@@ -182,6 +196,33 @@ macro_rules! __unwrap {
     };
 }
 
+// Emits a trace message with the raw value of a field before any conversion happens. Only active
+// with the `field_tracing` feature, as it produces a lot of output
+#[cfg(feature = "field_tracing")]
+macro_rules! __trace_field {
+    ($struct_name: ty, $field_name: ident, $idx: expr, $value: expr) => {
+        trace!(
+            "{}::{} at index {}: '{}'",
+            stringify!($struct_name),
+            stringify!($field_name),
+            $idx,
+            $value
+        )
+    };
+}
+
+#[cfg(not(feature = "field_tracing"))]
+macro_rules! __trace_field {
+    ($struct_name: ty, $field_name: ident, $idx: expr, $value: expr) => {{}};
+}
+
+// Variant of `__unwrap!` that records the error of a missing value instead of returning it
+macro_rules! __unwrap_collecting {
+    ($errors: ident, $field_name: ident($($tokens: tt)*)) => {
+        $crate::util::collect_error(&mut $errors, || Ok(__unwrap!($field_name($($tokens)*))))
+    };
+}
+
 macro_rules! __declare {
     ($field_name: ident, index = $idx: expr, ignore $(, $($t:tt)*)?) => {{}};
     ($field_name: ident, $($t:tt)*) => {
@@ -189,6 +230,42 @@ macro_rules! __declare {
     };
 }
 
+// Binds the unwrapped value of a helper variable. Ignored helpers have no value, so nothing is bound
+// for them
+macro_rules! __bind {
+    ($field_name: ident, index = $idx: expr, ignore $(, $($t:tt)*)?) => {};
+    ($field_name: ident, $($t:tt)*) => {
+        let $field_name = __unwrap!($field_name($($t)*));
+    };
+}
+
+// Variants of `__bind!` for `parse_collecting`, which first collects the errors of all values and
+// only unwraps them afterwards
+macro_rules! __bind_collecting {
+    ($errors: ident, $field_name: ident, index = $idx: expr, ignore $(, $($t:tt)*)?) => {};
+    ($errors: ident, $field_name: ident, $($t:tt)*) => {
+        let $field_name = __unwrap_collecting!($errors, $field_name($($t)*));
+    };
+}
+
+// Binds the collected value of a helper variable, returning without an object if it is missing
+macro_rules! __bind_collected {
+    ($errors: ident, $field_name: ident, index = $idx: expr, ignore $(, $($t:tt)*)?) => {};
+    ($errors: ident, $field_name: ident, $($t:tt)*) => {
+        let $field_name = __collected!($errors, $field_name);
+    };
+}
+
+// Unwraps a collected value, returning the errors without an object if it is missing
+macro_rules! __collected {
+    ($errors: ident, $value: ident) => {
+        match $value {
+            Some(value) => value,
+            None => return (None, $errors),
+        }
+    };
+}
+
 macro_rules! parser {
     ($struct_name: ty => {$($tokens:tt)*}$(, $($tokens2:tt)*)?) => {
         parser!(@ $struct_name [] [] [] [$($tokens)*] [$($($tokens2)*)?]);
@@ -243,10 +320,16 @@ macro_rules! parser {
                 for (idx, value) in iter.into_iter() {
                     match idx {
                         $(
-                            __index!($($tokens)*) => __match_arm_expr!(@ f, $field_name, value, $($tokens)*),
+                            __index!($($tokens)*) => {
+                                __trace_field!($struct_name, $field_name, idx, value);
+                                __match_arm_expr!(@ f, $field_name, value, $($tokens)*)
+                            },
                         )*
                         $(
-                            __index!($($tokens2)*) => __match_arm_expr!(@ f, $helper_field, value, $($tokens2)*),
+                            __index!($($tokens2)*) => {
+                                __trace_field!($struct_name, $helper_field, idx, value);
+                                __match_arm_expr!(@ f, $helper_field, value, $($tokens2)*)
+                            },
                         )*
                         _ => f(idx, value)?
                     }
@@ -257,21 +340,108 @@ macro_rules! parser {
                 )*
 
                 $(
-                    let $helper_field = __unwrap!($helper_field($($tokens2)*));
+                    __bind!($helper_field, $($tokens2)*);
                 )*
 
                 trace!("Finished parsing {}", stringify!($struct_name));
 
+                // Computed before constructing the struct so custom functions may borrow fields
+                $(
+                    let $custom_field = $func($($field,)*);
+                )*
+
                 Ok(Self {
                     $(
                         $field_name,
                     )*
                     $(
-                        $custom_field: $func($($field,)*),
+                        $custom_field,
                     )*
                 })
             }
 
+            fn parse_collecting<I, F>(iter: I, mut f: F) -> (Option<Self>, Vec<ValueError<'a>>)
+            where
+                I: Iterator<Item = (&'a str, &'a str)> + Clone,
+                F: FnMut(&'a str, &'a str) -> Result<(), ValueError<'a>>
+            {
+                #[allow(unused_imports)]
+                use $crate::util::parse;
+                #[allow(unused_imports)]
+                use $crate::convert::RobtopFromInfallible;
+                #[allow(unused_imports)]
+                use $crate::convert::RobtopFrom;
+
+                trace!("Parsing {}, collecting errors", stringify!($struct_name));
+
+                let mut errors = Vec::new();
+
+                $(
+                    __declare!($field_name, $($tokens)*);
+                )*
+
+                $(
+                    __declare!($helper_field, $($tokens2)*);
+                )*
+
+                for (idx, value) in iter.into_iter() {
+                    $crate::util::collect_error(&mut errors, || -> Result<(), ValueError<'a>> {
+                        match idx {
+                            $(
+                                __index!($($tokens)*) => {
+                                    __trace_field!($struct_name, $field_name, idx, value);
+                                    __match_arm_expr!(@ f, $field_name, value, $($tokens)*)
+                                },
+                            )*
+                            $(
+                                __index!($($tokens2)*) => {
+                                    __trace_field!($struct_name, $helper_field, idx, value);
+                                    __match_arm_expr!(@ f, $helper_field, value, $($tokens2)*)
+                                },
+                            )*
+                            _ => f(idx, value)?
+                        }
+
+                        Ok(())
+                    });
+                }
+
+                $(
+                    let $field_name = __unwrap_collecting!(errors, $field_name($($tokens)*));
+                )*
+
+                $(
+                    __bind_collecting!(errors, $helper_field, $($tokens2)*);
+                )*
+
+                $crate::util::collapse_errors(&mut errors);
+
+                if !errors.is_empty() {
+                    debug!("Parsing {} encountered {} errors", stringify!($struct_name), errors.len());
+                }
+
+                $(
+                    let $field_name = __collected!(errors, $field_name);
+                )*
+
+                $(
+                    __bind_collected!(errors, $helper_field, $($tokens2)*);
+                )*
+
+                $(
+                    let $custom_field = $func($($field,)*);
+                )*
+
+                (Some(Self {
+                    $(
+                        $field_name,
+                    )*
+                    $(
+                        $custom_field,
+                    )*
+                }), errors)
+            }
+
             fn unparse(self) -> std::collections::HashMap<&'a str, String> {
                 #[allow(unused_imports)]
                 use crate::convert::RobtopInto;
@@ -345,10 +515,16 @@ macro_rules! parser {
                 let closure = |idx: &'a str, value: &'a str| -> Result<(), ValueError<'a>> {
                     match idx {
                         $(
-                            __index!($($tokens)*) => __match_arm_expr!(@ f, $field_name, value, $($tokens)*),
+                            __index!($($tokens)*) => {
+                                __trace_field!($struct_name, $field_name, idx, value);
+                                __match_arm_expr!(@ f, $field_name, value, $($tokens)*)
+                            },
                         )*
                         $(
-                            __index!($($tokens2)*) => __match_arm_expr!(@ f, $helper_field, value, $($tokens2)*),
+                            __index!($($tokens2)*) => {
+                                __trace_field!($struct_name, $helper_field, idx, value);
+                                __match_arm_expr!(@ f, $helper_field, value, $($tokens2)*)
+                            },
                         )*
                         _ => f(idx, value)?
                     }
@@ -363,7 +539,7 @@ macro_rules! parser {
                 )*
 
                 $(
-                    let $helper_field = __unwrap!($helper_field($($tokens2)*));
+                    __bind!($helper_field, $($tokens2)*);
                 )*
 
                 trace!("Finished parsing {}", stringify!($struct_name));
@@ -379,6 +555,89 @@ macro_rules! parser {
                 })
             }
 
+            fn parse_collecting<I, F>(iter: I, mut f: F) -> (Option<Self>, Vec<ValueError<'a>>)
+            where
+                I: Iterator<Item = (&'a str, &'a str)> + Clone,
+                F: FnMut(&'a str, &'a str) -> Result<(), ValueError<'a>>
+            {
+                #[allow(unused_imports)]
+                use $crate::util::parse;
+                #[allow(unused_imports)]
+                use $crate::convert::RobtopFromInfallible;
+                #[allow(unused_imports)]
+                use $crate::convert::RobtopFrom;
+
+                trace!("Parsing {}, collecting errors", stringify!($struct_name));
+
+                let mut errors = Vec::new();
+
+                $(
+                    __declare!($field_name, $($tokens)*);
+                )*
+
+                $(
+                    __declare!($helper_field, $($tokens2)*);
+                )*
+
+                let closure = |idx: &'a str, value: &'a str| -> Result<(), ValueError<'a>> {
+                    match idx {
+                        $(
+                            __index!($($tokens)*) => {
+                                __trace_field!($struct_name, $field_name, idx, value);
+                                __match_arm_expr!(@ f, $field_name, value, $($tokens)*)
+                            },
+                        )*
+                        $(
+                            __index!($($tokens2)*) => {
+                                __trace_field!($struct_name, $helper_field, idx, value);
+                                __match_arm_expr!(@ f, $helper_field, value, $($tokens2)*)
+                            },
+                        )*
+                        _ => f(idx, value)?
+                    }
+
+                    Ok(())
+                };
+
+                let ($delegated, inner_errors) = Parse::parse_collecting(iter, closure);
+
+                errors.extend(inner_errors);
+
+                $(
+                    let $field_name = __unwrap_collecting!(errors, $field_name($($tokens)*));
+                )*
+
+                $(
+                    __bind_collecting!(errors, $helper_field, $($tokens2)*);
+                )*
+
+                $crate::util::collapse_errors(&mut errors);
+
+                if !errors.is_empty() {
+                    debug!("Parsing {} encountered {} errors", stringify!($struct_name), errors.len());
+                }
+
+                $(
+                    let $field_name = __collected!(errors, $field_name);
+                )*
+
+                $(
+                    __bind_collected!(errors, $helper_field, $($tokens2)*);
+                )*
+
+                let $delegated = __collected!(errors, $delegated);
+
+                (Some(Self {
+                    $delegated,
+                    $(
+                        $field_name,
+                    )*
+                    $(
+                        $custom_field: $func($($field,)*),
+                    )*
+                }), errors)
+            }
+
             fn unparse(self) -> std::collections::HashMap<&'a str, String> {
                 #[allow(unused_imports)]
                 use crate::convert::RobtopInto;
@@ -1,16 +1,92 @@
-use crate::{convert::UrlConverter, error::ValueError, Parse};
+use crate::{
+    convert::{FormUrlConverter, LocaleDecimalConverter},
+    error::ValueError,
+    Parse,
+};
 use gdcf_model::song::NewgroundsSong;
 
 parser! {
     NewgroundsSong => {
         song_id(index = 1),
         name(index = 2),
-        index_3(index = 3, default),
+        artist_id(index = 3, default),
         artist(index = 4, default),
-        filesize(index = 5),
+        filesize(index = 5, parse = LocaleDecimalConverter),
         index_6(index = 6, default),
         index_7(index = 7, default),
         index_8(index = 8),
-        link(index = 10, parse = UrlConverter),
+        link(index = 10, parse = FormUrlConverter),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Parse;
+    use gdcf_model::song::NewgroundsSong;
+
+    #[test]
+    fn song_name_keeps_plus_signs() {
+        let response = "1~|~503219~|~2~|~Hearts + Minds~|~3~|~47~|~4~|~Artist+Name~|~5~|~4.2~|~6~|~~|~8~|~1~|~10~|~\
+                        http%3A%2F%2Faudio.ngfiles.com%2F503000%2F503219_Hearts+%2B+Minds.mp3";
+
+        let song = NewgroundsSong::parse_str2(response, "~|~").unwrap();
+
+        assert_eq!(song.name, "Hearts + Minds");
+        assert_eq!(song.artist, "Artist+Name");
+        assert_eq!(song.link, "http://audio.ngfiles.com/503000/503219_Hearts + Minds.mp3");
+
+        let response = song.to_response_string('|');
+        let reparsed = NewgroundsSong::parse_str(&response, '|').unwrap();
+
+        assert_eq!(reparsed, song);
+    }
+
+    #[test]
+    fn song_duration_is_estimated_from_filesize() {
+        let response = "1~|~467339~|~2~|~At the Speed of Light~|~3~|~1~|~4~|~Dimrain47~|~5~|~9.56~|~6~|~~|~8~|~1~|~10~|~\
+                        http%3A%2F%2Faudio.ngfiles.com";
+
+        // 9.56 MiB at 128kbps
+        let duration = NewgroundsSong::parse_str2(response, "~|~").unwrap().estimated_duration();
+
+        assert_eq!(duration.map(|duration| duration.as_secs()), Some(626));
+
+        let response = response.replace("9.56", "0");
+
+        assert_eq!(NewgroundsSong::parse_str2(&response, "~|~").unwrap().estimated_duration(), None);
+    }
+
+    #[test]
+    fn song_filesize_with_dot_decimal_separator() {
+        let response = "1~|~803223~|~2~|~Xenogenesis~|~3~|~34051~|~4~|~TheFatRat~|~5~|~5.68~|~6~|~~|~10~|~\
+                        http%3A%2F%2Fwww.audio.ngfiles.com%2F803000%2F803223_Xenogenesis.mp3~|~7~|~~|~8~|~1";
+
+        let song = NewgroundsSong::parse_str2(response, "~|~").unwrap();
+
+        assert_eq!(song.song_id, 803_223);
+        assert_eq!(song.name, "Xenogenesis");
+        assert_eq!(song.artist, "TheFatRat");
+        assert_eq!(song.filesize, 5.68);
+        assert_eq!(song.estimated_duration().map(|duration| duration.as_secs()), Some(372));
+    }
+
+    #[test]
+    fn song_filesize_with_comma_decimal_separator() {
+        let with_dot = "1~|~467339~|~2~|~At the Speed of Light~|~3~|~123~|~4~|~Dimrain47~|~5~|~9.56~|~6~|~~|~10~|~\
+                        http%3A%2F%2Faudio.ngfiles.com%2F467000%2F467339_At_the_Speed_of_Light_FINA.mp3~|~7~|~~|~8~|~1";
+        let with_comma = with_dot.replace("9.56", "9,56");
+
+        let song = NewgroundsSong::parse_str2(&with_comma, "~|~").unwrap();
+
+        assert_eq!(song.filesize, 9.56);
+        assert_eq!(song, NewgroundsSong::parse_str2(with_dot, "~|~").unwrap());
+    }
+
+    #[test]
+    fn malformed_song_filesize_is_rejected() {
+        let response = "1~|~467339~|~2~|~At the Speed of Light~|~3~|~123~|~4~|~Dimrain47~|~5~|~9,5,6~|~6~|~~|~10~|~\
+                        http%3A%2F%2Faudio.ngfiles.com~|~7~|~~|~8~|~1";
+
+        assert!(NewgroundsSong::parse_str2(response, "~|~").is_err());
     }
 }
@@ -68,7 +68,7 @@ parser! {
     Creator => {
         user_id(index = 1),
         name(index = 2),
-        account_id(index = 3),
+        account_id(index = 3, default),
     }
 }
 
@@ -90,3 +90,27 @@ parser! {
         user_coins(index = 17),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::Parse;
+    use gdcf_model::user::Creator;
+
+    #[test]
+    fn registered_creator() {
+        let creator = Creator::parse_unindexed_str("503085:Riot:37415", ':').unwrap();
+
+        assert_eq!(creator.account_id, Some(37415));
+        assert!(creator.is_registered());
+    }
+
+    #[test]
+    fn anonymous_creator_is_not_registered() {
+        for block in &["4170784:Anonymous:0", "4170784:Anonymous:", "4170784:Anonymous"] {
+            let creator = Creator::parse_unindexed_str(block, ':').unwrap();
+
+            assert_eq!(creator.account_id, None, "creator block {}", block);
+            assert!(!creator.is_registered(), "creator block {}", block);
+        }
+    }
+}
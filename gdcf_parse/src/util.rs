@@ -3,6 +3,7 @@ use crate::{
     error::ValueError,
 };
 use base64::{DecodeError, URL_SAFE};
+use std::collections::HashSet;
 
 #[derive(Debug, Clone)]
 pub struct SelfZip<I> {
@@ -68,6 +69,87 @@ where
         .map_err(|error| ValueError::Parse(idx, value, error))
 }
 
+/// Checks that the given tokens alternate between non-empty indices and values
+///
+/// Returns the position of the first token at which the alternation broke
+pub fn check_alternation<'a>(tokens: impl Iterator<Item = &'a str>) -> Result<(), ValueError<'a>> {
+    let mut count = 0;
+
+    for (position, token) in tokens.enumerate() {
+        if position % 2 == 0 && token.is_empty() {
+            return Err(ValueError::Alternation(position))
+        }
+
+        count += 1;
+    }
+
+    if count % 2 != 0 {
+        return Err(ValueError::Alternation(count - 1))
+    }
+
+    Ok(())
+}
+
+/// Checks that no index appears more than once in the given tokens, which must alternate between
+/// indices and values
+pub fn check_duplicates<'a>(tokens: impl Iterator<Item = &'a str>) -> Result<(), ValueError<'a>> {
+    let mut seen = HashSet::new();
+
+    for (index, _) in tokens.self_zip() {
+        if !seen.insert(index) {
+            return Err(ValueError::DuplicateIndex(index))
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks whether the given tokens contain any data
+///
+/// ## Errors
+/// If all tokens are empty, or there are none, [`ValueError::EmptyResponse`] is returned
+pub fn check_empty<'a>(mut tokens: impl Iterator<Item = &'a str>) -> Result<(), ValueError<'a>> {
+    if tokens.all(str::is_empty) {
+        return Err(ValueError::EmptyResponse)
+    }
+
+    Ok(())
+}
+
+/// Removes [`ValueError::NoValue`] errors for indices that already failed to parse
+///
+/// When collecting errors, a value that failed to parse is missing afterwards, which would
+/// otherwise be reported a second time
+pub(crate) fn collapse_errors(errors: &mut Vec<ValueError>) {
+    let failed: Vec<&str> = errors
+        .iter()
+        .filter_map(|error| {
+            match error {
+                ValueError::Parse(idx, ..) => Some(*idx),
+                _ => None,
+            }
+        })
+        .collect();
+
+    errors.retain(|error| {
+        match error {
+            ValueError::NoValue(idx) => !failed.contains(idx),
+            _ => true,
+        }
+    })
+}
+
+/// Runs the given closure, recording its error instead of returning it
+pub(crate) fn collect_error<'a, T>(errors: &mut Vec<ValueError<'a>>, f: impl FnOnce() -> Result<T, ValueError<'a>>) -> Option<T> {
+    match f() {
+        Ok(value) => Some(value),
+        Err(err) => {
+            errors.push(err);
+            None
+        },
+    }
+}
+
 // FIXME: this is just fucking horrible
 pub(crate) fn unparse<T>(value: T) -> String
 where
@@ -0,0 +1,188 @@
+//! Module containing the limiter bounding how many requests are in flight at the same time
+
+use futures::{
+    task::{self, Task},
+    Async, Future, Poll,
+};
+use log::trace;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// Limits the amount of requests a client has in flight at the same time
+///
+/// Unlike the [`RateLimiter`](crate::ratelimit::RateLimiter), this doesn't refuse requests, it
+/// queues them until a slot becomes available. Clones share their state, so all clones of a
+/// [`BoomlingsClient`](crate::BoomlingsClient) count towards the same limit. Without a limit, this
+/// only counts the requests in flight.
+#[derive(Debug, Clone, Default)]
+pub struct ConcurrencyLimiter {
+    limit: Option<usize>,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    in_flight: usize,
+
+    /// The tasks waiting for a slot, keyed by the id of the [`Acquire`] future they are polling.
+    /// Each future only keeps the task that polled it last.
+    waiting: HashMap<usize, Task>,
+    next_id: usize,
+}
+
+impl ConcurrencyLimiter {
+    /// Creates a new limiter allowing at most `limit` requests in flight at the same time
+    ///
+    /// ## Panics
+    /// Panics if `limit` is zero, since no request could ever be made
+    pub fn new(limit: usize) -> ConcurrencyLimiter {
+        assert!(limit > 0, "A concurrency limit of zero would never allow any request");
+
+        ConcurrencyLimiter {
+            limit: Some(limit),
+            state: Arc::new(Mutex::new(State::default())),
+        }
+    }
+
+    /// The maximum amount of requests in flight at the same time, if any
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// The amount of requests currently in flight
+    ///
+    /// Requests that are queued waiting for a slot aren't counted.
+    pub fn in_flight(&self) -> usize {
+        self.state.lock().unwrap().in_flight
+    }
+
+    /// Returns a future resolving to a [`Permit`] once a slot is available
+    pub fn acquire(&self) -> Acquire {
+        let id = {
+            let mut state = self.state.lock().unwrap();
+
+            state.next_id = state.next_id.wrapping_add(1);
+            state.next_id
+        };
+
+        Acquire {
+            id,
+            limiter: self.clone(),
+        }
+    }
+}
+
+/// Future returned by [`ConcurrencyLimiter::acquire`]
+#[derive(Debug)]
+pub struct Acquire {
+    id: usize,
+    limiter: ConcurrencyLimiter,
+}
+
+impl Future for Acquire {
+    type Error = ();
+    type Item = Permit;
+
+    fn poll(&mut self) -> Poll<Permit, ()> {
+        let mut state = self.limiter.state.lock().unwrap();
+
+        match self.limiter.limit {
+            Some(limit) if state.in_flight >= limit => {
+                trace!("{} requests in flight, queueing request", state.in_flight);
+
+                state.waiting.insert(self.id, task::current());
+
+                Ok(Async::NotReady)
+            },
+            _ => {
+                state.in_flight += 1;
+                state.waiting.remove(&self.id);
+
+                Ok(Async::Ready(Permit {
+                    limiter: self.limiter.clone(),
+                }))
+            },
+        }
+    }
+}
+
+impl Drop for Acquire {
+    fn drop(&mut self) {
+        self.limiter.state.lock().unwrap().waiting.remove(&self.id);
+    }
+}
+
+/// A slot in a [`ConcurrencyLimiter`]. The slot is freed once this is dropped.
+#[derive(Debug)]
+pub struct Permit {
+    limiter: ConcurrencyLimiter,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut state = self.limiter.state.lock().unwrap();
+
+        state.in_flight -= 1;
+
+        // All waiting requests are woken up, since the one that was woken might not be polled again
+        // before another request takes the slot. The ones that don't get the slot simply queue up again.
+        for (_, waiting) in state.waiting.drain() {
+            waiting.notify()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConcurrencyLimiter;
+    use futures::{
+        executor::{self, Notify},
+        Async, Future,
+    };
+    use std::sync::Arc;
+
+    struct NoopNotify;
+
+    impl Notify for NoopNotify {
+        fn notify(&self, _: usize) {}
+    }
+
+    #[test]
+    fn repeated_polls_keep_one_waiting_task() {
+        let limiter = ConcurrencyLimiter::new(1);
+        let notify = Arc::new(NoopNotify);
+        let permit = limiter.acquire().wait().unwrap();
+        let mut acquire = executor::spawn(limiter.acquire());
+
+        for _ in 0..10 {
+            assert!(acquire.poll_future_notify(&notify, 0).unwrap().is_not_ready());
+        }
+
+        assert_eq!(limiter.state.lock().unwrap().waiting.len(), 1);
+
+        drop(permit);
+
+        match acquire.poll_future_notify(&notify, 0) {
+            Ok(Async::Ready(_)) => (),
+            other => panic!("expected the slot to be acquired, got {:?}", other),
+        }
+
+        assert!(limiter.state.lock().unwrap().waiting.is_empty());
+    }
+
+    #[test]
+    fn dropped_acquire_stops_waiting() {
+        let limiter = ConcurrencyLimiter::new(1);
+        let notify = Arc::new(NoopNotify);
+        let _permit = limiter.acquire().wait().unwrap();
+        let mut acquire = executor::spawn(limiter.acquire());
+
+        assert!(acquire.poll_future_notify(&notify, 0).unwrap().is_not_ready());
+
+        drop(acquire);
+
+        assert!(limiter.state.lock().unwrap().waiting.is_empty());
+    }
+}
@@ -1,41 +1,77 @@
-use failure_derive::Fail;
 use gdcf::error::ApiError as TApiError;
 use gdcf_parse::error::ValueError;
+use std::{
+    error::Error as StdError,
+    fmt::{Display, Formatter},
+    time::Duration,
+};
 use tokio_retry::Error as RetryError;
 
-#[derive(Fail, Debug)]
+#[derive(Debug)]
 pub enum ApiError {
     /// The API server returned a 500 INTERNAL SERVER ERROR response
-    #[fail(display = "Internal Server Error")]
     InternalServerError,
 
     /// The request resulted in no data
     ///
     /// This can either be a 404 response, or an otherwise empty response, like
     /// RobTop's `-1` responses
-    #[fail(display = "The request completed successfully, but no data was provided")]
     NoData,
 
     /// The response had an unexpected format
-    #[fail(display = "Parsing of the response failed")]
     UnexpectedFormat,
 
     /// The request data was malformed
     ///
     /// This variant is only intended to be used while constructing data from
     /// [RawObject](model/structs.RawObject.html)s
-    #[fail(
-        display = "Processing the response data failed at index {} due to value '{}': {}",
-        index, value, msg
-    )]
     MalformedData { index: String, value: String, msg: String },
 
-    #[fail(display = "Required data at index {} missing", _0)]
     MissingData(String),
 
+    /// The response body exceeded the maximum size configured for the client
+    ResponseTooLarge(usize),
+
+    /// The request wasn't made because its search string contains a character the servers can't
+    /// handle
+    InvalidSearchString(char),
+
+    /// The request wasn't made because the server previously told us to back off via rate-limit
+    /// headers. Contains the remaining time until requests will be made again
+    RateLimited(Duration),
+
     /// An error caused by the underlying api client implementation occured
-    #[fail(display = "An API client specific error occurate: {}", _0)]
-    Custom(#[cause] hyper::Error),
+    Custom(hyper::Error),
+}
+
+impl Display for ApiError {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            ApiError::InternalServerError => write!(f, "Internal Server Error"),
+            ApiError::NoData => write!(f, "The request completed successfully, but no data was provided"),
+            ApiError::UnexpectedFormat => write!(f, "Parsing of the response failed"),
+            ApiError::MalformedData { index, value, msg } =>
+                write!(
+                    f,
+                    "Processing the response data failed at index {} due to value '{}': {}",
+                    index, value, msg
+                ),
+            ApiError::MissingData(index) => write!(f, "Required data at index {} missing", index),
+            ApiError::ResponseTooLarge(limit) => write!(f, "The response exceeded the maximum size of {} bytes", limit),
+            ApiError::InvalidSearchString(c) => write!(f, "The search string contains the unsupported character {:?}", c),
+            ApiError::RateLimited(remaining) => write!(f, "Rate limited for another {:?}", remaining),
+            ApiError::Custom(err) => write!(f, "An API client specific error occurate: {}", err),
+        }
+    }
+}
+
+impl StdError for ApiError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            ApiError::Custom(err) => Some(err),
+            _ => None,
+        }
+    }
 }
 
 impl<'a> From<ValueError<'a>> for ApiError {
@@ -48,6 +84,8 @@ impl<'a> From<ValueError<'a>> for ApiError {
                     value: value.to_owned(),
                     msg: err,
                 },
+            ValueError::EmptyResponse => ApiError::NoData,
+            ValueError::Alternation(_) | ValueError::TooManyObjects(_) | ValueError::DuplicateIndex(_) => ApiError::UnexpectedFormat,
         }
     }
 }
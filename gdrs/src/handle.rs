@@ -1,11 +1,11 @@
 use crate::{error::ApiError, Req};
 use gdcf::{
     api::{
-        client::Response,
+        client::{PageInfo, Response},
         request::{
             comment::{LevelCommentsRequest, ProfileCommentsRequest},
             user::UserSearchRequest,
-            LevelRequest, LevelsRequest, Request as GdcfRequest, UserRequest,
+            validate_search_string, LevelRequest, LevelsRequest, Request as GdcfRequest, SongRequest, UserRequest,
         },
     },
     Secondary,
@@ -16,7 +16,7 @@ use gdcf_model::{
     song::NewgroundsSong,
     user::{Creator, SearchedUser, User},
 };
-use gdcf_parse::Parse;
+use gdcf_parse::{level::ParseProfile, Parse};
 use log::{info, trace, warn};
 
 pub trait Handler: GdcfRequest {
@@ -24,6 +24,19 @@ pub trait Handler: GdcfRequest {
     fn handle(response_body: &str) -> Result<Response<Self::Result>, ApiError>;
 
     fn to_req(&self) -> Req;
+
+    /// Encodes this request into the exact `application/x-www-form-urlencoded` body sent to the
+    /// servers, e.g. `gameVersion=22&binaryVersion=37&secret=Wmfd2893gb7&targetAccountID=5` for a
+    /// [`UserRequest`]
+    fn encode(&self) -> String {
+        serde_urlencoded::to_string(self.to_req()).unwrap()
+    }
+
+    /// Checks whether this request can be sent to the servers. Requests failing this check fail
+    /// without being made
+    fn validate(&self) -> Result<(), ApiError> {
+        Ok(())
+    }
 }
 
 impl Handler for LevelRequest {
@@ -37,7 +50,13 @@ impl Handler for LevelRequest {
         let mut sections = response_body.split('#');
 
         match sections.next() {
-            Some(section) => Ok(Response::Exact(Level::parse_iter(section.split(':'))?)),
+            Some(section) => {
+                let level = Level::parse_iter(section.split(':'))?;
+
+                ParseProfile::Modern.check(&level.base);
+
+                Ok(Response::Exact(level))
+            },
             None => Err(ApiError::UnexpectedFormat),
         }
     }
@@ -69,6 +88,11 @@ impl Handler for LevelsRequest {
 
         info!("Found {} levels", levels.len());
 
+        // Our parsers are written for the modern format, so warn about levels they likely misread
+        for level in &levels {
+            ParseProfile::Modern.check(level);
+        }
+
         if let Some(section) = sections.next() {
             // No creators are fine with us
             if !section.is_empty() {
@@ -93,6 +117,9 @@ impl Handler for LevelsRequest {
 
         info!("Found {} songs", other.len() - creator_count);
 
+        // Page info is only metadata, so a missing or malformed section doesn't invalidate the levels
+        let page_info = sections.next().and_then(parse_page_info);
+
         for level in &levels {
             if other
                 .iter()
@@ -129,12 +156,36 @@ impl Handler for LevelsRequest {
             }
         }
 
-        Ok(Response::More(levels, other))
+        let response = Response::More(levels, other);
+
+        match page_info {
+            Some(page_info) => Ok(Response::Paged(Box::new(response), page_info)),
+            None => Ok(response),
+        }
     }
 
     fn to_req(&self) -> Req {
         Req::LevelsRequest(self)
     }
+
+    fn validate(&self) -> Result<(), ApiError> {
+        validate_search_string(&self.search_string).map_err(ApiError::InvalidSearchString)
+    }
+}
+
+/// Parses the `total:offset:page_size` section of paginated responses, logging a warning and
+/// returning [`None`] if it is malformed
+fn parse_page_info(section: &str) -> Option<PageInfo> {
+    let mut values = section.split(':').map(|value| value.parse::<u32>());
+
+    match (values.next(), values.next(), values.next()) {
+        (Some(Ok(total)), Some(Ok(offset)), Some(Ok(page_size))) => Some(PageInfo { total, offset, page_size }),
+        _ => {
+            warn!("Malformed page info section '{}', ignoring it", section);
+
+            None
+        },
+    }
 }
 
 impl Handler for UserRequest {
@@ -172,6 +223,10 @@ impl Handler for UserSearchRequest {
     fn to_req(&self) -> Req {
         Req::UserSearchRequest(self)
     }
+
+    fn validate(&self) -> Result<(), ApiError> {
+        validate_search_string(&self.search_string).map_err(ApiError::InvalidSearchString)
+    }
 }
 
 impl Handler for LevelCommentsRequest {
@@ -212,7 +267,7 @@ impl Handler for LevelCommentsRequest {
                             is_flagged_spam: comment.is_flagged_spam,
                             time_since_post: comment.time_since_post,
                             progress: comment.progress,
-                            is_elder_mod: comment.is_elder_mod,
+                            mod_level: comment.mod_level,
                             special_color: comment.special_color,
                         })
                     } else {
@@ -263,3 +318,210 @@ impl Handler for ProfileCommentsRequest {
         Req::ProfileCommentsRequest(self)
     }
 }
+
+impl Handler for SongRequest {
+    fn endpoint() -> &'static str {
+        endpoint!("getGJSongInfo")
+    }
+
+    fn handle(response_body: &str) -> Result<Response<Self::Result>, ApiError> {
+        check_resp!(response_body);
+
+        // `-2` means the song exists, but isn't allowed to be used in Geometry Dash
+        if response_body.trim() == "-2" {
+            return Err(ApiError::NoData)
+        }
+
+        Ok(Response::Exact(NewgroundsSong::parse_str2(response_body.trim_end(), "~|~")?))
+    }
+
+    fn to_req(&self) -> Req {
+        Req::SongRequest(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Handler;
+    use crate::error::ApiError;
+    use gdcf::api::{
+        client::{PageInfo, Response},
+        request::{
+            comment::{LevelCommentsRequest, ProfileCommentsRequest},
+            user::UserSearchRequest,
+            LevelRequest, LevelsRequest, SongRequest, UserRequest,
+        },
+    };
+    use gdcf_model::level::PartialLevel;
+
+    type LevelsResponse = Response<Vec<PartialLevel<Option<u64>, u64>>>;
+
+    const LEVEL: &str = "1:10565740:2:Bloodbath:5:3:6:503085:8:10:9:50:10:31279016:12:0:13:21:14:1549098:15:3:17:1:18:10:19:10330:\
+                         25::30:7679228:31:0:35:467339:37:0:38:0:39:10:42:0:43:0:45:24746";
+    const CREATOR: &str = "503085:Riot:37415";
+    const SONG: &str = "1~|~467339~|~2~|~At the Speed of Light~|~3~|~1~|~4~|~Dimrain47~|~5~|~9.56~|~6~|~~|~8~|~1~|~10~|~\
+                        http%3A%2F%2Faudio.ngfiles.com";
+
+    fn handle(page_info: Option<&str>) -> LevelsResponse {
+        let mut body = format!("{}#{}#{}", LEVEL, CREATOR, SONG);
+
+        if let Some(page_info) = page_info {
+            body.push('#');
+            body.push_str(page_info);
+        }
+
+        LevelsRequest::handle(&body).unwrap()
+    }
+
+    fn assert_levels_parsed(response: &LevelsResponse) {
+        match response {
+            Response::More(levels, secondaries) => {
+                assert_eq!(levels.len(), 1);
+                assert_eq!(levels[0].level_id, 10_565_740);
+                assert_eq!(secondaries.len(), 2);
+            },
+            other => panic!("Expected levels without page info, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn levels_response_with_page_info() {
+        match handle(Some("9999:0:10")) {
+            Response::Paged(inner, page_info) => {
+                assert_eq!(page_info, PageInfo {
+                    total: 9999,
+                    offset: 0,
+                    page_size: 10
+                });
+                assert_levels_parsed(&inner);
+            },
+            other => panic!("Expected paged response, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn levels_response_without_page_info_section() {
+        assert_levels_parsed(&handle(None));
+    }
+
+    #[test]
+    fn levels_response_with_empty_page_info_section() {
+        assert_levels_parsed(&handle(Some("")));
+    }
+
+    #[test]
+    fn levels_response_with_garbage_page_info_section() {
+        assert_levels_parsed(&handle(Some("lots:of:garbage")));
+    }
+
+    #[test]
+    fn levels_response_with_legacy_level() {
+        // Legacy levels are only warned about, not rejected
+        let body = format!("{}#{}#{}", LEVEL.replace(":13:21:", ":13:18:"), CREATOR, SONG);
+
+        assert_levels_parsed(&LevelsRequest::handle(&body).unwrap());
+    }
+
+    #[test]
+    fn non_existing_user_response() {
+        for body in &["-1", "-1\n"] {
+            match UserRequest::handle(body) {
+                Err(ApiError::NoData) => (),
+                other => panic!("Expected no data for body {:?}, got {:?}", body, other),
+            }
+        }
+    }
+
+    #[test]
+    fn song_info_response() {
+        let body = "1~|~467339~|~2~|~At the Speed of Light~|~3~|~1~|~4~|~Dimrain47~|~5~|~9.56~|~6~|~~|~10~|~\
+                    http%3A%2F%2Faudio.ngfiles.com%2F467000%2F467339_At-the-Speed-of-Light-FINAL.mp3~|~7~|~~|~8~|~1\n";
+
+        match SongRequest::handle(body).unwrap() {
+            Response::Exact(song) => {
+                assert_eq!(song.song_id, 467_339);
+                assert_eq!(song.name, "At the Speed of Light");
+                assert_eq!(song.artist_id, 1);
+                assert_eq!(song.artist, "Dimrain47");
+                assert_eq!(song.filesize, 9.56);
+                assert_eq!(song.link, "http://audio.ngfiles.com/467000/467339_At-the-Speed-of-Light-FINAL.mp3");
+            },
+            other => panic!("Expected exact response, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn disallowed_song_response() {
+        match SongRequest::handle("-2") {
+            Err(ApiError::NoData) => (),
+            other => panic!("Expected no data, got {:?}", other),
+        }
+    }
+
+    const BASE: &str = "gameVersion=22&binaryVersion=37&secret=Wmfd2893gb7";
+
+    /// Checks that the given request encodes to the base parameters followed by `params`, and that
+    /// decoding the body yields exactly the encoded values again
+    fn assert_encodes_to<R: Handler>(request: &R, params: &[(&str, &str)]) {
+        let body = request.encode();
+        let decoded: Vec<(String, String)> = serde_urlencoded::from_str(&body).unwrap();
+        let mut expected: Vec<(String, String)> = serde_urlencoded::from_str(BASE).unwrap();
+        expected.extend(params.iter().map(|&(key, value)| (key.to_string(), value.to_string())));
+
+        assert_eq!(decoded, expected, "unexpected body {}", body);
+        assert_eq!(serde_urlencoded::to_string(&decoded).unwrap(), body);
+    }
+
+    #[test]
+    fn user_request_encoding() {
+        assert_eq!(UserRequest::new(5).encode(), format!("{}&targetAccountID=5", BASE));
+        assert_encodes_to(&UserRequest::new(5), &[("targetAccountID", "5")]);
+    }
+
+    #[test]
+    fn level_request_encoding() {
+        assert_encodes_to(&LevelRequest::new(10_565_740), &[("levelID", "10565740"), ("inc", "1"), ("extras", "0")]);
+    }
+
+    #[test]
+    fn levels_request_encoding() {
+        assert_encodes_to(&LevelsRequest::default().search("Blood bath&co".to_string()).page(2), &[
+            ("type", "0"),
+            ("str", "Blood bath&co"),
+            ("len", "-"),
+            ("diff", "-"),
+            ("page", "2"),
+            ("total", "0"),
+            ("uncompleted", "0"),
+            ("onlyCompleted", "0"),
+            ("featured", "0"),
+            ("original", "0"),
+            ("twoPlayer", "0"),
+            ("coins", "0"),
+            ("epic", "0"),
+            ("star", "0"),
+        ]);
+    }
+
+    #[test]
+    fn user_search_request_encoding() {
+        assert_encodes_to(&UserSearchRequest::new("Riot".to_string()), &[("total", "0"), ("page", "0"), ("str", "Riot")]);
+    }
+
+    #[test]
+    fn comment_request_encoding() {
+        assert_encodes_to(&LevelCommentsRequest::new(10_565_740), &[
+            ("total", "0"),
+            ("page", "0"),
+            ("mode", "0"),
+            ("levelID", "10565740"),
+            ("count", "20"),
+        ]);
+        assert_encodes_to(&ProfileCommentsRequest::new(5), &[("total", "0"), ("page", "0"), ("accountID", "5")]);
+    }
+
+    #[test]
+    fn song_request_encoding() {
+        assert_encodes_to(&SongRequest::new(467_339), &[("songID", "467339")]);
+    }
+}
@@ -11,14 +11,18 @@
 )]
 
 use crate::{
+    concurrency::{Acquire, ConcurrencyLimiter, Permit},
     error::ApiError,
     handle::Handler,
-    ser::{LevelCommentsRequestRem, LevelRequestRem, LevelsRequestRem, ProfileCommentsRequestRem, UserRequestRem, UserSearchRequestRem},
+    ratelimit::{RateLimitHeaders, RateLimiter},
+    ser::{
+        LevelCommentsRequestRem, LevelRequestRem, LevelsRequestRem, ProfileCommentsRequestRem, SongRequestRem, UserRequestRem,
+        UserSearchRequestRem,
+    },
 };
 use failure::_core::marker::PhantomData;
 use futures::{
     future::{Executor, FromErr},
-    stream::Concat2,
     Async, Future, Stream,
 };
 use gdcf::api::{
@@ -26,6 +30,7 @@ use gdcf::api::{
     request::{
         comment::{LevelCommentsRequest, ProfileCommentsRequest},
         level::{LevelRequest, LevelsRequest},
+        song::SongRequest,
         user::{UserRequest, UserSearchRequest},
         Request as GdcfRequest,
     },
@@ -33,18 +38,21 @@ use gdcf::api::{
 };
 use hyper::{
     client::{Builder, HttpConnector, ResponseFuture},
-    header::HeaderValue,
+    header::{HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH},
     Body, Client, Method, Request, StatusCode,
 };
 use log::{debug, error, info, trace, warn};
 use serde_derive::Serialize;
-use std::{iter::Take, mem, str};
+use flate2::read::GzDecoder;
+use std::{io::Read, iter::Take, mem, time::Duration};
 use tokio_retry::{strategy::ExponentialBackoff, Action, Condition, RetryIf};
 
 #[macro_use]
 mod macros;
+pub mod concurrency;
 pub mod error;
 pub mod handle;
+pub mod ratelimit;
 mod ser;
 
 #[derive(Serialize, Debug)]
@@ -67,11 +75,32 @@ pub enum Req<'a> {
 
     #[serde(with = "ProfileCommentsRequestRem")]
     ProfileCommentsRequest(&'a ProfileCommentsRequest),
+
+    #[serde(with = "SongRequestRem")]
+    SongRequest(&'a SongRequest),
 }
 
-#[derive(Debug, Default, Clone)]
+/// The maximum size of a response body (after decompression) the [`BoomlingsClient`] accepts by
+/// default, in bytes
+pub const DEFAULT_MAX_RESPONSE_SIZE: usize = 32 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
 pub struct BoomlingsClient {
     client: Client<HttpConnector>,
+    max_response_size: usize,
+    rate_limiter: RateLimiter,
+    concurrency_limiter: ConcurrencyLimiter,
+}
+
+impl Default for BoomlingsClient {
+    fn default() -> Self {
+        BoomlingsClient {
+            client: Client::default(),
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+            rate_limiter: RateLimiter::default(),
+            concurrency_limiter: ConcurrencyLimiter::default(),
+        }
+    }
 }
 
 #[allow(missing_debug_implementations)]
@@ -92,7 +121,12 @@ impl BoomlingsClient {
     pub fn new() -> BoomlingsClient {
         info!("Creating new BoomlingsApiClient");
 
-        BoomlingsClient { client: Client::new() }
+        BoomlingsClient {
+            client: Client::new(),
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+            rate_limiter: RateLimiter::default(),
+            concurrency_limiter: ConcurrencyLimiter::default(),
+        }
     }
 
     pub fn with_exec<E>(exec: E) -> Self
@@ -101,7 +135,51 @@ impl BoomlingsClient {
     {
         let client = Builder::default().executor(exec).build_http();
 
-        BoomlingsClient { client }
+        BoomlingsClient {
+            client,
+            max_response_size: DEFAULT_MAX_RESPONSE_SIZE,
+            rate_limiter: RateLimiter::default(),
+            concurrency_limiter: ConcurrencyLimiter::default(),
+        }
+    }
+
+    /// Sets the maximum size of response bodies, in bytes
+    ///
+    /// Requests whose response exceeds this limit fail with [`ApiError::ResponseTooLarge`]. This is
+    /// mainly useful when talking to servers that aren't trusted, like private servers.
+    pub fn max_response_size(mut self, max_response_size: usize) -> Self {
+        self.max_response_size = max_response_size;
+        self
+    }
+
+    /// Sets the names of the response headers the client's rate limiter inspects
+    ///
+    /// Once a response indicates that we should back off, further requests fail with
+    /// [`ApiError::RateLimited`] until the window passes. This resets the limiter's state.
+    pub fn rate_limit_headers(mut self, headers: RateLimitHeaders) -> Self {
+        self.rate_limiter = RateLimiter::new(headers);
+        self
+    }
+
+    /// Sets the maximum amount of requests this client (and all its clones) have in flight at the
+    /// same time
+    ///
+    /// Further requests are queued until a previous one completes. This resets the count of
+    /// requests in flight, so it should be called before any requests are made.
+    ///
+    /// ## Panics
+    /// Panics if `limit` is zero
+    pub fn max_concurrent_requests(mut self, limit: usize) -> Self {
+        self.concurrency_limiter = ConcurrencyLimiter::new(limit);
+        self
+    }
+
+    /// The amount of requests this client (and all its clones) currently have in flight
+    ///
+    /// Requests queued because of the limit set via
+    /// [`max_concurrent_requests`](BoomlingsClient::max_concurrent_requests) aren't counted.
+    pub fn in_flight(&self) -> usize {
+        self.concurrency_limiter.in_flight()
     }
 }
 
@@ -118,7 +196,11 @@ impl<R: Handler> MakeRequest<R> for BoomlingsClient {
                 ExponentialBackoff::from_millis(10).take(5),
                 ApiRequestAction {
                     client: self.client.clone(),
-                    encoded_request: serde_urlencoded::to_string(request.to_req()).unwrap(),
+                    max_response_size: self.max_response_size,
+                    rate_limiter: self.rate_limiter.clone(),
+                    concurrency_limiter: self.concurrency_limiter.clone(),
+                    validation_error: request.validate().err(),
+                    encoded_request: request.encode(),
                     phantom: PhantomData,
                 },
                 ApiRetryCondition,
@@ -130,6 +212,10 @@ impl<R: Handler> MakeRequest<R> for BoomlingsClient {
 
 struct ApiRequestAction<R: Handler> {
     client: Client<HttpConnector>,
+    max_response_size: usize,
+    rate_limiter: RateLimiter,
+    concurrency_limiter: ConcurrencyLimiter,
+    validation_error: Option<ApiError>,
     encoded_request: String,
     phantom: PhantomData<R>,
 }
@@ -149,8 +235,23 @@ impl Condition<ApiError> for ApiRetryCondition {
 }
 
 enum ProcessRequestFuture<R: Handler> {
-    WaitingForResponse(ResponseFuture, PhantomData<R>),
-    ProcessingResponse(Concat2<Body>),
+    Invalid(Option<ApiError>),
+    RateLimited(Duration),
+    Queued {
+        acquire: Acquire,
+        client: Client<HttpConnector>,
+        request: Option<Request<Body>>,
+        max_size: usize,
+        rate_limiter: RateLimiter,
+    },
+    WaitingForResponse(ResponseFuture, usize, RateLimiter, Option<Permit>, PhantomData<R>),
+    ProcessingResponse {
+        permit: Option<Permit>,
+        body: Body,
+        buffer: Vec<u8>,
+        gzipped: bool,
+        max_size: usize,
+    },
 }
 
 impl<R: Handler> Future for ProcessRequestFuture<R> {
@@ -158,60 +259,172 @@ impl<R: Handler> Future for ProcessRequestFuture<R> {
     type Item = Response<R::Result>;
 
     fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
-        let response_poll_result = match self {
-            ProcessRequestFuture::WaitingForResponse(response_future, _) =>
-                match response_future.poll() {
-                    Ok(Async::NotReady) => return Ok(Async::NotReady),
-                    Err(err) => {
-                        error!("Error making request: {:?}", err);
+        if let ProcessRequestFuture::Invalid(error) = self {
+            return Err(error.take().expect("Future already polled to completion"))
+        }
 
-                        return Err(ApiError::Custom(err))
-                    },
-                    Ok(Async::Ready(response)) => {
-                        debug!("Received {} response", response.status());
+        if let ProcessRequestFuture::RateLimited(remaining) = self {
+            return Err(ApiError::RateLimited(*remaining))
+        }
+
+        if let ProcessRequestFuture::Queued {
+            acquire,
+            client,
+            request,
+            max_size,
+            rate_limiter,
+        } = self
+        {
+            let permit = match acquire.poll() {
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Ok(Async::Ready(permit)) => permit,
+                Err(()) => unreachable!(),
+            };
+
+            let request = request.take().expect("Future already polled to completion");
+
+            *self = ProcessRequestFuture::WaitingForResponse(
+                client.request(request),
+                *max_size,
+                rate_limiter.clone(),
+                Some(permit),
+                PhantomData,
+            );
+        }
+
+        if let ProcessRequestFuture::WaitingForResponse(response_future, max_size, rate_limiter, permit, _) = self {
+            match response_future.poll() {
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Err(err) => {
+                    error!("Error making request: {:?}", err);
+
+                    return Err(ApiError::Custom(err))
+                },
+                Ok(Async::Ready(response)) => {
+                    debug!("Received {} response", response.status());
+
+                    rate_limiter.update(response.headers());
 
-                        match response.status() {
-                            StatusCode::INTERNAL_SERVER_ERROR => return Err(ApiError::InternalServerError),
-                            StatusCode::NOT_FOUND => return Err(ApiError::NoData),
-                            _ => (),
+                    match response.status() {
+                        StatusCode::INTERNAL_SERVER_ERROR => return Err(ApiError::InternalServerError),
+                        StatusCode::NOT_FOUND => return Err(ApiError::NoData),
+                        _ => (),
+                    }
+
+                    let content_length = response
+                        .headers()
+                        .get(CONTENT_LENGTH)
+                        .and_then(|length| length.to_str().ok())
+                        .and_then(|length| length.parse::<usize>().ok());
+
+                    if let Some(content_length) = content_length {
+                        if content_length > *max_size {
+                            error!("Response announced a length of {} bytes, which exceeds the limit", content_length);
+
+                            return Err(ApiError::ResponseTooLarge(*max_size))
                         }
+                    }
+
+                    let gzipped = response
+                        .headers()
+                        .get(CONTENT_ENCODING)
+                        .map(|encoding| encoding.as_bytes().eq_ignore_ascii_case(b"gzip"))
+                        .unwrap_or(false);
+
+                    *self = ProcessRequestFuture::ProcessingResponse {
+                        permit: permit.take(),
+                        body: response.into_body(),
+                        buffer: Vec::new(),
+                        gzipped,
+                        max_size: *max_size,
+                    };
+                },
+            }
+        }
 
-                        let mut body = response.into_body().concat2();
-                        let poll_result = body.poll();
-                        mem::replace(self, ProcessRequestFuture::ProcessingResponse(body));
-                        poll_result
-                    },
+        let (buffer, gzipped, max_size) = match self {
+            ProcessRequestFuture::ProcessingResponse {
+                permit,
+                body,
+                buffer,
+                gzipped,
+                max_size,
+            } =>
+                loop {
+                    match body.poll() {
+                        Ok(Async::NotReady) => return Ok(Async::NotReady),
+                        Err(err) => {
+                            error!("Error reading/processing request response {:?}", err);
+
+                            return Err(ApiError::Custom(err))
+                        },
+                        Ok(Async::Ready(Some(chunk))) => {
+                            // The body is read chunk by chunk so that we can bail out before buffering
+                            // an unbounded amount of data
+                            if buffer.len() + chunk.len() > *max_size {
+                                error!("Response exceeded the maximum size of {} bytes", max_size);
+
+                                return Err(ApiError::ResponseTooLarge(*max_size))
+                            }
+
+                            buffer.extend_from_slice(&chunk)
+                        },
+                        Ok(Async::Ready(None)) => {
+                            // The request is done as far as the server is concerned, so let the next one go ahead
+                            // while we process this one
+                            permit.take();
+
+                            break (mem::replace(buffer, Vec::new()), *gzipped, *max_size)
+                        },
+                    }
                 },
-            ProcessRequestFuture::ProcessingResponse(body) => body.poll(),
+            _ => unreachable!(),
         };
 
-        match response_poll_result {
-            Ok(Async::NotReady) => Ok(Async::NotReady),
-            Err(err) => {
-                error!("Error reading/processing request response {:?}", err);
+        // This only undoes the transport-level compression. Fields like the level data are
+        // compressed on their own and are left untouched here.
+        let buffer = if gzipped {
+            let mut decompressed = Vec::new();
 
-                return Err(ApiError::Custom(err))
-            },
-            Ok(Async::Ready(chunk)) =>
-                match str::from_utf8(&chunk) {
-                    Ok(body) => {
-                        trace!("Received response {}", body);
-
-                        match R::handle(&body) {
-                            Err(err) => {
-                                error!("Error processing body: {:?}", err);
-
-                                Err(err)
-                            },
-                            Ok(object) => Ok(Async::Ready(object)),
-                        }
-                    },
+            // Limit the decompressed size as well, otherwise a tiny response could still blow up
+            if let Err(err) = GzDecoder::new(&buffer[..])
+                .take(max_size as u64 + 1)
+                .read_to_end(&mut decompressed)
+            {
+                error!("Error decompressing gzip encoded response: {:?}", err);
+
+                return Err(ApiError::UnexpectedFormat)
+            }
+
+            if decompressed.len() > max_size {
+                error!("Decompressed response exceeded the maximum size of {} bytes", max_size);
+
+                return Err(ApiError::ResponseTooLarge(max_size))
+            }
+
+            decompressed
+        } else {
+            buffer
+        };
+
+        match String::from_utf8(buffer) {
+            Ok(body) => {
+                trace!("Received response {}", body);
+
+                match R::handle(&body) {
                     Err(err) => {
-                        error!("Encoding error in response! {:?}", err);
+                        error!("Error processing body: {:?}", err);
 
-                        Err(ApiError::UnexpectedFormat)
+                        Err(err)
                     },
-                },
+                    Ok(object) => Ok(Async::Ready(Response::Raw(Box::new(object), body))),
+                }
+            },
+            Err(err) => {
+                error!("Encoding error in response! {:?}", err);
+
+                Err(ApiError::UnexpectedFormat)
+            },
         }
     }
 }
@@ -222,7 +435,26 @@ impl<R: GdcfRequest + Handler> Action for ApiRequestAction<R> {
     type Item = Response<R::Result>;
 
     fn run(&mut self) -> Self::Future {
-        ProcessRequestFuture::WaitingForResponse(self.client.request(make_request::<R>(&self.encoded_request)), PhantomData)
+        // Invalid requests are never retried, so this is only ever hit on the first attempt
+        if let Some(error) = self.validation_error.take() {
+            error!("Not making invalid request to {}: {}", R::endpoint(), error);
+
+            return ProcessRequestFuture::Invalid(Some(error))
+        }
+
+        if let Err(remaining) = self.rate_limiter.check() {
+            warn!("Not making request to {}, rate limited for another {:?}", R::endpoint(), remaining);
+
+            return ProcessRequestFuture::RateLimited(remaining)
+        }
+
+        ProcessRequestFuture::Queued {
+            acquire: self.concurrency_limiter.acquire(),
+            client: self.client.clone(),
+            request: Some(make_request::<R>(&self.encoded_request)),
+            max_size: self.max_response_size,
+            rate_limiter: self.rate_limiter.clone(),
+        }
     }
 }
 
@@ -239,6 +471,7 @@ fn make_request<R: GdcfRequest + Handler>(encoded_request: &str) -> Request<Body
         .insert("Content-Type", HeaderValue::from_str("application/x-www-form-urlencoded").unwrap());
     req.headers_mut()
         .insert("Content-Length", HeaderValue::from_str(&len.to_string()).unwrap());
+    req.headers_mut().insert(ACCEPT_ENCODING, HeaderValue::from_static("gzip"));
 
     req
 }
@@ -6,7 +6,8 @@ macro_rules! endpoint {
 
 macro_rules! check_resp {
     ($data:expr) => {{
-        if $data == "-1" {
+        // Some endpoints terminate their response with a newline
+        if $data.trim() == "-1" {
             return Err(ApiError::NoData)
         }
     }};
@@ -0,0 +1,207 @@
+//! Module containing the client-side rate limiter driven by rate-limit response headers
+
+use hyper::HeaderMap;
+use log::{debug, warn};
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// Reset header values larger than this many seconds are considered unix timestamps instead of
+/// relative durations. No sane rate-limit window is longer than a day, while any timestamp is
+/// magnitudes larger.
+const MAX_RELATIVE_RESET: u64 = 24 * 60 * 60;
+
+/// The longest the limiter ever blocks requests for, no matter what the server tells us
+pub const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+/// The names of the response headers the [`RateLimiter`] inspects
+///
+/// The boomlings servers currently don't send any of these, but some private servers do. Since
+/// there is no standard, all names can be changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitHeaders {
+    /// Header containing the number of requests remaining in the current window
+    pub remaining: String,
+
+    /// Header containing either the number of seconds until the current window resets, or the
+    /// unix timestamp at which it resets
+    pub reset: String,
+
+    /// Header containing the number of seconds to wait before making the next request
+    pub retry_after: String,
+}
+
+impl Default for RateLimitHeaders {
+    fn default() -> Self {
+        RateLimitHeaders {
+            remaining: "X-RateLimit-Remaining".to_string(),
+            reset: "X-RateLimit-Reset".to_string(),
+            retry_after: "Retry-After".to_string(),
+        }
+    }
+}
+
+/// Client-side rate limiter that refuses requests while the server told us to back off
+///
+/// Clones share their state, so all clones of a [`BoomlingsClient`](crate::BoomlingsClient) are
+/// limited together. If the server never sends any rate-limit headers, this never limits anything.
+#[derive(Debug, Clone, Default)]
+pub struct RateLimiter {
+    headers: RateLimitHeaders,
+    blocked_until: Arc<Mutex<Option<Instant>>>,
+}
+
+impl RateLimiter {
+    pub fn new(headers: RateLimitHeaders) -> RateLimiter {
+        RateLimiter {
+            headers,
+            blocked_until: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Checks whether a request may be made right now, returning the remaining time until the
+    /// current window ends if not
+    pub fn check(&self) -> Result<(), Duration> {
+        let mut blocked_until = self.blocked_until.lock().unwrap();
+
+        match *blocked_until {
+            Some(until) => {
+                let now = Instant::now();
+
+                if until > now {
+                    return Err(until - now)
+                }
+
+                *blocked_until = None;
+
+                Ok(())
+            },
+            None => Ok(()),
+        }
+    }
+
+    /// Adjusts the limiter's window based on the rate-limit headers of the given response
+    ///
+    /// A `Retry-After` style header always blocks for the given amount of seconds. The
+    /// remaining/reset pair only blocks once no requests remain in the current window. The reset
+    /// header may either be relative or a unix timestamp, which are told apart by their magnitude.
+    ///
+    /// Requests are never blocked for longer than [`MAX_BACKOFF`].
+    pub fn update(&self, headers: &HeaderMap) {
+        let seconds = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+        };
+
+        let retry_after = seconds(&self.headers.retry_after);
+        let reset = match seconds(&self.headers.remaining) {
+            Some(0) => seconds(&self.headers.reset).map(relative_reset),
+            _ => None,
+        };
+
+        let wait = match (retry_after, reset) {
+            (None, None) => return,
+            (a, b) => Duration::from_secs(a.unwrap_or(0).max(b.unwrap_or(0))),
+        };
+
+        if wait > MAX_BACKOFF {
+            warn!("Server requested us to back off for {:?}, capping to {:?}", wait, MAX_BACKOFF);
+        }
+
+        let wait = wait.min(MAX_BACKOFF);
+
+        warn!("Server requested us to back off for {:?}", wait);
+
+        let until = Instant::now() + wait;
+        let mut blocked_until = self.blocked_until.lock().unwrap();
+
+        // Never shorten a window a concurrent response already extended
+        match *blocked_until {
+            Some(current) if current >= until => debug!("Rate limit window already extends past {:?}", until),
+            _ => *blocked_until = Some(until),
+        }
+    }
+}
+
+/// Converts the value of a reset header into the number of seconds until the reset
+fn relative_reset(reset: u64) -> u64 {
+    if reset <= MAX_RELATIVE_RESET {
+        return reset
+    }
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|since| since.as_secs()).unwrap_or(0);
+
+    reset.saturating_sub(now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RateLimiter, MAX_BACKOFF};
+    use hyper::{header::HeaderValue, HeaderMap};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn exhausted(reset: u64) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        headers.insert("X-RateLimit-Remaining", HeaderValue::from_static("0"));
+        headers.insert("X-RateLimit-Reset", HeaderValue::from_str(&reset.to_string()).unwrap());
+        headers
+    }
+
+    fn now() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+    }
+
+    #[test]
+    fn relative_reset_header() {
+        let limiter = RateLimiter::default();
+
+        limiter.update(&exhausted(30));
+
+        let remaining = limiter.check().unwrap_err();
+
+        assert!(remaining <= Duration::from_secs(30));
+        assert!(remaining > Duration::from_secs(25));
+    }
+
+    #[test]
+    fn absolute_reset_header() {
+        let limiter = RateLimiter::default();
+
+        limiter.update(&exhausted(now() + 30));
+
+        let remaining = limiter.check().unwrap_err();
+
+        assert!(remaining <= Duration::from_secs(31));
+        assert!(remaining > Duration::from_secs(25));
+    }
+
+    #[test]
+    fn absolute_reset_header_in_the_past() {
+        let limiter = RateLimiter::default();
+
+        limiter.update(&exhausted(now() - 30));
+
+        assert_eq!(limiter.check(), Ok(()));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let limiter = RateLimiter::default();
+        let mut headers = HeaderMap::new();
+
+        headers.insert("Retry-After", HeaderValue::from_static("1000000"));
+        limiter.update(&headers);
+
+        assert!(limiter.check().unwrap_err() <= MAX_BACKOFF);
+
+        let limiter = RateLimiter::default();
+
+        limiter.update(&exhausted(now() + 1_000_000));
+
+        assert!(limiter.check().unwrap_err() <= MAX_BACKOFF);
+    }
+}
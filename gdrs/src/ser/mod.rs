@@ -1,8 +1,8 @@
 pub use self::request::{
     comment::{LevelCommentsRequestRem, ProfileCommentsRequestRem},
     level::{LevelRequestRem, LevelsRequestRem},
+    song::SongRequestRem,
     user::{UserRequestRem, UserSearchRequestRem},
-    BaseRequestRem,
 };
 use gdcf::api::request::{
     comment::SortMode,
@@ -5,6 +5,7 @@ use serde_derive::Serialize;
 
 pub(super) mod comment;
 pub(super) mod level;
+pub(super) mod song;
 pub(super) mod user;
 
 #[derive(Serialize)]
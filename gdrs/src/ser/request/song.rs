@@ -0,0 +1,13 @@
+use super::BaseRequestRem;
+use gdcf::api::request::{song::SongRequest, BaseRequest};
+use serde_derive::Serialize;
+
+#[derive(Serialize)]
+#[serde(remote = "SongRequest")]
+pub struct SongRequestRem {
+    #[serde(flatten, with = "BaseRequestRem")]
+    base: BaseRequest,
+
+    #[serde(rename = "songID")]
+    song_id: u64,
+}